@@ -18,6 +18,7 @@ import ToolBar from "./ToolBar";
 import Sidebar from "./Sidebar";
 import SidebarRisizer from "./SidebarResizer";
 import { useBookmarkState } from "../stores/bookmarks";
+import { useFaviconState } from "../stores/favicons";
 
 const Dashboard = lazy(() => import("./pages/Dashboard"));
 const SettingsPage = lazy(() => import("./pages/SettingsPage"));
@@ -29,6 +30,7 @@ const App: Component = () => {
   const useUrl = useUrlState();
   const useWindow = useWindowState();
   const useBookmark = useBookmarkState();
+  const useFavicons = useFaviconState();
 
   let unlistenSettingsUpdated: UnlistenFn;
   let unlistenExternalNavigation: UnlistenFn;
@@ -41,7 +43,9 @@ const App: Component = () => {
     await useWindow().setupWindowGeometry();
     await useUserSettings().get();
     await useAppSettings().get();
+    await useFavicons().setup();
     await useBookmark().getBookmarks(1);
+    useFavicons().prefetchFavicons().catch(() => {});
 
     // listen for settings updated events on rust side
     unlistenSettingsUpdated = await listen<string>(AppEvent.SettingsUpdated, (event) => {
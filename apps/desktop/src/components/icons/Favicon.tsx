@@ -1,5 +1,5 @@
-import type { Component } from "solid-js";
-import { FAVICON_SERVER } from "../../constants";
+import { Show, type Component } from "solid-js";
+import { faviconUrl, useFaviconState } from "../../stores/favicons";
 
 type Props = {
   url: string;
@@ -8,13 +8,27 @@ type Props = {
 };
 
 const Favicon: Component<Props> = (props) => {
+  const ready = useFaviconState((state) => state.ready);
+  const server = useFaviconState((state) => state.server);
+  // the server rounds the size up to 16, 32, 64 or 128
+  const size = () => Number.parseInt(String(props.width)) || 16;
+  // keep the space until it's known where favicons are served
+  const placeholder = () => ({
+    display: "inline-block",
+    width: `${size()}px`,
+    height: `${size()}px`,
+  });
+
   return (
-    <img
-      width={props.width}
-      height={props.height}
-      src={`${FAVICON_SERVER}/favicon?url=${props.url}`}
-      alt="favicon"
-    />
+    <Show when={ready()} fallback={<span style={placeholder()} />}>
+      <img
+        width={props.width}
+        height={props.height}
+        src={faviconUrl(server(), props.url, size())}
+        srcset={`${faviconUrl(server(), props.url, size() * 2)} 2x`}
+        alt="favicon"
+      />
+    </Show>
   );
 };
 
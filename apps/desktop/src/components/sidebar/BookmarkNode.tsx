@@ -46,7 +46,9 @@ const BookmarkNode: Component<Props> = (props) => {
   const isOpen = () => props.bookmark.is_open;
   const hasChildren = () => props.bookmark.children?.length > 0;
   const isFolder = () =>
-    props.bookmark.node_type === "Folder" || props.bookmark.node_type === "Root";
+    props.bookmark.node_type === "Folder" ||
+    props.bookmark.node_type === "Root" ||
+    props.bookmark.node_type === "SmartFolder";
   const isBookmark = () => props.bookmark.node_type === "Bookmark";
 
   // size, width, height
@@ -177,6 +179,7 @@ const BookmarkNode: Component<Props> = (props) => {
         class="dropzone flex flex-col hover:bg-sidebar-accent transition-colors duration-150 cursor-pointer"
         classList={{
           hasChildren: hasChildren(),
+          isContainer: isFolder(),
           isOpen: isOpen(),
           "bg-sidebar-accent": isActive(),
         }}
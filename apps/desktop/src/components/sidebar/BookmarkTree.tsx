@@ -58,7 +58,9 @@ const BookmarkTree: Component<Props> = (props) => {
           if (closest) {
             useDragging().setDestination(closest);
             const rect = closest.getBoundingClientRect();
-            const isInside = ev.clientY <= rect.top + rect.height / 2;
+            // bookmarks can't contain children, so only folders accept "inside"
+            const isContainer = closest.classList.contains("isContainer");
+            const isInside = isContainer && ev.clientY <= rect.top + rect.height / 2;
             if (isInside) {
               // inside the destination
               useDragging().setMode("inside");
@@ -9,8 +9,6 @@ export const FAVICON_SIZE = 16;
 export const RESIZE_HANDLE_WIDTH = 4;
 export const SELECT_BOX_WIDTH = 180;
 
-export const FAVICON_SERVER = "http://localhost:7853";
-
 export const ROUTES = {
   HOME: "/",
 } as const;
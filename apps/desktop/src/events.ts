@@ -5,4 +5,5 @@ export const AppEvent = {
   ExternalNavigation: "external://navigation",
   ExternalTitleChanged: "external://title-changed",
   ExternalUrlChanged: "external://url-changed",
+  ExternalPageRead: "external://page-read",
 };
@@ -7,8 +7,22 @@ import type {
   Rect,
   UserSettings,
   NestedBookmark,
+  SearchResultData,
+  BookmarkStats,
+  DomainGrouping,
+  DomainGroupData,
+  ReadingListItemData,
+  SmartQuery,
+  EncryptionStatus,
+  CollectionInfo,
+  CollectionList,
+  SyncConfig,
+  SyncReport,
+  SyncStatus,
   AppSettings,
   WindowGeometry,
+  FaviconServer,
+  PrefetchProgress,
 } from "./types";
 
 export const Invoke = {
@@ -83,6 +97,116 @@ export const Invoke = {
   ToggleIsOpen: async (index: number, topLevelIndex: number) => {
     return invoke<NestedBookmark>("plugin:bookmarks|toggle_is_open", { index, topLevelIndex });
   },
+  SearchBookmarks: async (query: string) => {
+    return invoke<SearchResultData[]>("plugin:bookmarks|search_bookmarks", { query });
+  },
+  SortByFrecency: async (index: number, topLevelIndex: number) => {
+    return invoke<NestedBookmark>("plugin:bookmarks|sort_by_frecency", { index, topLevelIndex });
+  },
+  GetBookmarkStats: async () => {
+    return invoke<BookmarkStats>("plugin:bookmarks|get_bookmark_stats");
+  },
+  GroupBookmarksByDomain: async (index: number, grouping: DomainGrouping) => {
+    return invoke<DomainGroupData[]>("plugin:bookmarks|group_bookmarks_by_domain", {
+      index,
+      grouping,
+    });
+  },
+  GetFaviconUrls: async () => {
+    return invoke<string[]>("plugin:bookmarks|get_favicon_urls");
+  },
+  OrganizeByDomain: async (index: number, grouping: DomainGrouping, topLevelIndex: number) => {
+    return invoke<NestedBookmark>("plugin:bookmarks|organize_by_domain", {
+      index,
+      grouping,
+      topLevelIndex,
+    });
+  },
+  AddSmartFolder: async (
+    parentIndex: number,
+    title: string,
+    query: SmartQuery,
+    topLevelIndex: number,
+  ) => {
+    return invoke<BookmarkResponse>("plugin:bookmarks|add_smart_folder", {
+      parentIndex,
+      title,
+      query,
+      topLevelIndex,
+    });
+  },
+  UpdateSmartFolderQuery: async (index: number, query: SmartQuery, topLevelIndex: number) => {
+    return invoke<NestedBookmark>("plugin:bookmarks|update_smart_folder_query", {
+      index,
+      query,
+      topLevelIndex,
+    });
+  },
+  SetBookmarkTags: async (index: number, tags: string[], topLevelIndex: number) => {
+    return invoke<NestedBookmark>("plugin:bookmarks|set_bookmark_tags", {
+      index,
+      tags,
+      topLevelIndex,
+    });
+  },
+  SetBookmarkKeyword: async (index: number, keyword: string | null, topLevelIndex: number) => {
+    return invoke<NestedBookmark>("plugin:bookmarks|set_bookmark_keyword", {
+      index,
+      keyword,
+      topLevelIndex,
+    });
+  },
+  AddToReadingList: async (title: string, topLevelIndex: number) => {
+    return invoke<BookmarkResponse>("plugin:bookmarks|add_to_reading_list", {
+      title,
+      topLevelIndex,
+    });
+  },
+  SetIsRead: async (index: number, isRead: boolean, topLevelIndex: number) => {
+    return invoke<NestedBookmark>("plugin:bookmarks|set_is_read", { index, isRead, topLevelIndex });
+  },
+  GetReadingList: async (unreadOnly: boolean) => {
+    return invoke<ReadingListItemData[]>("plugin:bookmarks|get_reading_list", { unreadOnly });
+  },
+  GetSyncConfig: async () => {
+    return invoke<SyncConfig | null>("plugin:bookmarks|get_sync_config", {});
+  },
+  SetSyncConfig: async (config: SyncConfig | null) => {
+    return invoke("plugin:bookmarks|set_sync_config", { config });
+  },
+  GetSyncStatus: async () => {
+    return invoke<SyncStatus>("plugin:bookmarks|get_sync_status", {});
+  },
+  SyncBookmarks: async () => {
+    return invoke<SyncReport>("plugin:bookmarks|sync_bookmarks", {});
+  },
+  GetEncryptionStatus: async () => {
+    return invoke<EncryptionStatus>("plugin:bookmarks|get_encryption_status", {});
+  },
+  UnlockBookmarks: async (passphrase: string) => {
+    return invoke<NestedBookmark>("plugin:bookmarks|unlock_bookmarks", { passphrase });
+  },
+  EnableEncryption: async (passphrase: string) => {
+    return invoke("plugin:bookmarks|enable_encryption", { passphrase });
+  },
+  DisableEncryption: async (passphrase: string) => {
+    return invoke("plugin:bookmarks|disable_encryption", { passphrase });
+  },
+  ListCollections: async () => {
+    return invoke<CollectionList>("plugin:bookmarks|list_collections", {});
+  },
+  CreateCollection: async (name: string) => {
+    return invoke<CollectionInfo>("plugin:bookmarks|create_collection", { name });
+  },
+  RenameCollection: async (id: string, name: string) => {
+    return invoke<CollectionInfo>("plugin:bookmarks|rename_collection", { id, name });
+  },
+  DeleteCollection: async (id: string) => {
+    return invoke("plugin:bookmarks|delete_collection", { id });
+  },
+  SwitchCollection: async (id: string) => {
+    return invoke<NestedBookmark>("plugin:bookmarks|switch_collection", { id });
+  },
 
   // js-injection
   GetExternalWebviewTitle: async () => {
@@ -129,4 +253,18 @@ export const Invoke = {
   HideExternalWebview: async () => {
     return invoke("hide_external_webview", {});
   },
+
+  // Favicon commands
+  GetFaviconServer: async () => {
+    return invoke<FaviconServer | null>("get_favicon_server", {});
+  },
+  PrefetchFavicons: async (urls: string[], sizes: number[]) => {
+    return invoke<PrefetchProgress>("prefetch_favicons", { urls, sizes });
+  },
+  SetCustomFavicon: async (host: string, source: string) => {
+    return invoke("set_custom_favicon", { host, source });
+  },
+  ResetCustomFavicon: async (host: string) => {
+    return invoke<boolean>("reset_custom_favicon", { host });
+  },
 } as const;
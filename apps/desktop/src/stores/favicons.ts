@@ -0,0 +1,47 @@
+import { createWithSignal } from "solid-zustand";
+import { convertFileSrc } from "@tauri-apps/api/core";
+
+import { NODE_ICON_SIZE } from "../constants";
+import { Invoke } from "../invokes";
+import type { FaviconServer } from "../types";
+
+// must sync with FAVICON_SCHEME in app/src/favicon.rs
+const FAVICON_SCHEME = "favicon";
+
+interface FaviconState {
+  // false until it's known whether favicons are served by the scheme or the server
+  ready: boolean;
+  // null if favicons are served by the favicon:// scheme
+  server: FaviconServer | null;
+  setup: () => Promise<void>;
+  prefetchFavicons: () => Promise<void>;
+}
+
+// favicon://localhost/ (http://favicon.localhost/ on Windows), or the server of this launch
+// which binds a random port and requires a token
+export const faviconUrl = (server: FaviconServer | null, url: string, size: number) => {
+  const query = new URLSearchParams({ url, size: String(size) });
+  if (server === null) {
+    return `${convertFileSrc("", FAVICON_SCHEME)}?${query}`;
+  }
+  query.set("token", server.token);
+  return `http://${server.addr}/favicon?${query}`;
+};
+
+// size of the image loaded for the icon size, the 2x one of srcset on high-DPI displays
+export const loadedFaviconSize = (size: number) => (window.devicePixelRatio > 1 ? size * 2 : size);
+
+export const useFaviconState = createWithSignal<FaviconState>((set) => ({
+  ready: false,
+  server: null,
+  setup: async () => {
+    const server = await Invoke.GetFaviconServer();
+    set(() => ({ ready: true, server }));
+  },
+  // fetch the favicons of all bookmarked hosts in the background, e.g. after an import,
+  // so that the sidebar doesn't fire hundreds of requests at once
+  prefetchFavicons: async () => {
+    const urls = await Invoke.GetFaviconUrls();
+    await Invoke.PrefetchFavicons(urls, [loadedFaviconSize(NODE_ICON_SIZE)]);
+  },
+}));
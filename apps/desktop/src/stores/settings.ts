@@ -10,9 +10,11 @@ interface UserSettingsState {
   theme: string;
   home_page_url: string;
   sidebar_font_size: number;
+  allowed_url_schemes: string[];
   get: () => Promise<void>;
   updateHomePageUrl: (value: string) => Promise<void>;
   updateSidebarFontSize: (value: number) => Promise<void>;
+  updateAllowedUrlSchemes: (value: string[]) => Promise<void>;
 }
 
 export const useUserSettingsState = createWithSignal<UserSettingsState>((set, get) => ({
@@ -20,6 +22,7 @@ export const useUserSettingsState = createWithSignal<UserSettingsState>((set, ge
   theme: "light",
   home_page_url: "https://search.brave.com/",
   sidebar_font_size: 13.0,
+  allowed_url_schemes: ["http", "https"],
   get: async () => {
     const result = await Invoke.GetUserSettings();
     set({ ...result });
@@ -30,6 +33,7 @@ export const useUserSettingsState = createWithSignal<UserSettingsState>((set, ge
       theme: get().theme,
       sidebar_font_size: get().sidebar_font_size,
       home_page_url: value,
+      allowed_url_schemes: get().allowed_url_schemes,
     });
     set({ ...result });
   },
@@ -39,6 +43,17 @@ export const useUserSettingsState = createWithSignal<UserSettingsState>((set, ge
       theme: get().theme,
       sidebar_font_size: value,
       home_page_url: get().home_page_url,
+      allowed_url_schemes: get().allowed_url_schemes,
+    });
+    set({ ...result });
+  },
+  updateAllowedUrlSchemes: async (value) => {
+    const result = await Invoke.UpdateUserSettings({
+      language: get().language,
+      theme: get().theme,
+      sidebar_font_size: get().sidebar_font_size,
+      home_page_url: get().home_page_url,
+      allowed_url_schemes: value,
     });
     set({ ...result });
   },
@@ -55,6 +70,7 @@ export const useAppSettingsState = createWithSignal<AppSettingsState>((set) => (
     gpu_acceleration_enabled: false,
     incognito: true,
     start_page_url: "https://search.brave.com/",
+    active_bookmark_collection: "default",
   },
   get: async () => {
     const settings = await Invoke.GetAppSettings();
@@ -1,15 +1,36 @@
 //! Must sync with core/src/nested.rs
 export type NestedBookmark = {
   index: number;
+  guid: string | null;
   title: string;
   url: string | null;
   host: string | null;
-  node_type: "Root" | "Folder" | "Bookmark" | "Separator";
+  display_host: string | null;
+  node_type: "Root" | "Folder" | "Bookmark" | "Separator" | "SmartFolder";
   date_added: number;
   is_open: boolean;
+  date_modified: number | null;
+  last_visited: number | null;
+  visit_count: number;
+  tags: string[];
+  query: SmartQuery | null;
+  keyword: string | null;
+  is_read: boolean;
   children: NestedBookmark[];
 };
 
+//! Must sync with core/src/query.rs
+export type QueryCondition =
+  | { HostEquals: string }
+  | { UrlMatches: string }
+  | { Tag: string }
+  | { AddedWithinDays: number }
+  | "NeverVisited";
+
+export type SmartQuery = {
+  conditions: QueryCondition[];
+};
+
 export type BookmarkResponse = {
   index: number;
   bookmarks: NestedBookmark;
@@ -25,7 +46,147 @@ export type ToolbarBookmarkData = {
   index: number;
   title: string;
   url: string;
-  host: string;
+  host: string | null;
+};
+
+export type ReadingListItemData = {
+  index: number;
+  title: string;
+  url: string;
+  host: string | null;
+  date_added: number | null;
+  is_read: boolean;
+};
+
+export type SearchResultData = {
+  index: number;
+  title: string;
+  url: string;
+  host: string | null;
+  frecency: number;
+};
+
+//! Must sync with bookmark/src/bookmarks/domain.rs
+export type DomainGrouping = "Host" | "RegistrableDomain";
+
+export type DomainBookmarkData = {
+  index: number;
+  title: string;
+  url: string;
+  host: string | null;
+};
+
+export type DomainGroupData = {
+  domain: string;
+  display_domain: string;
+  count: number;
+  bookmarks: DomainBookmarkData[];
+};
+
+//! Must sync with bookmark/src/bookmarks/stats.rs
+export type BookmarkStats = {
+  bookmarks: number;
+  folders: number;
+  smart_folders: number;
+  separators: number;
+  never_visited: number;
+  depths: { depth: number; count: number }[];
+  largest_folders: { index: number; title: string; children: number }[];
+  additions_per_month: { month: string; count: number }[];
+  top_hosts: { host: string; display_host: string; count: number }[];
+  total_nodes: number;
+  garbage_nodes: number;
+  garbage_ratio: number;
+};
+
+//! Must sync with favicon-server/src/lib.rs and prefetch.rs
+export type FaviconServer = {
+  addr: string;
+  token: string;
+};
+
+export type PrefetchProgress = {
+  total: number;
+  done: number;
+  fetched: number;
+  failed: number;
+  skipped: number;
+  running: boolean;
+};
+
+//! Must sync with bookmark/src/diff.rs and merge.rs
+export type NodeKey =
+  | "Root"
+  | { WellKnown: string }
+  | { Guid: string }
+  | { Url: [string, number] }
+  | { Path: [string, number] };
+
+export type NodeSummary = {
+  key: NodeKey;
+  title: string;
+  url: string | null;
+  path: string[];
+};
+
+export type DiffOperation =
+  | { Added: NodeSummary }
+  | { Removed: NodeSummary }
+  | { Moved: { node: NodeSummary; from: string[] } }
+  | { Renamed: { node: NodeSummary; from: string } };
+
+export type ConflictKind =
+  | { Title: { ours: string; theirs: string } }
+  | { Url: { ours: string; theirs: string } }
+  | { Parent: { ours: string[]; theirs: string[] } }
+  | "RemovedByOurs"
+  | "RemovedByTheirs"
+  | "Orphaned"
+  | "Cycle";
+
+export type MergeConflict = {
+  key: NodeKey;
+  title: string;
+  kind: ConflictKind;
+};
+
+//! Must sync with sync/src/backend.rs and sync.rs
+export type BackendConfig =
+  | { type: "Directory"; path: string }
+  | { type: "WebDav"; url: string; username: string | null; password: string | null };
+
+export type SyncConfig = {
+  backend: BackendConfig;
+  strategy: "PreferLocal" | "PreferRemote";
+};
+
+//! Must sync with plugin-bookmarks/src/models.rs
+export type SyncStatus = {
+  configured: boolean;
+  last_synced_at: number | null;
+  last_error: string | null;
+  pending_changes: DiffOperation[];
+};
+
+export type SyncReport = {
+  synced_at: number;
+  uploaded: boolean;
+  conflicts: MergeConflict[];
+};
+
+export type EncryptionStatus = {
+  encrypted: boolean;
+  locked: boolean;
+};
+
+export type CollectionInfo = {
+  id: string;
+  name: string;
+};
+
+export type CollectionList = {
+  active: string;
+  collections: CollectionInfo[];
 };
 
 //! Must sync with rust struct in app/settings.rs
@@ -34,12 +195,14 @@ export type UserSettings = {
   theme: string;
   home_page_url: string;
   sidebar_font_size: number;
+  allowed_url_schemes: string[];
 };
 
 export type AppSettings = {
   gpu_acceleration_enabled: boolean;
   incognito: boolean;
   start_page_url: string;
+  active_bookmark_collection: string;
 };
 
 export type WindowGeometry = {
@@ -1 +1,2 @@
+pub mod favicons;
 pub mod webviews;
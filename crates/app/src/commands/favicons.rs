@@ -0,0 +1,72 @@
+use favicon_server::{FaviconServer, Favicons, PrefetchProgress};
+use parus_common::Error;
+use tauri::Manager;
+
+/// Address and token of the favicon server of this launch.
+/// None if favicons are served by the `favicon://` scheme.
+#[tauri::command]
+pub fn get_favicon_server(app_handle: tauri::AppHandle) -> Option<FaviconServer> {
+    app_handle
+        .try_state::<FaviconServer>()
+        .map(|server| server.inner().clone())
+}
+
+/// Fetch the favicons of the sites not cached yet in the background, e.g. after an import.
+/// The sizes are the ones the sidebar loads, 16 if empty.
+/// Returns the progress of the running job if a job is already running.
+#[tauri::command]
+pub async fn prefetch_favicons(
+    app_handle: tauri::AppHandle,
+    urls: Vec<String>,
+    sizes: Vec<u32>,
+) -> Result<PrefetchProgress, Error> {
+    Ok(favicons(&app_handle)?
+        .prefetch(&urls, &sizes)
+        .unwrap_or_else(|running| running))
+}
+
+/// Use the image as the favicon of the host, e.g. an internal tool without a favicon.
+/// The source is a file path or a data URL. The favicon is kept until it's reset.
+/// Image files larger than 4 MB are rejected.
+#[tauri::command]
+pub async fn set_custom_favicon(
+    app_handle: tauri::AppHandle,
+    host: String,
+    source: String,
+) -> Result<(), Error> {
+    // the file may be on a slow drive, so it's read on a blocking thread
+    let data =
+        tauri::async_runtime::spawn_blocking(move || favicon_server::read_image_source(&source))
+            .await
+            .map_err(|e| Error::Other(e.to_string()))?
+            .map_err(favicon_error)?;
+    favicons(&app_handle)?
+        .set_custom(&host, data)
+        .await
+        .map_err(favicon_error)?;
+    Ok(())
+}
+
+/// Fetch the favicon of the host automatically again, false if it had no custom favicon
+#[tauri::command]
+pub async fn reset_custom_favicon(
+    app_handle: tauri::AppHandle,
+    host: String,
+) -> Result<bool, Error> {
+    favicons(&app_handle)?
+        .reset_custom(&host)
+        .await
+        .map_err(favicon_error)
+}
+
+/// Favicons are not available if the cache failed to open in setup
+fn favicons(app_handle: &tauri::AppHandle) -> Result<Favicons, Error> {
+    app_handle
+        .try_state::<Favicons>()
+        .map(|favicons| favicons.inner().clone())
+        .ok_or_else(|| Error::Other("Favicons are not available".to_string()))
+}
+
+fn favicon_error(e: favicon_server::error::Error) -> Error {
+    Error::Other(e.to_string())
+}
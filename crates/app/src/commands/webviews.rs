@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
 use tauri::{PhysicalPosition, PhysicalSize, Position, Rect, Size, Url};
 
+use parus_bookmark::scheme::bookmarklet_script;
 use parus_common::Error;
+use tauri_plugin_bookmarks::BookmarksExt;
 
 use crate::window::{get_app_webview, get_external_webview};
 
@@ -34,11 +36,19 @@ pub fn get_app_webview_bounds(app_handle: tauri::AppHandle) -> Result<Rect, Erro
     Ok(bounds)
 }
 
-/// Navigate the external webview to the given URL
+/// Navigate the external webview to the given URL.
+/// The input is resolved as a bookmark keyword first (e.g. `rs serde`).
+/// Bookmarklets (`javascript:` URLs) run in the current page instead of navigating.
 #[tauri::command]
 pub fn navigate_webview_url(app_handle: tauri::AppHandle, url: String) -> Result<(), Error> {
+    let url = app_handle.resolve_keyword(&url)?.unwrap_or(url);
     let parsed_url = Url::parse(&url).map_err(tauri::Error::InvalidUrl)?;
     let webview = get_external_webview(&app_handle)?;
+    if let Some(script) = bookmarklet_script(&parsed_url) {
+        app_handle.scheme_policy().check(&parsed_url)?;
+        webview.eval(&script)?;
+        return Ok(());
+    }
     webview.navigate(parsed_url)?;
     Ok(())
 }
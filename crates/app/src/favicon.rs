@@ -0,0 +1,73 @@
+use favicon_server::{Favicon, Favicons};
+use tauri::{
+    http::{header, Request, Response, StatusCode},
+    Manager, Runtime, UriSchemeContext, UriSchemeResponder, Url,
+};
+
+use parus_common::constants::APP_WEBVIEW_LABEL;
+
+/// URI scheme of the favicons, e.g. `favicon://localhost/?url=https%3A%2F%2Fdocs.rs&size=32`.
+/// Windows and Android use `http://favicon.localhost/?url=...` instead.
+pub const FAVICON_SCHEME: &str = "favicon";
+
+/// Serve the favicon of the `url` query parameter in the `size` from the cache, fetching it if needed.
+/// Only the app webview can use it, otherwise pages in the external webview could make
+/// the app fetch any URL.
+pub fn handle_favicon_request<R: Runtime>(
+    ctx: UriSchemeContext<'_, R>,
+    request: Request<Vec<u8>>,
+    responder: UriSchemeResponder,
+) {
+    if ctx.webview_label() != APP_WEBVIEW_LABEL {
+        responder.respond(error_response(StatusCode::FORBIDDEN));
+        return;
+    }
+    // the cache is opened in setup, which may not have finished yet or have failed
+    let Some(favicons) = ctx.app_handle().try_state::<Favicons>() else {
+        responder.respond(error_response(StatusCode::SERVICE_UNAVAILABLE));
+        return;
+    };
+    let favicons = favicons.inner().clone();
+    let Some((url, size)) = parse_query(&request.uri().to_string()) else {
+        responder.respond(error_response(StatusCode::BAD_REQUEST));
+        return;
+    };
+
+    tauri::async_runtime::spawn(async move {
+        // the globe is shown for sites without a favicon as the HTTP server does
+        let favicon = favicons.get(&url, size).await.unwrap_or_else(|e| {
+            log::debug!("Failed to get the favicon of {url}: {e}");
+            Favicon::fallback()
+        });
+        responder.respond(image_response(favicon));
+    });
+}
+
+/// `url` and `size` query parameters of the request URI
+fn parse_query(uri: &str) -> Option<(String, Option<u32>)> {
+    let uri = Url::parse(uri).ok()?;
+    let mut url = None;
+    let mut size = None;
+    for (key, value) in uri.query_pairs() {
+        match key.as_ref() {
+            "url" => url = Some(value.into_owned()),
+            "size" => size = value.parse().ok(),
+            _ => {}
+        }
+    }
+    Some((url?, size))
+}
+
+fn image_response(favicon: Favicon) -> Response<Vec<u8>> {
+    Response::builder()
+        .header(header::CONTENT_TYPE, favicon.mime_type)
+        .header(header::CACHE_CONTROL, "public, max-age=86400")
+        .body(favicon.data)
+        .unwrap_or_else(|_| error_response(StatusCode::INTERNAL_SERVER_ERROR))
+}
+
+fn error_response(status: StatusCode) -> Response<Vec<u8>> {
+    let mut response = Response::new(Vec::new());
+    *response.status_mut() = status;
+    response
+}
@@ -6,11 +6,11 @@ use window::create_window;
 use parus_common::AppHandlePathExt;
 
 mod commands;
+mod favicon;
 mod updater;
 mod window;
 
-// TODO: use random port picker?
-const FAVICON_SERVER_PORT: u16 = 7853;
+#[cfg(feature = "favicon-http-server")]
 const FAVICON_SERVER_ALLOW_ORIGINS: [&str; 3] = [
     "http://localhost/",
     "http://tauri.localhost/",
@@ -19,22 +19,15 @@ const FAVICON_SERVER_ALLOW_ORIGINS: [&str; 3] = [
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    let app = tauri::Builder::default()
-        .setup(|app| {
-            // run favicon server
-            let database_path = app.handle().favicon_database_path();
-            tauri::async_runtime::spawn(favicon_server::run(
-                database_path,
-                FAVICON_SERVER_PORT,
-                FAVICON_SERVER_ALLOW_ORIGINS.to_vec(),
-            ));
-
-            #[cfg(desktop)]
-            {
-                app.handle()
-                    .plugin(tauri_plugin_single_instance::init(|_app, _args, _cwd| {}))?;
-            }
+    let mut builder = tauri::Builder::default();
+    // must be the first plugin, a second instance exits before it opens any file
+    #[cfg(desktop)]
+    {
+        builder = builder.plugin(tauri_plugin_single_instance::init(|_app, _args, _cwd| {}));
+    }
 
+    let app = builder
+        .setup(|app| {
             // show debug log when debug build
             if cfg!(debug_assertions) {
                 app.handle().plugin(
@@ -44,6 +37,11 @@ pub fn run() {
                 )?;
             }
 
+            // the app works without favicons, e.g. when the cache file is broken
+            if let Err(e) = setup_favicons(app) {
+                log::error!("Failed to set up favicons: {e}");
+            }
+
             #[cfg(desktop)]
             {
                 // setup tauri plugin updater
@@ -86,7 +84,15 @@ pub fn run() {
 
             Ok(())
         })
+        .register_asynchronous_uri_scheme_protocol(
+            favicon::FAVICON_SCHEME,
+            favicon::handle_favicon_request,
+        )
         .invoke_handler(tauri::generate_handler![
+            commands::favicons::get_favicon_server,
+            commands::favicons::prefetch_favicons,
+            commands::favicons::set_custom_favicon,
+            commands::favicons::reset_custom_favicon,
             commands::webviews::get_app_webview_bounds,
             commands::webviews::navigate_webview_url,
             commands::webviews::set_external_webview_bounds,
@@ -104,3 +110,22 @@ pub fn run() {
         _ => {}
     });
 }
+
+/// Open the favicon cache, which is served by the favicon:// scheme or by a server on localhost
+fn setup_favicons(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
+    let database_path = app.handle().favicon_database_path();
+    let favicons =
+        favicon_server::Favicons::open(database_path, favicon_server::FaviconConfig::default())?;
+    app.manage(favicons.clone());
+    #[cfg(feature = "favicon-http-server")]
+    {
+        // an ephemeral port not to conflict with other instances or programs
+        let server = tauri::async_runtime::block_on(favicon_server::serve(
+            favicons,
+            0,
+            FAVICON_SERVER_ALLOW_ORIGINS.to_vec(),
+        ))?;
+        app.manage(server);
+    }
+    Ok(())
+}
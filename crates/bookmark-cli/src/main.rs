@@ -0,0 +1,378 @@
+mod output;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use anyhow::Context;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use parus_bookmark::{
+    bookmarks::validate::Problem, crypto::EncryptionKey, scheme::SchemePolicy, Bookmarks,
+};
+
+use output::{print_json, ListEntry};
+
+/// Exit code when the bookmark file has problems, usage errors exit with 2
+const VALIDATION_FAILED: u8 = 3;
+
+/// Maintain a Parus bookmark file without launching the app
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    /// Bookmark file
+    #[arg(short, long, env = "PARUS_BOOKMARKS_FILE")]
+    file: PathBuf,
+
+    /// Passphrase of an encrypted bookmark file
+    #[arg(long, env = "PARUS_BOOKMARKS_PASSPHRASE", hide_env_values = true)]
+    passphrase: Option<String>,
+
+    /// Print results as JSON for scripting
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Allow bookmarks of a URL scheme other than http and https, e.g. `file` or `javascript`
+    #[arg(long = "allow-scheme", value_name = "SCHEME", global = true)]
+    allow_schemes: Vec<String>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List bookmarks under the node with their folder path
+    List {
+        #[arg(default_value_t = 1)]
+        index: usize,
+    },
+    /// Print the tree under the node
+    Tree {
+        #[arg(default_value_t = 1)]
+        index: usize,
+    },
+    /// Search bookmarks by title and URL
+    Search { query: String },
+    /// Add a bookmark, to the toolbar by default
+    Add {
+        title: String,
+        url: String,
+        /// Parent node, the toolbar if omitted
+        #[arg(long)]
+        parent: Option<usize>,
+    },
+    /// Add a folder
+    AddFolder {
+        title: String,
+        #[arg(long, default_value_t = 1)]
+        parent: usize,
+    },
+    /// Move a node
+    Move {
+        source: usize,
+        #[command(flatten)]
+        destination: Destination,
+    },
+    /// Remove a node and its descendants
+    Remove { index: usize },
+    /// Import a Netscape bookmark file (bookmarks.html) exported by a browser
+    Import {
+        input: PathBuf,
+        #[arg(long, default_value_t = 1)]
+        parent: usize,
+    },
+    /// Export the node and its descendants
+    Export {
+        output: PathBuf,
+        #[arg(long, value_enum, default_value_t = ExportFormat::Html)]
+        format: ExportFormat,
+        #[arg(long, default_value_t = 1)]
+        index: usize,
+    },
+    /// Check the bookmark file, exits with 3 if there are problems
+    Validate {
+        /// Fix the problems and save the file
+        #[arg(long)]
+        repair: bool,
+    },
+    /// Drop removed nodes from the file. Indexes are renumbered.
+    Compact,
+}
+
+#[derive(Args)]
+#[group(required = true, multiple = false)]
+struct Destination {
+    /// Move as the last child of the node
+    #[arg(long)]
+    into: Option<usize>,
+    /// Move as the first child of the node
+    #[arg(long)]
+    into_first: Option<usize>,
+    /// Move after the node
+    #[arg(long)]
+    after: Option<usize>,
+    /// Move before the node
+    #[arg(long)]
+    before: Option<usize>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    /// Netscape bookmark file, which can be imported by most browsers
+    Html,
+    /// Bookmark file of Parus, always the whole tree
+    Json,
+    /// Nested JSON tree
+    Nested,
+}
+
+/// Bookmarks loaded from the file, saved back with the same encryption
+struct BookmarkFile {
+    path: PathBuf,
+    bookmarks: Bookmarks,
+    key: Option<EncryptionKey>,
+}
+
+impl BookmarkFile {
+    /// Load the file, or start from the default bookmarks if `create` and the file does not exist
+    fn open(path: &Path, passphrase: Option<&str>, create: bool) -> anyhow::Result<Self> {
+        if create && !path.exists() {
+            return Ok(Self {
+                path: path.to_path_buf(),
+                bookmarks: Bookmarks::default(),
+                key: None,
+            });
+        }
+
+        let (bookmarks, key) = if Bookmarks::is_encrypted_file(path)
+            .with_context(|| format!("can't read {}", path.display()))?
+        {
+            let passphrase = passphrase.context(
+                "the bookmark file is encrypted, set PARUS_BOOKMARKS_PASSPHRASE or --passphrase",
+            )?;
+            let (bookmarks, key) = Bookmarks::load_from_encrypted_file(path, passphrase)?;
+            (bookmarks, Some(key))
+        } else {
+            (Bookmarks::load_from_file(path)?, None)
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            bookmarks,
+            key,
+        })
+    }
+
+    fn save(&self) -> anyhow::Result<()> {
+        match &self.key {
+            Some(key) => self.bookmarks.save_to_encrypted_file(&self.path, key)?,
+            None => self.bookmarks.save_to_file(&self.path)?,
+        }
+        Ok(())
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(&cli) {
+        Ok(code) => code,
+        Err(e) => {
+            if cli.json {
+                print_json(&serde_json::json!({ "error": format!("{e:#}") }));
+            } else {
+                eprintln!("error: {e:#}");
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: &Cli) -> anyhow::Result<ExitCode> {
+    let passphrase = cli.passphrase.as_deref();
+    let creates = matches!(
+        cli.command,
+        Command::Add { .. } | Command::AddFolder { .. } | Command::Import { .. }
+    );
+    if let Command::Validate { repair } = cli.command {
+        return validate(&cli.file, passphrase, repair, cli.json);
+    }
+    let mut file = BookmarkFile::open(&cli.file, passphrase, creates)?;
+    let bookmarks = &mut file.bookmarks;
+    bookmarks.set_scheme_policy(SchemePolicy::new(
+        ["http", "https"]
+            .iter()
+            .map(|scheme| scheme.to_string())
+            .chain(cli.allow_schemes.iter().cloned()),
+    ));
+
+    match &cli.command {
+        Command::List { index } => {
+            let entries = ListEntry::collect(bookmarks, *index)?;
+            if cli.json {
+                print_json(&entries);
+            } else {
+                for entry in entries {
+                    println!("{}", entry);
+                }
+            }
+            return Ok(ExitCode::SUCCESS);
+        }
+        Command::Tree { index } => {
+            if cli.json {
+                print_json(&bookmarks.to_nested_bookmark(*index)?);
+            } else {
+                print!("{}", output::tree(bookmarks, *index)?);
+            }
+            return Ok(ExitCode::SUCCESS);
+        }
+        Command::Search { query } => {
+            let results = bookmarks.search(query)?;
+            if cli.json {
+                print_json(&results);
+            } else {
+                for result in results {
+                    println!("{:>5}  {}  {}", result.index, result.title, result.url);
+                }
+            }
+            return Ok(ExitCode::SUCCESS);
+        }
+        Command::Export {
+            output,
+            format,
+            index,
+        } => {
+            let data = match format {
+                ExportFormat::Html => bookmarks.to_html(*index)?,
+                ExportFormat::Json => bookmarks.to_json()?,
+                ExportFormat::Nested => bookmarks.to_nested_json_pretty(*index)?,
+            };
+            fs::write(output, data).with_context(|| format!("can't write {}", output.display()))?;
+            output::done(cli.json, &format!("Exported to {}", output.display()), None);
+            return Ok(ExitCode::SUCCESS);
+        }
+        Command::Add { title, url, parent } => {
+            let parent = match parent {
+                Some(parent) => *parent,
+                None => bookmarks.get_toolbar_node_id()?.into(),
+            };
+            let index = bookmarks.append_bookmark(parent, title, url)?;
+            output::done(cli.json, &format!("Added bookmark {index}"), Some(index));
+        }
+        Command::AddFolder { title, parent } => {
+            let index = bookmarks.add_folder(*parent, title)?;
+            output::done(cli.json, &format!("Added folder {index}"), Some(index));
+        }
+        Command::Move {
+            source,
+            destination,
+        } => {
+            match destination {
+                Destination { into: Some(d), .. } => bookmarks.append_to_child(*source, *d)?,
+                Destination {
+                    into_first: Some(d),
+                    ..
+                } => bookmarks.prepend_to_child(*source, *d)?,
+                Destination { after: Some(d), .. } => bookmarks.insert_after(*source, *d)?,
+                Destination {
+                    before: Some(d), ..
+                } => bookmarks.insert_before(*source, *d)?,
+                _ => unreachable!("clap requires one destination"),
+            }
+            output::done(cli.json, &format!("Moved {source}"), Some(*source));
+        }
+        Command::Remove { index } => {
+            bookmarks.remove_subtree(*index)?;
+            output::done(cli.json, &format!("Removed {index}"), Some(*index));
+        }
+        Command::Import { input, parent } => {
+            let html = fs::read_to_string(input)
+                .with_context(|| format!("can't read {}", input.display()))?;
+            let summary = bookmarks.import_html(&html, *parent)?;
+            if cli.json {
+                print_json(&summary);
+            } else {
+                println!(
+                    "Imported {} bookmarks and {} folders, skipped {}",
+                    summary.bookmarks, summary.folders, summary.skipped
+                );
+            }
+        }
+        Command::Compact => {
+            let dropped = bookmarks.compact()?;
+            if cli.json {
+                print_json(&serde_json::json!({ "dropped": dropped }));
+            } else {
+                println!("Dropped {dropped} removed nodes");
+            }
+        }
+        Command::Validate { .. } => unreachable!("validated before loading"),
+    }
+
+    file.save()?;
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Problems of the file. Broken contents are reported as a problem,
+/// while failing to read the file, e.g. with a wrong passphrase, is an error.
+fn validate(
+    path: &Path,
+    passphrase: Option<&str>,
+    repair: bool,
+    json: bool,
+) -> anyhow::Result<ExitCode> {
+    let (problems, remaining) = match BookmarkFile::open(path, passphrase, false) {
+        Ok(mut file) if repair => {
+            let problems = file.bookmarks.repair()?;
+            file.save()?;
+            (
+                into_messages(problems),
+                into_messages(file.bookmarks.validate()),
+            )
+        }
+        Ok(file) => {
+            let problems = into_messages(file.bookmarks.validate());
+            (problems.clone(), problems)
+        }
+        Err(e) if is_corrupted(&e) => (vec![format!("{e:#}")], vec![format!("{e:#}")]),
+        Err(e) => return Err(e),
+    };
+
+    if json {
+        print_json(&serde_json::json!({
+            "valid": remaining.is_empty(),
+            "problems": problems,
+            "remaining": remaining,
+        }));
+    } else if problems.is_empty() {
+        println!("OK");
+    } else {
+        for problem in &problems {
+            println!("{problem}");
+        }
+        if repair {
+            println!("Repaired, {} problems remaining", remaining.len());
+        }
+    }
+
+    Ok(if remaining.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(VALIDATION_FAILED)
+    })
+}
+
+/// Whether the file was read but its contents are broken
+fn is_corrupted(e: &anyhow::Error) -> bool {
+    match e.downcast_ref::<parus_bookmark::Error>() {
+        Some(parus_bookmark::Error::Json(e)) => !e.is_io(),
+        Some(parus_bookmark::Error::CorruptedEncryptedFile(_)) => true,
+        _ => false,
+    }
+}
+
+fn into_messages(problems: Vec<Problem>) -> Vec<String> {
+    problems.iter().map(|problem| problem.to_string()).collect()
+}
@@ -0,0 +1,99 @@
+use std::fmt;
+
+use indextree::NodeId;
+use serde::Serialize;
+
+use parus_bookmark::{data::NodeType, Bookmarks, Error};
+
+/// Bookmark with the titles of its ancestors
+#[derive(Serialize)]
+pub struct ListEntry {
+    pub index: usize,
+    pub title: String,
+    pub url: String,
+    /// Titles of the ancestors joined with `/`, without the root
+    pub path: String,
+}
+
+impl ListEntry {
+    /// Bookmarks under the node in tree order
+    pub fn collect(bookmarks: &Bookmarks, index: usize) -> Result<Vec<Self>, Error> {
+        let node_id = bookmarks.find_node_id_by_index(index)?;
+        let arena = bookmarks.arena();
+        let entries = node_id
+            .descendants(arena)
+            .filter_map(|node_id| {
+                let data = arena.get(node_id)?.get();
+                let url = data.url.as_ref().filter(|_| data.is_bookmark())?;
+                let mut ancestors = node_id
+                    .ancestors(arena)
+                    .skip(1)
+                    .filter_map(|ancestor| arena.get(ancestor))
+                    .filter(|ancestor| !ancestor.get().is_root())
+                    .map(|ancestor| ancestor.get().title.as_str())
+                    .collect::<Vec<_>>();
+                ancestors.reverse();
+                Some(Self {
+                    index: node_id.into(),
+                    title: data.title.clone(),
+                    url: url.to_string(),
+                    path: ancestors.join("/"),
+                })
+            })
+            .collect();
+        Ok(entries)
+    }
+}
+
+impl fmt::Display for ListEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:>5}  {}  {}  [{}]",
+            self.index, self.title, self.url, self.path
+        )
+    }
+}
+
+/// Indented tree with indexes
+pub fn tree(bookmarks: &Bookmarks, index: usize) -> Result<String, Error> {
+    let node_id = bookmarks.find_node_id_by_index(index)?;
+    let mut out = String::new();
+    write_tree(bookmarks, node_id, 0, &mut out);
+    Ok(out)
+}
+
+fn write_tree(bookmarks: &Bookmarks, node_id: NodeId, depth: usize, out: &mut String) {
+    let Some(node) = bookmarks.arena().get(node_id) else {
+        return;
+    };
+    let data = node.get();
+    let index: usize = node_id.into();
+    let indent = "  ".repeat(depth);
+    let line = match (&data.node_type, &data.url) {
+        (NodeType::Bookmark, Some(url)) => format!("{indent}{}  {url}", data.title),
+        (NodeType::Separator, _) => format!("{indent}---"),
+        (NodeType::SmartFolder, _) => format!("{indent}{}/ (smart folder)", data.title),
+        _ => format!("{indent}{}/", data.title),
+    };
+    out.push_str(&format!("{index:>5}  {line}\n"));
+    for child in node_id.children(bookmarks.arena()) {
+        write_tree(bookmarks, child, depth + 1, out);
+    }
+}
+
+pub fn print_json<T: Serialize + ?Sized>(value: &T) {
+    match serde_json::to_string_pretty(value) {
+        Ok(json) => println!("{json}"),
+        Err(e) => eprintln!("error: {e}"),
+    }
+}
+
+/// Result of a command changing the file
+pub fn done(json: bool, message: &str, index: Option<usize>) {
+    if json {
+        print_json(&serde_json::json!({ "message": message, "index": index }));
+    } else {
+        println!("{message}");
+    }
+}
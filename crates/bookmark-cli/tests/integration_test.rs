@@ -0,0 +1,177 @@
+#[cfg(test)]
+mod tests {
+    use std::{path::Path, process::Command};
+
+    use serde_json::Value;
+
+    fn run(file: &Path, args: &[&str]) -> anyhow::Result<(i32, String)> {
+        let output = Command::new(env!("CARGO_BIN_EXE_parus-bookmark"))
+            .arg("--file")
+            .arg(file)
+            .args(args)
+            .env_remove("PARUS_BOOKMARKS_PASSPHRASE")
+            .output()?;
+        Ok((
+            output.status.code().unwrap_or(-1),
+            String::from_utf8(output.stdout)?,
+        ))
+    }
+
+    fn run_json(file: &Path, args: &[&str]) -> anyhow::Result<Value> {
+        let mut args = args.to_vec();
+        args.push("--json");
+        let (code, stdout) = run(file, &args)?;
+        assert_eq!(code, 0, "{stdout}");
+        Ok(serde_json::from_str(&stdout)?)
+    }
+
+    #[test]
+    fn test_edit_file() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file = dir.path().join("bookmarks.json");
+
+        // the file is created by the first change
+        let added = run_json(&file, &["add", "Rust", "https://www.rust-lang.org/"])?;
+        assert_eq!(added["index"], 3);
+        let folder = run_json(&file, &["add-folder", "Docs"])?;
+        assert_eq!(folder["index"], 4);
+        run_json(
+            &file,
+            &["add", "docs.rs", "https://docs.rs/", "--parent", "4"],
+        )?;
+
+        let list = run_json(&file, &["list"])?;
+        assert_eq!(list.as_array().map(Vec::len), Some(2));
+        assert_eq!(list[0]["path"], "Toolbar");
+        assert_eq!(list[1]["path"], "Docs");
+
+        run_json(&file, &["move", "3", "--into", "4"])?;
+        let list = run_json(&file, &["list", "4"])?;
+        assert_eq!(list[1]["title"], "Rust");
+
+        // other schemes need to be allowed
+        let (code, _) = run(&file, &["add", "Title", "javascript:alert(document.title)"])?;
+        assert_eq!(code, 1);
+        let bookmarklet = run_json(
+            &file,
+            &[
+                "add",
+                "Title",
+                "javascript:alert(document.title)",
+                "--allow-scheme",
+                "javascript",
+            ],
+        )?;
+        run_json(&file, &["remove", &bookmarklet["index"].to_string()])?;
+
+        let results = run_json(&file, &["search", "docs"])?;
+        assert_eq!(results[0]["url"], "https://docs.rs/");
+
+        run_json(&file, &["remove", "4"])?;
+        assert_eq!(
+            run_json(&file, &["list"])?.as_array().map(Vec::len),
+            Some(0)
+        );
+
+        let compacted = run_json(&file, &["compact"])?;
+        assert_eq!(compacted["dropped"], 4);
+
+        let (code, stdout) = run(&file, &["tree"])?;
+        assert_eq!(code, 0);
+        assert_eq!(stdout, "    1  All Bookmarks/\n    2    Toolbar/\n");
+
+        // errors exit with 1
+        let (code, stdout) = run(&file, &["remove", "1", "--json"])?;
+        assert_eq!(code, 1);
+        assert!(stdout.contains("error"));
+        let (code, _) = run(&dir.path().join("missing.json"), &["list"])?;
+        assert_eq!(code, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_and_export() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file = dir.path().join("bookmarks.json");
+        let html = dir.path().join("bookmarks.html");
+        std::fs::write(
+            &html,
+            r#"<DL><p>
+    <DT><H3 PERSONAL_TOOLBAR_FOLDER="true">Bookmarks bar</H3>
+    <DL><p>
+        <DT><A HREF="https://github.com/">GitHub</A>
+    </DL><p>
+    <DT><H3>Rust</H3>
+    <DL><p>
+        <DT><A HREF="https://www.rust-lang.org/">Rust</A>
+        <DT><A HREF="place:sort=8">Recent</A>
+    </DL><p>
+</DL><p>
+"#,
+        )?;
+
+        let summary = run_json(&file, &["import", html.to_str().unwrap()])?;
+        assert_eq!(summary["bookmarks"], 2);
+        assert_eq!(summary["folders"], 1);
+        assert_eq!(summary["skipped"], 1);
+        let list = run_json(&file, &["list"])?;
+        assert_eq!(list[0]["path"], "Toolbar");
+        assert_eq!(list[1]["path"], "Rust");
+
+        let exported = dir.path().join("exported.html");
+        run_json(&file, &["export", exported.to_str().unwrap()])?;
+        let exported = std::fs::read_to_string(exported)?;
+        assert!(exported.contains(r#"<A HREF="https://github.com/""#));
+
+        let nested = dir.path().join("nested.json");
+        run_json(
+            &file,
+            &["export", nested.to_str().unwrap(), "--format", "nested"],
+        )?;
+        let nested: Value = serde_json::from_str(&std::fs::read_to_string(nested)?)?;
+        assert_eq!(nested["title"], "All Bookmarks");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file = dir.path().join("bookmarks.json");
+        run_json(&file, &["add", "Rust", "https://www.rust-lang.org/"])?;
+
+        let (code, stdout) = run(&file, &["validate"])?;
+        assert_eq!(code, 0);
+        assert_eq!(stdout.trim(), "OK");
+
+        // the toolbar is gone
+        run_json(&file, &["remove", "2"])?;
+        let (code, stdout) = run(&file, &["validate", "--json"])?;
+        assert_eq!(code, 3);
+        let report: Value = serde_json::from_str(&stdout)?;
+        assert_eq!(report["valid"], false);
+
+        let (code, stdout) = run(&file, &["validate", "--repair", "--json"])?;
+        assert_eq!(code, 0);
+        let report: Value = serde_json::from_str(&stdout)?;
+        assert_eq!(report["valid"], true);
+        assert_eq!(report["problems"][0], "toolbar folder not found");
+        let (code, _) = run(&file, &["validate"])?;
+        assert_eq!(code, 0);
+
+        // a file with broken contents
+        std::fs::write(&file, "not json")?;
+        let (code, _) = run(&file, &["validate"])?;
+        assert_eq!(code, 3);
+
+        // failing to read the file is an error, not a problem
+        let (code, _) = run(&dir.path().join("missing.json"), &["validate"])?;
+        assert_eq!(code, 1);
+        std::fs::write(&file, "PARUSENC")?;
+        let (code, _) = run(&file, &["validate"])?;
+        assert_eq!(code, 1);
+
+        Ok(())
+    }
+}
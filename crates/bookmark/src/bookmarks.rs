@@ -1,17 +1,26 @@
 pub mod collection;
+pub mod domain;
+pub mod frecency;
+pub mod html;
 pub mod io;
 pub mod json;
+pub mod keyword;
 pub mod manipulation;
 pub mod nested;
+pub mod reading_list;
+pub mod stats;
 pub mod tree;
+pub mod validate;
 
 pub use nested::NestedBookmark;
 
-use crate::data::BookmarkData;
+use crate::{data::BookmarkData, error::Error, scheme::SchemePolicy};
 use indextree::{macros::tree, Arena};
 
 pub struct Bookmarks {
     arena: Arena<BookmarkData>,
+    /// URL schemes allowed for new bookmarks, not saved to the file
+    scheme_policy: SchemePolicy,
 }
 
 impl Default for Bookmarks {
@@ -25,12 +34,30 @@ impl Default for Bookmarks {
                 toolbar,
             }
         );
-        Self { arena }
+        Self::new(arena)
     }
 }
 
 impl Bookmarks {
     pub fn new(arena: Arena<BookmarkData>) -> Self {
-        Self { arena }
+        Self {
+            arena,
+            scheme_policy: SchemePolicy::default(),
+        }
+    }
+
+    pub fn scheme_policy(&self) -> &SchemePolicy {
+        &self.scheme_policy
+    }
+
+    /// Set the URL schemes allowed for bookmarks added from now on.
+    /// Existing bookmarks are kept as is.
+    pub fn set_scheme_policy(&mut self, policy: SchemePolicy) {
+        self.scheme_policy = policy;
+    }
+
+    /// New bookmark data checked by the scheme policy
+    fn new_bookmark_data(&self, title: &str, url: &str) -> Result<BookmarkData, Error> {
+        BookmarkData::try_new_bookmark_with_policy(title, url, &self.scheme_policy)
     }
 }
@@ -1,6 +1,8 @@
 use crate::{
     data::{FolderData, NodeType, ToolbarBookmarkData},
     error::Error,
+    query::SmartQuery,
+    utils::get_unix_timestamp,
 };
 
 use super::Bookmarks;
@@ -27,6 +29,16 @@ impl Bookmarks {
         Ok(vec)
     }
 
+    /// Get indexes of bookmarks matching the query
+    pub fn find_bookmarks_by_query(&self, query: &SmartQuery) -> Vec<usize> {
+        let now = get_unix_timestamp().unwrap_or_default();
+        query
+            .find_matches(&self.arena, now)
+            .into_iter()
+            .map(|node_id| node_id.into())
+            .collect()
+    }
+
     /// Get toolbar bookmarks as Vec<ToolbarBookmarkData>
     pub fn get_toolbar_bookmarks(&self) -> Vec<ToolbarBookmarkData> {
         if let Ok(toolbar_id) = self.get_toolbar_node_id() {
@@ -39,15 +51,13 @@ impl Bookmarks {
                         }
 
                         let data = node.get();
-                        match (&data.node_type, &data.url, &data.host) {
-                            (NodeType::Bookmark, Some(url), Some(host)) => {
-                                Some(ToolbarBookmarkData {
-                                    index: node_id.into(),
-                                    title: data.title.clone(),
-                                    url: url.to_string(),
-                                    host: host.clone(),
-                                })
-                            }
+                        match (&data.node_type, &data.url) {
+                            (NodeType::Bookmark, Some(url)) => Some(ToolbarBookmarkData {
+                                index: node_id.into(),
+                                title: data.title.clone(),
+                                url: url.to_string(),
+                                host: data.host.clone(),
+                            }),
                             _ => None,
                         }
                     })
@@ -0,0 +1,148 @@
+use std::collections::{BTreeMap, HashMap};
+
+use indextree::NodeId;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    data::{BookmarkData, DomainBookmarkData, DomainGroupData},
+    error::Error,
+};
+
+use super::Bookmarks;
+
+/// How bookmarks are grouped by their host
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum DomainGrouping {
+    /// Same host, ignoring `www.` (e.g. `docs.rs` and `www.docs.rs`)
+    #[default]
+    Host,
+    /// Same registrable domain (e.g. `gist.github.com` and `github.com`)
+    RegistrableDomain,
+}
+
+impl DomainGrouping {
+    /// Domain of the bookmark, None for bookmarks without a host
+    fn domain_of(&self, data: &BookmarkData) -> Option<String> {
+        let host = data.host.as_deref()?;
+        let key = parus_url::host_key(host);
+        match self {
+            Self::Host => Some(key),
+            Self::RegistrableDomain => Some(parus_url::registrable_domain(&key).unwrap_or(key)),
+        }
+    }
+}
+
+/// Domains
+impl Bookmarks {
+    /// Group the bookmarks under the node by domain.
+    /// Domains with more bookmarks come first, bookmarks are in the tree order.
+    /// Bookmarks without a host (e.g. bookmarklets) are not included.
+    pub fn group_by_domain(
+        &self,
+        index: usize,
+        grouping: DomainGrouping,
+    ) -> Result<Vec<DomainGroupData>, Error> {
+        let node_id = self.find_node_id_by_index(index)?;
+        let mut groups: HashMap<String, Vec<DomainBookmarkData>> = HashMap::new();
+        for descendant in node_id.descendants(&self.arena) {
+            let Ok(node) = self.find_node_by_node_id(descendant) else {
+                continue;
+            };
+            let data = node.get();
+            let (true, Some(url), Some(domain)) =
+                (data.is_bookmark(), &data.url, grouping.domain_of(data))
+            else {
+                continue;
+            };
+            groups.entry(domain).or_default().push(DomainBookmarkData {
+                index: descendant.into(),
+                title: data.title.clone(),
+                url: url.to_string(),
+                host: data.host.clone(),
+            });
+        }
+
+        let mut groups = groups
+            .into_iter()
+            .map(|(domain, bookmarks)| DomainGroupData {
+                display_domain: parus_url::display_host(&domain),
+                domain,
+                count: bookmarks.len(),
+                bookmarks,
+            })
+            .collect::<Vec<_>>();
+        groups.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.domain.cmp(&b.domain)));
+        Ok(groups)
+    }
+
+    /// Move the bookmarks directly under the folder into a subfolder per domain.
+    /// Subfolders are titled by the domain in Unicode, an existing subfolder of the same title is reused.
+    /// Returns the number of moved bookmarks.
+    pub fn organize_by_domain(
+        &mut self,
+        index: usize,
+        grouping: DomainGrouping,
+    ) -> Result<usize, Error> {
+        let folder_id = self.find_writable_node_id_by_index(index)?;
+
+        // keep the order of the bookmarks in each domain, domains are sorted by title
+        let mut domains: BTreeMap<String, Vec<NodeId>> = BTreeMap::new();
+        for child in folder_id.children(&self.arena) {
+            let Ok(node) = self.find_node_by_node_id(child) else {
+                continue;
+            };
+            let data = node.get();
+            if !data.is_bookmark() {
+                continue;
+            }
+            if let Some(domain) = grouping.domain_of(data) {
+                domains
+                    .entry(parus_url::display_host(&domain))
+                    .or_default()
+                    .push(child);
+            }
+        }
+
+        let mut moved = 0;
+        for (title, bookmarks) in domains {
+            let subfolder_id = match self.find_subfolder(folder_id, &title) {
+                Some(subfolder_id) => subfolder_id,
+                None => {
+                    let subfolder_id = self.arena.new_node(BookmarkData::new_folder(&title));
+                    folder_id.checked_append(subfolder_id, &mut self.arena)?;
+                    subfolder_id
+                }
+            };
+            for bookmark_id in bookmarks {
+                bookmark_id.detach(&mut self.arena);
+                subfolder_id.checked_append(bookmark_id, &mut self.arena)?;
+                if let Some(node) = self.arena.get_mut(bookmark_id) {
+                    node.get_mut().touch();
+                }
+                moved += 1;
+            }
+        }
+        Ok(moved)
+    }
+
+    /// One bookmark URL of each host in the tree, e.g. to prefetch favicons per host
+    pub fn favicon_urls(&self) -> Result<Vec<String>, Error> {
+        let root_id = self.get_root_node_id()?;
+        let urls = self
+            .group_by_domain(root_id.into(), DomainGrouping::Host)?
+            .into_iter()
+            .filter_map(|group| group.bookmarks.into_iter().next())
+            .map(|bookmark| bookmark.url)
+            .collect();
+        Ok(urls)
+    }
+
+    /// Folder directly under the parent with the title
+    fn find_subfolder(&self, parent_id: NodeId, title: &str) -> Option<NodeId> {
+        parent_id.children(&self.arena).find(|node_id| {
+            self.arena
+                .get(*node_id)
+                .is_some_and(|node| node.get().is_folder() && node.get().title == title)
+        })
+    }
+}
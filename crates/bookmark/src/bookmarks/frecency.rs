@@ -0,0 +1,138 @@
+use std::cmp::Reverse;
+
+use indextree::NodeId;
+use url::Url;
+
+use crate::{
+    data::{BookmarkData, NodeType, SearchResultData},
+    error::Error,
+    utils::{get_unix_timestamp, is_same_document},
+};
+
+use super::Bookmarks;
+
+/// Visits
+impl Bookmarks {
+    /// Record a visit on all bookmarks pointing to the given URL.
+    /// Returns the indexes of the updated bookmarks.
+    pub fn record_visit(&mut self, url: &str) -> Result<Vec<usize>, Error> {
+        let now = get_unix_timestamp().unwrap_or_default();
+        self.record_visit_at(url, now)
+    }
+
+    /// Record a visit at the given unix timestamp in milliseconds
+    pub fn record_visit_at(&mut self, url: &str, timestamp: u64) -> Result<Vec<usize>, Error> {
+        let visited = Url::parse(url)?;
+        let node_ids = self.find_bookmark_node_ids_by_url(&visited)?;
+        for node_id in node_ids.iter() {
+            if let Some(node) = self.arena.get_mut(*node_id) {
+                node.get_mut().record_visit(timestamp);
+            }
+        }
+        Ok(node_ids.into_iter().map(|node_id| node_id.into()).collect())
+    }
+
+    /// Find bookmarks whose URL equals the given URL (fragment is ignored)
+    fn find_bookmark_node_ids_by_url(&self, url: &Url) -> Result<Vec<NodeId>, Error> {
+        let root_id = self.get_root_node_id()?;
+        let node_ids = root_id
+            .descendants(&self.arena)
+            .filter(|node_id| {
+                self.find_node_by_node_id(*node_id)
+                    .map(|node| {
+                        let data = node.get();
+                        data.is_bookmark()
+                            && data.url.as_ref().is_some_and(|u| is_same_document(u, url))
+                    })
+                    .unwrap_or(false)
+            })
+            .collect::<Vec<_>>();
+        Ok(node_ids)
+    }
+}
+
+/// Frecency
+impl Bookmarks {
+    /// Search bookmarks whose title or URL contains the query (case-insensitive).
+    /// Results are ordered by frecency, so frequently and recently visited bookmarks come first.
+    /// An empty query matches all bookmarks.
+    pub fn search(&self, query: &str) -> Result<Vec<SearchResultData>, Error> {
+        let now = get_unix_timestamp().unwrap_or_default();
+        let query = query.trim().to_lowercase();
+        let root_id = self.get_root_node_id()?;
+
+        let mut results = root_id
+            .descendants(&self.arena)
+            .filter_map(|node_id| {
+                let data = self.find_node_by_node_id(node_id).ok()?.get();
+                let url = data.url.as_ref().filter(|_| data.is_bookmark())?;
+                if !query.is_empty()
+                    && !data.title.to_lowercase().contains(&query)
+                    && !url.as_str().to_lowercase().contains(&query)
+                {
+                    return None;
+                }
+                Some((
+                    SearchResultData {
+                        index: node_id.into(),
+                        title: data.title.clone(),
+                        url: url.to_string(),
+                        host: data.host.clone(),
+                        frecency: data.frecency(now),
+                    },
+                    data.last_visited,
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        results.sort_by_key(|(result, last_visited)| {
+            (Reverse(result.frecency), Reverse(*last_visited))
+        });
+
+        Ok(results.into_iter().map(|(result, _)| result).collect())
+    }
+
+    /// Sort children of the given node by frecency.
+    /// Folders are kept at the front in their original order, followed by the bookmarks.
+    /// Separators stay at their positions, so they still split the children at the same places.
+    pub fn sort_by_frecency(&mut self, index: usize) -> Result<(), Error> {
+        let now = get_unix_timestamp().unwrap_or_default();
+        let parent_id = self.find_node_id_by_index(index)?;
+
+        let children = parent_id
+            .children(&self.arena)
+            .filter_map(|node_id| Some((node_id, self.find_node_by_node_id(node_id).ok()?.get())))
+            .collect::<Vec<_>>();
+        let is_separator = |data: &BookmarkData| data.node_type == NodeType::Separator;
+
+        let mut bookmarks = children
+            .iter()
+            .filter(|(_, data)| data.is_bookmark())
+            .map(|(node_id, data)| (*node_id, data.frecency(now)))
+            .collect::<Vec<_>>();
+        bookmarks.sort_by_key(|(_, frecency)| Reverse(*frecency));
+        let mut sorted = children
+            .iter()
+            .filter(|(_, data)| !data.is_bookmark() && !is_separator(data))
+            .map(|(node_id, _)| *node_id)
+            .chain(bookmarks.into_iter().map(|(node_id, _)| node_id));
+
+        let order = children
+            .iter()
+            .filter_map(|(node_id, data)| {
+                if is_separator(data) {
+                    Some(*node_id)
+                } else {
+                    sorted.next()
+                }
+            })
+            .collect::<Vec<_>>();
+
+        for node_id in order {
+            node_id.detach(&mut self.arena);
+            parent_id.checked_append(node_id, &mut self.arena)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+
+use indextree::NodeId;
+use serde::Serialize;
+
+use crate::{
+    data::{BookmarkData, NodeType},
+    error::Error,
+};
+
+use super::Bookmarks;
+
+/// Number of nodes created by an import
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct ImportSummary {
+    pub bookmarks: usize,
+    pub folders: usize,
+    /// Links whose scheme is not allowed by the scheme policy, e.g. `place:`
+    pub skipped: usize,
+}
+
+/// Netscape bookmark file format, which is supported by most browsers
+impl Bookmarks {
+    /// Export the children of the node as a Netscape bookmark file.
+    /// Smart folders are skipped because their children are computed.
+    /// A bookmark having children, which only exists in files not migrated yet,
+    /// is written as a folder containing the bookmark and its children.
+    pub fn to_html(&self, index: usize) -> Result<String, Error> {
+        let node_id = self.find_node_id_by_index(index)?;
+        let mut html = String::from(
+            "<!DOCTYPE NETSCAPE-Bookmark-file-1>\n\
+             <!-- This is an automatically generated file.\n     \
+             It will be read and overwritten.\n     \
+             DO NOT EDIT! -->\n\
+             <META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">\n\
+             <TITLE>Bookmarks</TITLE>\n\
+             <H1>Bookmarks</H1>\n",
+        );
+        self.write_html_list(&mut html, node_id, 0);
+        Ok(html)
+    }
+
+    fn write_html_list(&self, html: &mut String, node_id: NodeId, depth: usize) {
+        let indent = "    ".repeat(depth);
+        html.push_str(&format!("{indent}<DL><p>\n"));
+        self.write_html_children(html, node_id, depth);
+        html.push_str(&format!("{indent}</DL><p>\n"));
+    }
+
+    fn write_html_children(&self, html: &mut String, node_id: NodeId, depth: usize) {
+        for child in node_id.children(&self.arena) {
+            let Some(node) = self.arena.get(child) else {
+                continue;
+            };
+            let data = node.get();
+            let inner = "    ".repeat(depth + 1);
+            match data.node_type {
+                NodeType::Bookmark if child.children(&self.arena).next().is_some() => {
+                    // same as the folder created by Bookmarks::lift_nested_bookmarks
+                    let attrs = data
+                        .date_added
+                        .map(|date_added| format!(" ADD_DATE=\"{}\"", date_added / 1000))
+                        .unwrap_or_default();
+                    html.push_str(&format!(
+                        "{inner}<DT><H3{attrs}>{}</H3>\n",
+                        escape_html(&data.title)
+                    ));
+                    html.push_str(&format!("{inner}<DL><p>\n"));
+                    self.write_html_bookmark(html, data, depth + 2);
+                    self.write_html_children(html, child, depth + 1);
+                    html.push_str(&format!("{inner}</DL><p>\n"));
+                }
+                NodeType::Bookmark => self.write_html_bookmark(html, data, depth + 1),
+                NodeType::Folder => {
+                    let mut attrs = String::new();
+                    push_date_attrs(&mut attrs, data);
+                    if data.is_toolbar_folder() {
+                        attrs.push_str(" PERSONAL_TOOLBAR_FOLDER=\"true\"");
+                    }
+                    html.push_str(&format!(
+                        "{inner}<DT><H3{attrs}>{}</H3>\n",
+                        escape_html(&data.title)
+                    ));
+                    self.write_html_list(html, child, depth + 1);
+                }
+                NodeType::Separator => html.push_str(&format!("{inner}<HR>\n")),
+                NodeType::Root | NodeType::SmartFolder => {}
+            }
+        }
+    }
+
+    fn write_html_bookmark(&self, html: &mut String, data: &BookmarkData, depth: usize) {
+        let Some(url) = &data.url else {
+            return;
+        };
+        let mut attrs = format!("HREF=\"{}\"", escape_html(url.as_str()));
+        push_date_attrs(&mut attrs, data);
+        if !data.tags.is_empty() {
+            attrs.push_str(&format!(" TAGS=\"{}\"", escape_html(&data.tags.join(","))));
+        }
+        if let Some(keyword) = &data.keyword {
+            attrs.push_str(&format!(" SHORTCUTURL=\"{}\"", escape_html(keyword)));
+        }
+        html.push_str(&format!(
+            "{}<DT><A {attrs}>{}</A>\n",
+            "    ".repeat(depth),
+            escape_html(&data.title)
+        ));
+    }
+
+    /// Import a Netscape bookmark file into the node.
+    /// The toolbar folder of the file is merged into the toolbar when importing into the root.
+    pub fn import_html(&mut self, html: &str, parent_index: usize) -> Result<ImportSummary, Error> {
+        let parent_id = self.find_writable_node_id_by_index(parent_index)?;
+        let toolbar_id = self
+            .get_toolbar_node_id()
+            .ok()
+            .filter(|_| parent_index == 1);
+
+        let mut summary = ImportSummary::default();
+        // nodes whose children are being read, the parent itself is pushed for the top level list
+        let mut stack: Vec<NodeId> = Vec::new();
+        // last created folder, the next list contains its children.
+        // A list after a link is read into the current folder because bookmarks are leaf nodes.
+        let mut last: Option<NodeId> = None;
+        let mut rest = html;
+
+        while let Some(start) = rest.find('<') {
+            rest = &rest[start..];
+            let Some(end) = rest.find('>') else {
+                break;
+            };
+            let (name, attrs) = parse_tag(&rest[1..end]);
+            rest = &rest[end + 1..];
+            let current = stack.last().copied().unwrap_or(parent_id);
+
+            match name.as_str() {
+                "DL" => stack.push(last.take().unwrap_or(current)),
+                "/DL" => {
+                    stack.pop();
+                    last = None;
+                }
+                "H3" | "A" => {
+                    let close = format!("</{name}>");
+                    let text_end = find_ignore_ascii_case(rest, &close).unwrap_or(rest.len());
+                    let title = unescape_html(rest[..text_end].trim());
+                    rest = &rest[(text_end + close.len()).min(rest.len())..];
+
+                    if name == "H3" {
+                        if let Some(toolbar_id) = toolbar_id.filter(|_| {
+                            attrs.contains_key("PERSONAL_TOOLBAR_FOLDER") && current == parent_id
+                        }) {
+                            last = Some(toolbar_id);
+                            continue;
+                        }
+                        let mut data = BookmarkData::new_folder(&title);
+                        apply_date_attrs(&mut data, &attrs);
+                        let node_id = self.arena.new_node(data);
+                        current.checked_append(node_id, &mut self.arena)?;
+                        summary.folders += 1;
+                        last = Some(node_id);
+                    } else {
+                        let href = attrs.get("HREF").map(String::as_str).unwrap_or_default();
+                        let Ok(mut data) = self.new_bookmark_data(&title, href) else {
+                            summary.skipped += 1;
+                            last = None;
+                            continue;
+                        };
+                        apply_date_attrs(&mut data, &attrs);
+                        if let Some(tags) = attrs.get("TAGS") {
+                            data.tags = tags
+                                .split(',')
+                                .map(|tag| tag.trim().to_string())
+                                .filter(|tag| !tag.is_empty())
+                                .collect();
+                        }
+                        let node_id = self.arena.new_node(data);
+                        current.checked_append(node_id, &mut self.arena)?;
+                        if let Some(keyword) = attrs.get("SHORTCUTURL") {
+                            // keywords already used are dropped
+                            let _ = self.set_keyword(node_id.into(), Some(keyword.clone()));
+                        }
+                        summary.bookmarks += 1;
+                        last = None;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(summary)
+    }
+}
+
+fn push_date_attrs(attrs: &mut String, data: &BookmarkData) {
+    // Netscape format uses seconds
+    if let Some(date_added) = data.date_added {
+        attrs.push_str(&format!(" ADD_DATE=\"{}\"", date_added / 1000));
+    }
+    if let Some(date_modified) = data.date_modified {
+        attrs.push_str(&format!(" LAST_MODIFIED=\"{}\"", date_modified / 1000));
+    }
+}
+
+fn apply_date_attrs(data: &mut BookmarkData, attrs: &HashMap<String, String>) {
+    let parse = |name: &str| {
+        attrs
+            .get(name)
+            .and_then(|value| value.parse::<u64>().ok())
+            .map(|seconds| seconds * 1000)
+    };
+    if let Some(date_added) = parse("ADD_DATE") {
+        data.date_added = Some(date_added);
+    }
+    if let Some(date_modified) = parse("LAST_MODIFIED") {
+        data.date_modified = Some(date_modified);
+    }
+}
+
+/// Split a tag like `A HREF="https://example.com/" ADD_DATE="0"` into the upper case name and attributes
+fn parse_tag(tag: &str) -> (String, HashMap<String, String>) {
+    let tag = tag.trim();
+    let (name, mut rest) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+    let mut attrs = HashMap::new();
+
+    loop {
+        rest = rest.trim_start();
+        let Some(eq) = rest.find('=') else {
+            break;
+        };
+        let key = rest[..eq].trim().to_ascii_uppercase();
+        rest = rest[eq + 1..].trim_start();
+        let value = match rest.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let value_end = rest[1..].find(quote).map(|i| i + 1).unwrap_or(rest.len());
+                let value = &rest[1..value_end];
+                rest = &rest[(value_end + 1).min(rest.len())..];
+                value
+            }
+            _ => {
+                let value_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                let value = &rest[..value_end];
+                rest = &rest[value_end..];
+                value
+            }
+        };
+        attrs.insert(key, unescape_html(value));
+    }
+
+    (name.to_ascii_uppercase(), attrs)
+}
+
+fn find_ignore_ascii_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack
+        .as_bytes()
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn unescape_html(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tag() {
+        let (name, attrs) = parse_tag(r#"a href="https://example.com/?a=1&amp;b=2" ADD_DATE=10"#);
+        assert_eq!(name, "A");
+        assert_eq!(attrs["HREF"], "https://example.com/?a=1&b=2");
+        assert_eq!(attrs["ADD_DATE"], "10");
+
+        let (name, attrs) = parse_tag("/DL");
+        assert_eq!(name, "/DL");
+        assert!(attrs.is_empty());
+    }
+}
@@ -2,7 +2,11 @@ use std::{fs::File, io::BufReader, path::Path};
 
 use indextree::Arena;
 
-use crate::{data::BookmarkData, error::Error};
+use crate::{
+    crypto::{self, EncryptionKey},
+    data::BookmarkData,
+    error::Error,
+};
 
 use super::Bookmarks;
 
@@ -29,3 +33,51 @@ impl Bookmarks {
         Ok(())
     }
 }
+
+/// Encrypted file I/O
+impl Bookmarks {
+    /// Whether the file is encrypted
+    pub fn is_encrypted_file<P>(path: P) -> Result<bool, Error>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(crypto::is_encrypted(&std::fs::read(path)?))
+    }
+
+    /// Load Arena from an encrypted file.
+    /// Returns the derived key as well to save the file again without asking for the passphrase.
+    pub fn load_from_encrypted_file<P>(
+        path: P,
+        passphrase: &str,
+    ) -> Result<(Self, EncryptionKey), Error>
+    where
+        P: AsRef<Path>,
+    {
+        let data = std::fs::read(path)?;
+        let (plaintext, key) = crypto::decrypt(&data, passphrase)?;
+        let arena: Arena<BookmarkData> = serde_json::from_slice(&plaintext)
+            .map_err(|e| Error::CorruptedEncryptedFile(e.to_string()))?;
+        Ok((Self::new(arena), key))
+    }
+
+    /// Load Arena from a file encrypted with the key, e.g. the sync base of an unlocked collection
+    pub fn load_from_encrypted_file_with_key<P>(path: P, key: &EncryptionKey) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let plaintext = key.decrypt(&std::fs::read(path)?)?;
+        let arena: Arena<BookmarkData> = serde_json::from_slice(&plaintext)
+            .map_err(|e| Error::CorruptedEncryptedFile(e.to_string()))?;
+        Ok(Self::new(arena))
+    }
+
+    /// Save Arena to an encrypted file
+    pub fn save_to_encrypted_file<P>(&self, path: P, key: &EncryptionKey) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+    {
+        let plaintext = serde_json::to_vec(&self.arena)?;
+        std::fs::write(path, key.encrypt(&plaintext)?)?;
+        Ok(())
+    }
+}
@@ -1,4 +1,6 @@
-use crate::error::Error;
+use indextree::Arena;
+
+use crate::{data::BookmarkData, error::Error};
 
 use super::Bookmarks;
 use super::NestedBookmark;
@@ -10,6 +12,12 @@ impl Bookmarks {
         Ok(serde_json::to_string(&self.arena)?)
     }
 
+    /// JSON string to Arena
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let arena: Arena<BookmarkData> = serde_json::from_str(json)?;
+        Ok(Self::new(arena))
+    }
+
     /// Arena to NestedBookmarks
     pub fn to_nested_bookmark(&self, index: usize) -> Result<NestedBookmark, Error> {
         let node_id = self.find_node_id_by_index(index)?;
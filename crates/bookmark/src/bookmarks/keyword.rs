@@ -0,0 +1,85 @@
+use url::form_urlencoded;
+
+use crate::error::Error;
+
+use super::Bookmarks;
+
+/// Placeholder replaced with the percent-encoded arguments
+const ENCODED_PLACEHOLDER: &str = "%s";
+/// Placeholder replaced with the raw arguments
+const RAW_PLACEHOLDER: &str = "%S";
+
+/// Keywords (Firefox-style quick navigation)
+impl Bookmarks {
+    /// Set or clear the keyword of a bookmark.
+    /// Keywords are case-insensitive, must not contain whitespace, and must be unique.
+    pub fn set_keyword(&mut self, index: usize, keyword: Option<String>) -> Result<(), Error> {
+        let node = self.find_node_by_index(index)?;
+        if !node.get().is_bookmark() {
+            return Err(Error::NotBookmark(index));
+        }
+
+        let keyword = match keyword.map(|k| k.trim().to_lowercase()) {
+            Some(k) if k.is_empty() || k.contains(char::is_whitespace) => {
+                return Err(Error::InvalidKeyword(k));
+            }
+            Some(k) => {
+                if let Some(other) = self.find_index_by_keyword(&k) {
+                    if other != index {
+                        return Err(Error::KeywordAlreadyUsed(k));
+                    }
+                }
+                Some(k)
+            }
+            None => None,
+        };
+
+        let node = self.get_mut_node_by_index(index)?;
+        let data = node.get_mut();
+        data.keyword = keyword;
+        data.touch();
+        Ok(())
+    }
+
+    /// Find the index of the bookmark having the keyword.
+    /// Both sides are lowercased as in [`Bookmarks::set_keyword`], so non-ASCII keywords match too.
+    pub fn find_index_by_keyword(&self, keyword: &str) -> Option<usize> {
+        let keyword = keyword.to_lowercase();
+        let root_id = self.get_root_node_id().ok()?;
+        root_id
+            .descendants(&self.arena)
+            .find(|node_id| {
+                self.find_node_by_node_id(*node_id).is_ok_and(|node| {
+                    node.get()
+                        .keyword
+                        .as_ref()
+                        .is_some_and(|k| k.to_lowercase() == keyword)
+                })
+            })
+            .map(|node_id| node_id.into())
+    }
+
+    /// Resolve an input like `rs serde` into a URL using the bookmark keyword.
+    /// `%s` in the bookmark URL is replaced with the percent-encoded arguments and `%S` with the raw arguments.
+    /// Returns None if the first word of the input is not a keyword.
+    pub fn resolve_keyword(&self, input: &str) -> Option<String> {
+        let input = input.trim();
+        let (keyword, args) = match input.split_once(char::is_whitespace) {
+            Some((keyword, args)) => (keyword, args.trim()),
+            None => (input, ""),
+        };
+        if keyword.is_empty() {
+            return None;
+        }
+
+        let index = self.find_index_by_keyword(keyword)?;
+        let url = self.find_node_by_index(index).ok()?.get().url.as_ref()?;
+
+        let encoded_args = form_urlencoded::byte_serialize(args.as_bytes()).collect::<String>();
+        let resolved = url
+            .as_str()
+            .replace(ENCODED_PLACEHOLDER, &encoded_args)
+            .replace(RAW_PLACEHOLDER, args);
+        Some(resolved)
+    }
+}
@@ -1,7 +1,7 @@
 use indextree::NodeId;
 use url::Url;
 
-use crate::{data::BookmarkData, error::Error};
+use crate::{data::BookmarkData, error::Error, query::SmartQuery};
 
 use super::Bookmarks;
 
@@ -28,6 +28,28 @@ impl Bookmarks {
         let node = self.get_mut_node_by_index(index)?;
         let data = node.get_mut();
         data.title = title;
+        data.touch();
+        Ok(())
+    }
+
+    /// Set tags
+    pub fn set_tags(&mut self, index: usize, tags: Vec<String>) -> Result<(), Error> {
+        let node = self.get_mut_node_by_index(index)?;
+        let data = node.get_mut();
+        data.tags = tags;
+        data.touch();
+        Ok(())
+    }
+
+    /// Update the saved query of a smart folder
+    pub fn update_smart_query(&mut self, index: usize, query: SmartQuery) -> Result<(), Error> {
+        let node = self.get_mut_node_by_index(index)?;
+        let data = node.get_mut();
+        if !data.is_smart_folder() {
+            return Err(Error::NotSmartFolder(index));
+        }
+        data.query = Some(query);
+        data.touch();
         Ok(())
     }
 }
@@ -36,74 +58,107 @@ impl Bookmarks {
 impl Bookmarks {
     /// Add folder
     pub fn add_folder(&mut self, parent_index: usize, title: &str) -> Result<usize, Error> {
-        let parent_node_id = self.find_node_id_by_index(parent_index)?;
+        let parent_node_id = self.find_writable_node_id_by_index(parent_index)?;
         let new_folder = BookmarkData::new_folder(title);
         let new_node = self.arena.new_node(new_folder);
         parent_node_id.checked_append(new_node, &mut self.arena)?;
         Ok(new_node.into())
     }
 
-    /// Adds a new bookmark by comparing paths between the given URL and existing URLs
-    /// If no matching URL is found, adds a new node to the top level node
+    /// Append bookmark as the last child of the parent
+    pub fn append_bookmark(
+        &mut self,
+        parent_index: usize,
+        title: &str,
+        url: &str,
+    ) -> Result<usize, Error> {
+        let parent_node_id = self.find_writable_node_id_by_index(parent_index)?;
+        let bookmark = self.new_bookmark_data(title, url)?;
+        let new_node = self.arena.new_node(bookmark);
+        parent_node_id.checked_append(new_node, &mut self.arena)?;
+        Ok(new_node.into())
+    }
+
+    /// Add smart folder
+    pub fn add_smart_folder(
+        &mut self,
+        parent_index: usize,
+        title: &str,
+        query: SmartQuery,
+    ) -> Result<usize, Error> {
+        let parent_node_id = self.find_writable_node_id_by_index(parent_index)?;
+        let new_folder = BookmarkData::new_smart_folder(title, query);
+        let new_node = self.arena.new_node(new_folder);
+        parent_node_id.checked_append(new_node, &mut self.arena)?;
+        Ok(new_node.into())
+    }
+
+    /// Adds a new bookmark by comparing paths between the given URL and existing URLs.
+    /// The new bookmark is placed after the matching bookmark,
+    /// if no matching URL is found, adds a new node to the top level node
     pub fn add_bookmark(
         &mut self,
         title: &str,
         url: &str,
         top_level_index: usize,
     ) -> Result<usize, Error> {
-        let bookmark = BookmarkData::try_new_bookmark(title, url)?;
+        let bookmark = self.new_bookmark_data(title, url)?;
 
         // get the URL of one level above the given URL as base_url_str
         // https://docs.rs/tauri/latest/tauri/webview/struct.Color.html
         // -> https://docs.rs/tauri/latest/tauri/webview/
-        let parsed_url = Url::parse(url)?;
-        let mut base_url = parsed_url.clone();
-        base_url
-            .path_segments_mut()
-            .map_err(|_| Error::CannotBeBase())?
-            .pop_if_empty()
-            .pop();
+        // URLs without a path such as `javascript:` and `about:` are added to the top level node
+        let mut base_url = Url::parse(url)?;
+        let has_path = match base_url.path_segments_mut() {
+            Ok(mut segments) => {
+                segments.pop_if_empty().pop();
+                true
+            }
+            Err(_) => false,
+        };
         let base_url_str = base_url.as_str();
 
-        let top_node_id = self.find_node_id_by_index(top_level_index)?;
+        let top_node_id = self.find_writable_node_id_by_index(top_level_index)?;
         let mut target: Option<NodeId> = None;
-        if let Ok(toolbar_node_id) = self.get_toolbar_node_id() {
-            // if toolbar node is found, find target node from descendants except toolbar node
-            for descendant in top_node_id.descendants(&self.arena) {
-                if toolbar_node_id
-                    .descendants(&self.arena)
-                    .any(|node_id| node_id == descendant)
-                {
-                    continue;
-                }
+        if has_path {
+            if let Ok(toolbar_node_id) = self.get_toolbar_node_id() {
+                // if toolbar node is found, find target node from descendants except toolbar node
+                for descendant in top_node_id.descendants(&self.arena) {
+                    if toolbar_node_id
+                        .descendants(&self.arena)
+                        .any(|node_id| node_id == descendant)
+                    {
+                        continue;
+                    }
 
-                if let Ok(node) = self.find_node_by_node_id(descendant) {
-                    if let Some(node_url) = &node.get().url {
-                        if node_url.as_str().starts_with(base_url_str) {
-                            target = Some(descendant);
-                            break;
+                    if let Ok(node) = self.find_node_by_node_id(descendant) {
+                        if let Some(node_url) = &node.get().url {
+                            if node_url.as_str().starts_with(base_url_str) {
+                                target = Some(descendant);
+                                break;
+                            }
                         }
                     }
                 }
-            }
-        } else {
-            // if toolbar node is not found, find target node from all descendants
-            target = top_node_id.descendants(&self.arena).find(|node_id| {
-                if let Ok(node) = self.find_node_by_node_id(*node_id) {
-                    if let Some(node_url) = &node.get().url {
-                        if node_url.as_str().starts_with(base_url_str) {
-                            return true;
+            } else {
+                // if toolbar node is not found, find target node from all descendants
+                target = top_node_id.descendants(&self.arena).find(|node_id| {
+                    if let Ok(node) = self.find_node_by_node_id(*node_id) {
+                        if let Some(node_url) = &node.get().url {
+                            if node_url.as_str().starts_with(base_url_str) {
+                                return true;
+                            }
                         }
                     }
-                }
-                false
-            });
+                    false
+                });
+            }
         }
 
         let new_node = self.arena.new_node(bookmark);
         if let Some(target) = target {
-            // if found target, append new node to the target node
-            target.checked_append(new_node, &mut self.arena)?;
+            // if found target, insert new node next to the target bookmark in the same folder
+            target.checked_insert_after(new_node, &mut self.arena)?;
         } else {
             // if not found target, append new node to the top node
             top_node_id.checked_append(new_node, &mut self.arena)?;
@@ -121,7 +176,7 @@ impl Bookmarks {
     /// Add bookmark to Toolbar folder
     pub fn append_bookmark_to_toolbar(&mut self, title: &str, url: &str) -> Result<(), Error> {
         let toolbar_id = self.get_toolbar_node_id()?;
-        let bookmark = BookmarkData::try_new_bookmark(title, url)?;
+        let bookmark = self.new_bookmark_data(title, url)?;
         let new_node = self.arena.new_node(bookmark);
         toolbar_id.checked_append(new_node, &mut self.arena)?;
         Ok(())
@@ -159,6 +214,19 @@ impl Bookmarks {
         Ok((source_node_id, dest_node_id))
     }
 
+    /// Nodes can't be placed next to a node inside a smart folder, whose children are computed
+    fn validate_sibling_destination(&self, dest_node_id: NodeId) -> Result<(), Error> {
+        let parent = self
+            .arena
+            .get(dest_node_id)
+            .and_then(|node| node.parent())
+            .and_then(|parent| self.arena.get(parent));
+        if parent.is_some_and(|parent| parent.get().is_smart_folder()) {
+            return Err(Error::SmartFolderIsReadOnly());
+        }
+        Ok(())
+    }
+
     /// Insert after the source node to the destination node
     pub fn insert_after(
         &mut self,
@@ -173,8 +241,10 @@ impl Bookmarks {
             dest_node_id.checked_prepend(source_node_id, &mut self.arena)?;
         } else {
             // insert after target node
+            self.validate_sibling_destination(dest_node_id)?;
             dest_node_id.checked_insert_after(source_node_id, &mut self.arena)?;
         }
+        self.touch_node(source_node_id);
 
         Ok(())
     }
@@ -189,7 +259,9 @@ impl Bookmarks {
             self.validate_movable(source_index, destination_index)?;
 
         // insert before target node
+        self.validate_sibling_destination(dest_node_id)?;
         dest_node_id.checked_insert_before(source_node_id, &mut self.arena)?;
+        self.touch_node(source_node_id);
 
         Ok(())
     }
@@ -202,9 +274,11 @@ impl Bookmarks {
     ) -> Result<(), Error> {
         let (source_node_id, dest_node_id) =
             self.validate_movable(source_index, destination_index)?;
+        self.find_writable_node_id_by_index(destination_index)?;
 
         // move to the dest children (append - to the end)
         dest_node_id.checked_append(source_node_id, &mut self.arena)?;
+        self.touch_node(source_node_id);
 
         Ok(())
     }
@@ -217,6 +291,7 @@ impl Bookmarks {
     ) -> Result<(), Error> {
         let (source_node_id, dest_node_id) =
             self.validate_movable(source_index, destination_index)?;
+        self.find_writable_node_id_by_index(destination_index)?;
 
         // check that source node is not the first child of the dest node
         let dest_first_child = dest_node_id.children(&self.arena).next();
@@ -226,9 +301,17 @@ impl Bookmarks {
 
         // move to the dest children (prepend - to the front)
         dest_node_id.checked_prepend(source_node_id, &mut self.arena)?;
+        self.touch_node(source_node_id);
 
         Ok(())
     }
+
+    /// Update date_modified of the moved node
+    fn touch_node(&mut self, node_id: NodeId) {
+        if let Some(node) = self.arena.get_mut(node_id) {
+            node.get_mut().touch();
+        }
+    }
 }
 
 /// Removing
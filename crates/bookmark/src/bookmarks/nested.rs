@@ -3,12 +3,15 @@ use serde::Serialize;
 
 use crate::data::BookmarkData;
 use crate::error::Error;
+use crate::utils::get_unix_timestamp;
 
 #[derive(Serialize)]
 pub struct NestedBookmark {
     index: usize,
     #[serde(flatten)]
     data: BookmarkData,
+    /// Host in Unicode for display
+    display_host: Option<String>,
     children: Vec<NestedBookmark>,
 }
 
@@ -17,13 +20,43 @@ impl NestedBookmark {
         let node = arena
             .get(node_id)
             .ok_or(Error::NestedNode(node_id.into()))?;
-        Ok(Self {
-            index: node_id.into(),
-            data: node.get().clone(),
-            children: node_id
+        let data = node.get();
+
+        let children = match &data.query {
+            // children of smart folder are computed from the query
+            Some(query) if data.is_smart_folder() => {
+                let now = get_unix_timestamp().unwrap_or_default();
+                query
+                    .find_matches(arena, now)
+                    .into_iter()
+                    .filter_map(|child| Self::try_new_leaf(arena, child).ok())
+                    .collect::<Vec<_>>()
+            }
+            _ => node_id
                 .children(arena)
                 .filter_map(|child| Self::try_new(arena, child).ok())
                 .collect::<Vec<_>>(),
+        };
+
+        Ok(Self {
+            index: node_id.into(),
+            display_host: data.display_host(),
+            data: data.clone(),
+            children,
+        })
+    }
+
+    /// Create NestedBookmark without children
+    fn try_new_leaf(arena: &Arena<BookmarkData>, node_id: NodeId) -> Result<Self, Error> {
+        let node = arena
+            .get(node_id)
+            .ok_or(Error::NestedNode(node_id.into()))?;
+        let data = node.get();
+        Ok(Self {
+            index: node_id.into(),
+            display_host: data.display_host(),
+            data: data.clone(),
+            children: Vec::new(),
         })
     }
 }
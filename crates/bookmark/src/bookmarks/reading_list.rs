@@ -0,0 +1,133 @@
+use std::cmp::Reverse;
+
+use indextree::NodeId;
+use url::Url;
+
+use crate::{
+    data::{BookmarkData, ReadingListItemData, READING_LIST_FOLDER_TITLE},
+    error::Error,
+    utils::is_same_document,
+};
+
+use super::Bookmarks;
+
+/// Reading list
+impl Bookmarks {
+    /// Get reading list node id
+    pub fn get_reading_list_node_id(&self) -> Result<NodeId, Error> {
+        let root_id = self.get_root_node_id()?;
+
+        // find a folder named "Reading List" under root
+        let reading_list_node_id = root_id.children(&self.arena).find(|node_id| {
+            self.find_node_by_node_id(*node_id)
+                .is_ok_and(|node| node.get().is_reading_list_folder())
+        });
+
+        reading_list_node_id.ok_or(Error::ReadingListFolderNotFound())
+    }
+
+    /// Get reading list node id, create the folder under root if not exists
+    fn get_or_create_reading_list_node_id(&mut self) -> Result<NodeId, Error> {
+        if let Ok(node_id) = self.get_reading_list_node_id() {
+            return Ok(node_id);
+        }
+        let root_id = self.get_root_node_id()?;
+        let folder = BookmarkData::new_folder(READING_LIST_FOLDER_TITLE);
+        let new_node = self.arena.new_node(folder);
+        root_id.checked_append(new_node, &mut self.arena)?;
+        Ok(new_node)
+    }
+
+    /// Add a page to the reading list as unread.
+    /// If the page is already in the reading list, it is marked as unread again.
+    pub fn add_to_reading_list(&mut self, title: &str, url: &str) -> Result<usize, Error> {
+        let bookmark = self.new_bookmark_data(title, url)?;
+        let reading_list_id = self.get_or_create_reading_list_node_id()?;
+
+        if let Some(node_id) = self
+            .find_reading_list_items_by_url(&Url::parse(url)?)?
+            .first()
+        {
+            let index: usize = (*node_id).into();
+            self.set_is_read(index, false)?;
+            return Ok(index);
+        }
+
+        let new_node = self.arena.new_node(bookmark);
+        reading_list_id.checked_append(new_node, &mut self.arena)?;
+        Ok(new_node.into())
+    }
+
+    /// Set is_read flag
+    pub fn set_is_read(&mut self, index: usize, is_read: bool) -> Result<(), Error> {
+        let node = self.get_mut_node_by_index(index)?;
+        let data = node.get_mut();
+        if !data.is_bookmark() {
+            return Err(Error::NotBookmark(index));
+        }
+        data.is_read = is_read;
+        data.touch();
+        Ok(())
+    }
+
+    /// Mark reading list items pointing to the given URL as read.
+    /// Returns the indexes of the updated items.
+    pub fn mark_read_by_url(&mut self, url: &str) -> Result<Vec<usize>, Error> {
+        let node_ids = match self.get_reading_list_node_id() {
+            Ok(_) => self.find_reading_list_items_by_url(&Url::parse(url)?)?,
+            Err(_) => Vec::new(),
+        };
+        let mut updated = Vec::new();
+        for node_id in node_ids {
+            let index: usize = node_id.into();
+            if !self.find_node_by_node_id(node_id)?.get().is_read {
+                self.set_is_read(index, true)?;
+                updated.push(index);
+            }
+        }
+        Ok(updated)
+    }
+
+    /// Get reading list items ordered by date added (newest first)
+    pub fn get_reading_list(&self, unread_only: bool) -> Vec<ReadingListItemData> {
+        let Ok(reading_list_id) = self.get_reading_list_node_id() else {
+            return Vec::new();
+        };
+
+        let mut items = reading_list_id
+            .descendants(&self.arena)
+            .filter_map(|node_id| {
+                let data = self.find_node_by_node_id(node_id).ok()?.get();
+                if !data.is_bookmark() || (unread_only && data.is_read) {
+                    return None;
+                }
+                Some(ReadingListItemData {
+                    index: node_id.into(),
+                    title: data.title.clone(),
+                    url: data.url.as_ref()?.to_string(),
+                    host: data.host.clone(),
+                    date_added: data.date_added,
+                    is_read: data.is_read,
+                })
+            })
+            .collect::<Vec<_>>();
+        items.sort_by_key(|item| Reverse(item.date_added));
+        items
+    }
+
+    /// Find reading list items whose URL equals the given URL (fragment is ignored)
+    fn find_reading_list_items_by_url(&self, url: &Url) -> Result<Vec<NodeId>, Error> {
+        let reading_list_id = self.get_reading_list_node_id()?;
+        let node_ids = reading_list_id
+            .descendants(&self.arena)
+            .filter(|node_id| {
+                self.find_node_by_node_id(*node_id).is_ok_and(|node| {
+                    let data = node.get();
+                    data.is_bookmark()
+                        && data.url.as_ref().is_some_and(|u| is_same_document(u, url))
+                })
+            })
+            .collect::<Vec<_>>();
+        Ok(node_ids)
+    }
+}
@@ -0,0 +1,160 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::{data::NodeType, error::Error, utils::year_month};
+
+use super::{domain::DomainGrouping, Bookmarks};
+
+/// Number of entries in the rankings of [`BookmarkStats`]
+const TOP_COUNT: usize = 10;
+
+/// Statistics of the bookmark tree, nodes not reachable from the root are counted as garbage
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct BookmarkStats {
+    pub bookmarks: usize,
+    /// Folders including the toolbar, without the root
+    pub folders: usize,
+    pub smart_folders: usize,
+    pub separators: usize,
+    /// Bookmarks never opened
+    pub never_visited: usize,
+    /// Number of bookmarks at each depth, the children of the root are at depth 1
+    pub depths: Vec<DepthCount>,
+    /// Folders with the most children
+    pub largest_folders: Vec<FolderSize>,
+    /// Bookmarks added in each month in UTC, bookmarks without `date_added` are not counted
+    pub additions_per_month: Vec<MonthCount>,
+    /// Hosts with the most bookmarks
+    pub top_hosts: Vec<HostCount>,
+    /// Nodes in the file including removed ones
+    pub total_nodes: usize,
+    /// Removed or detached nodes, which are dropped by [`Bookmarks::compact`]
+    pub garbage_nodes: usize,
+    /// `garbage_nodes / total_nodes`
+    pub garbage_ratio: f64,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct DepthCount {
+    pub depth: usize,
+    pub count: usize,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct FolderSize {
+    pub index: usize,
+    pub title: String,
+    pub children: usize,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct MonthCount {
+    /// `YYYY-MM`
+    pub month: String,
+    pub count: usize,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct HostCount {
+    pub host: String,
+    pub display_host: String,
+    pub count: usize,
+}
+
+/// Statistics
+impl Bookmarks {
+    pub fn stats(&self) -> Result<BookmarkStats, Error> {
+        let root_id = self.get_root_node_id()?;
+        let mut stats = BookmarkStats::default();
+        let mut depths: BTreeMap<usize, usize> = BTreeMap::new();
+        let mut months: BTreeMap<String, usize> = BTreeMap::new();
+        let mut folders = Vec::new();
+        let mut reachable = 0;
+
+        let mut stack = vec![(root_id, 0)];
+        while let Some((node_id, depth)) = stack.pop() {
+            let data = self.find_node_by_node_id(node_id)?.get();
+            reachable += 1;
+            stack.extend(
+                node_id
+                    .children(&self.arena)
+                    .map(|child| (child, depth + 1)),
+            );
+
+            match data.node_type {
+                NodeType::Bookmark => {
+                    stats.bookmarks += 1;
+                    *depths.entry(depth).or_default() += 1;
+                    if data.visit_count == 0 {
+                        stats.never_visited += 1;
+                    }
+                    if let Some(date_added) = data.date_added {
+                        *months.entry(year_month(date_added)).or_default() += 1;
+                    }
+                }
+                NodeType::Folder => {
+                    stats.folders += 1;
+                    folders.push(FolderSize {
+                        index: node_id.into(),
+                        title: data.title.clone(),
+                        children: node_id.children(&self.arena).count(),
+                    });
+                }
+                NodeType::SmartFolder => stats.smart_folders += 1,
+                NodeType::Separator => stats.separators += 1,
+                NodeType::Root => {}
+            }
+        }
+
+        stats.depths = depths
+            .into_iter()
+            .map(|(depth, count)| DepthCount { depth, count })
+            .collect();
+        stats.additions_per_month = months
+            .into_iter()
+            .map(|(month, count)| MonthCount { month, count })
+            .collect();
+
+        folders.sort_by(|a, b| b.children.cmp(&a.children).then(a.index.cmp(&b.index)));
+        folders.truncate(TOP_COUNT);
+        stats.largest_folders = folders;
+
+        stats.top_hosts = self
+            .group_by_domain(root_id.into(), DomainGrouping::Host)?
+            .into_iter()
+            .take(TOP_COUNT)
+            .map(|group| HostCount {
+                host: group.domain,
+                display_host: group.display_domain,
+                count: group.count,
+            })
+            .collect();
+
+        stats.total_nodes = self.arena.count();
+        stats.garbage_nodes = stats.total_nodes.saturating_sub(reachable);
+        stats.garbage_ratio = if stats.total_nodes == 0 {
+            0.0
+        } else {
+            stats.garbage_nodes as f64 / stats.total_nodes as f64
+        };
+
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_of_default() {
+        let stats = Bookmarks::default().stats().unwrap();
+        assert_eq!(stats.bookmarks, 0);
+        assert_eq!(stats.folders, 1);
+        assert_eq!(stats.largest_folders[0].title, "Toolbar");
+        assert!(stats.depths.is_empty());
+        assert_eq!(stats.total_nodes, 2);
+        assert_eq!(stats.garbage_ratio, 0.0);
+    }
+}
@@ -63,6 +63,47 @@ impl Bookmarks {
             .ok_or(Error::NodeNotFound(index))
     }
 
+    /// Find NodeId by index for a node that can have real children.
+    /// Smart folders are read-only because their children are computed, and bookmarks are leaf nodes.
+    pub(crate) fn find_writable_node_id_by_index(&self, index: usize) -> Result<NodeId, Error> {
+        let node = self.find_node_by_index(index)?;
+        if node.get().is_smart_folder() {
+            return Err(Error::SmartFolderIsReadOnly());
+        }
+        if !node.get().is_container() {
+            return Err(Error::NotContainer(index));
+        }
+        self.find_node_id_by_index(index)
+    }
+
+    /// Rebuild the arena in depth-first order without removed nodes and nodes detached from the root.
+    /// Indexes change after compaction. Returns the number of dropped slots.
+    pub fn compact(&mut self) -> Result<usize, Error> {
+        let root_id = self.get_root_node_id()?;
+        let mut arena = Arena::new();
+        let new_root_id = arena.new_node(self.find_node_by_node_id(root_id)?.get().clone());
+        // (source node, destination parent), children are pushed in reverse to copy in depth-first order
+        let mut stack = root_id
+            .children(&self.arena)
+            .rev()
+            .map(|child| (child, new_root_id))
+            .collect::<Vec<_>>();
+        while let Some((source, parent)) = stack.pop() {
+            let node_id = arena.new_node(self.find_node_by_node_id(source)?.get().clone());
+            parent.checked_append(node_id, &mut arena)?;
+            stack.extend(
+                source
+                    .children(&self.arena)
+                    .rev()
+                    .map(|child| (child, node_id)),
+            );
+        }
+
+        let dropped = self.arena.count() - arena.count();
+        self.arena = arena;
+        Ok(dropped)
+    }
+
     /// Get root node id (root node is always index 1)
     pub fn get_root_node_id(&self) -> Result<NodeId, Error> {
         self.find_node_id_by_index(1)
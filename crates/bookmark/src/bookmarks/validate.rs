@@ -0,0 +1,251 @@
+use std::fmt;
+
+use indextree::NodeId;
+use serde::Serialize;
+
+use crate::{
+    data::{BookmarkData, NodeType},
+    error::Error,
+    scheme::host_of,
+};
+
+use super::Bookmarks;
+
+/// A problem of the arena found by [`Bookmarks::validate`]
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub enum Problem {
+    /// Index 1 does not exist or is removed
+    RootNotFound,
+    /// Index 1 is not a root node
+    NotRoot { index: usize, node_type: NodeType },
+    /// A root node other than index 1
+    ExtraRoot { index: usize },
+    /// A bookmark without URL
+    MissingUrl { index: usize },
+    /// The host does not match the URL
+    HostMismatch {
+        index: usize,
+        expected: Option<String>,
+        actual: Option<String>,
+    },
+    /// A node other than bookmark has a URL
+    UnexpectedUrl { index: usize, node_type: NodeType },
+    /// A bookmark has children
+    BookmarkHasChildren { index: usize },
+    /// No toolbar folder directly under the root
+    ToolbarNotFound,
+    /// More than one toolbar folder directly under the root
+    DuplicateToolbar { indexes: Vec<usize> },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RootNotFound => write!(f, "root node not found at index 1"),
+            Self::NotRoot { index, node_type } => {
+                write!(f, "node {index} should be the root but is {node_type:?}")
+            }
+            Self::ExtraRoot { index } => write!(f, "node {index} is another root"),
+            Self::MissingUrl { index } => write!(f, "bookmark {index} has no URL"),
+            Self::HostMismatch {
+                index,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "bookmark {index} has host {actual:?} but the URL has {expected:?}"
+            ),
+            Self::UnexpectedUrl { index, node_type } => {
+                write!(f, "{node_type:?} {index} has a URL")
+            }
+            Self::BookmarkHasChildren { index } => write!(f, "bookmark {index} has children"),
+            Self::ToolbarNotFound => write!(f, "toolbar folder not found"),
+            Self::DuplicateToolbar { indexes } => {
+                write!(f, "toolbar folder exists more than once: {indexes:?}")
+            }
+        }
+    }
+}
+
+/// Integrity
+impl Bookmarks {
+    /// Check the arena for problems that the rest of the code assumes never happen.
+    /// Removed nodes are ignored.
+    pub fn validate(&self) -> Vec<Problem> {
+        let mut problems = Vec::new();
+
+        let root_id = self.get_root_node_id().ok().filter(|root_id| {
+            self.arena
+                .get(*root_id)
+                .is_some_and(|node| !node.is_removed())
+        });
+        match root_id.and_then(|root_id| self.arena.get(root_id)) {
+            None => problems.push(Problem::RootNotFound),
+            Some(node) if !node.get().is_root() => problems.push(Problem::NotRoot {
+                index: 1,
+                node_type: node.get().node_type.clone(),
+            }),
+            Some(_) => {}
+        }
+
+        for node in self.arena.iter().filter(|node| !node.is_removed()) {
+            let Some(node_id) = self.arena.get_node_id(node) else {
+                continue;
+            };
+            let index: usize = node_id.into();
+            let data = node.get();
+
+            if data.is_root() && index != 1 {
+                problems.push(Problem::ExtraRoot { index });
+            }
+            if data.is_bookmark() {
+                match &data.url {
+                    None => problems.push(Problem::MissingUrl { index }),
+                    Some(url) => {
+                        let expected = host_of(url);
+                        if data.host != expected {
+                            problems.push(Problem::HostMismatch {
+                                index,
+                                expected,
+                                actual: data.host.clone(),
+                            });
+                        }
+                    }
+                }
+                if node.first_child().is_some() {
+                    problems.push(Problem::BookmarkHasChildren { index });
+                }
+            } else if data.url.is_some() {
+                problems.push(Problem::UnexpectedUrl {
+                    index,
+                    node_type: data.node_type.clone(),
+                });
+            }
+        }
+
+        if let Some(root_id) = root_id {
+            let toolbars = self.find_toolbar_node_ids(root_id);
+            match toolbars.len() {
+                0 => problems.push(Problem::ToolbarNotFound),
+                1 => {}
+                _ => problems.push(Problem::DuplicateToolbar {
+                    indexes: toolbars.into_iter().map(|node_id| node_id.into()).collect(),
+                }),
+            }
+        }
+
+        problems
+    }
+
+    /// Fix the problems found by [`Bookmarks::validate`] and return them.
+    /// A missing root can't be repaired and is left as is.
+    pub fn repair(&mut self) -> Result<Vec<Problem>, Error> {
+        let problems = self.validate();
+        for problem in &problems {
+            match problem {
+                Problem::RootNotFound => {}
+                Problem::NotRoot { index, .. } => {
+                    let data = self.get_mut_node_by_index(*index)?.get_mut();
+                    data.node_type = NodeType::Root;
+                    data.url = None;
+                    data.host = None;
+                }
+                Problem::ExtraRoot { index } | Problem::MissingUrl { index } => {
+                    // keep the node and its children as a folder
+                    let data = self.get_mut_node_by_index(*index)?.get_mut();
+                    data.node_type = NodeType::Folder;
+                    data.url = None;
+                    data.host = None;
+                }
+                Problem::HostMismatch {
+                    index, expected, ..
+                } => {
+                    self.get_mut_node_by_index(*index)?.get_mut().host = expected.clone();
+                }
+                Problem::UnexpectedUrl { index, .. } => {
+                    let data = self.get_mut_node_by_index(*index)?.get_mut();
+                    data.url = None;
+                    data.host = None;
+                }
+                Problem::BookmarkHasChildren { index } => {
+                    // a bookmark without URL is already a folder by MissingUrl
+                    if self.find_node_by_index(*index)?.get().is_bookmark() {
+                        self.lift_into_folder(*index)?;
+                    }
+                }
+                Problem::ToolbarNotFound => {
+                    let root_id = self.get_root_node_id()?;
+                    let toolbar_id = self.arena.new_node(BookmarkData::new_folder("Toolbar"));
+                    root_id.checked_prepend(toolbar_id, &mut self.arena)?;
+                }
+                Problem::DuplicateToolbar { .. } => {
+                    // merge the children into the first toolbar
+                    let root_id = self.get_root_node_id()?;
+                    let toolbars = self.find_toolbar_node_ids(root_id);
+                    let Some((first, rest)) = toolbars.split_first() else {
+                        continue;
+                    };
+                    for toolbar_id in rest {
+                        let children = toolbar_id.children(&self.arena).collect::<Vec<_>>();
+                        for child in children {
+                            child.detach(&mut self.arena);
+                            first.checked_append(child, &mut self.arena)?;
+                        }
+                        toolbar_id.remove(&mut self.arena);
+                    }
+                }
+            }
+        }
+        Ok(problems)
+    }
+
+    /// Migrate files created when bookmarks could contain children.
+    /// Every bookmark having children is lifted into a folder of the same title,
+    /// see [`Problem::BookmarkHasChildren`]. Returns the number of lifted bookmarks.
+    pub fn lift_nested_bookmarks(&mut self) -> Result<usize, Error> {
+        let indexes = self
+            .validate()
+            .into_iter()
+            .filter_map(|problem| match problem {
+                Problem::BookmarkHasChildren { index } => Some(index),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        for index in &indexes {
+            self.lift_into_folder(*index)?;
+        }
+        Ok(indexes.len())
+    }
+
+    /// Replace a bookmark having children with a folder of the same title and date added,
+    /// containing the bookmark itself followed by its children
+    fn lift_into_folder(&mut self, index: usize) -> Result<(), Error> {
+        let node_id = self.find_node_id_by_index(index)?;
+        let data = self.find_node_by_node_id(node_id)?.get();
+        let mut folder = BookmarkData::new_folder(&data.title);
+        folder.date_added = data.date_added;
+        let folder_id = self.arena.new_node(folder);
+        node_id.checked_insert_before(folder_id, &mut self.arena)?;
+
+        let children = node_id.children(&self.arena).collect::<Vec<_>>();
+        node_id.detach(&mut self.arena);
+        folder_id.checked_append(node_id, &mut self.arena)?;
+        for child in children {
+            child.detach(&mut self.arena);
+            folder_id.checked_append(child, &mut self.arena)?;
+        }
+        Ok(())
+    }
+
+    /// Toolbar folders directly under the root
+    fn find_toolbar_node_ids(&self, root_id: NodeId) -> Vec<NodeId> {
+        root_id
+            .children(&self.arena)
+            .filter(|node_id| {
+                self.arena
+                    .get(*node_id)
+                    .is_some_and(|node| node.get().is_toolbar_folder())
+            })
+            .collect()
+    }
+}
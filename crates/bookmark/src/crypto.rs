@@ -0,0 +1,180 @@
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+
+use crate::error::Error;
+
+/// Magic bytes at the start of an encrypted bookmarks file
+const MAGIC: &[u8; 8] = b"PARUSENC";
+/// Format version, which also pins the KDF parameters (Argon2id defaults)
+const VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+/// Length of the key check value stored in the header to tell a wrong passphrase from a corrupted file
+const CHECK_LEN: usize = 32;
+const NONCE_LEN: usize = 24;
+const HEADER_LEN: usize = MAGIC.len() + 1 + SALT_LEN + CHECK_LEN;
+
+/// A key derived from a passphrase, kept in memory while the bookmarks are unlocked
+/// so that saving does not run the key derivation again.
+#[derive(Clone)]
+pub struct EncryptionKey {
+    salt: [u8; SALT_LEN],
+    key: [u8; KEY_LEN],
+    check: [u8; CHECK_LEN],
+}
+
+impl EncryptionKey {
+    /// Derive a key with a new random salt
+    pub fn derive(passphrase: &str) -> Result<Self, Error> {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        Self::derive_with_salt(passphrase, salt)
+    }
+
+    fn derive_with_salt(passphrase: &str, salt: [u8; SALT_LEN]) -> Result<Self, Error> {
+        let mut output = [0u8; KEY_LEN + CHECK_LEN];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut output)
+            .map_err(|e| Error::KeyDerivation(e.to_string()))?;
+
+        let mut key = [0u8; KEY_LEN];
+        let mut check = [0u8; CHECK_LEN];
+        key.copy_from_slice(&output[..KEY_LEN]);
+        check.copy_from_slice(&output[KEY_LEN..]);
+        Ok(Self { salt, key, check })
+    }
+
+    fn header(&self) -> Vec<u8> {
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        header.extend_from_slice(MAGIC);
+        header.push(VERSION);
+        header.extend_from_slice(&self.salt);
+        header.extend_from_slice(&self.check);
+        header
+    }
+
+    /// Encrypt with a new random nonce, the header is authenticated as associated data
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+        let cipher = XChaCha20Poly1305::new(&self.key.into());
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let header = self.header();
+        let ciphertext = cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: plaintext,
+                    aad: &header,
+                },
+            )
+            .map_err(|e| Error::CorruptedEncryptedFile(e.to_string()))?;
+
+        let mut data = header;
+        data.extend_from_slice(&nonce);
+        data.extend_from_slice(&ciphertext);
+        Ok(data)
+    }
+}
+
+/// Whether the data starts with the magic bytes of an encrypted file
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// Derive the key from the passphrase and the stored salt, then decrypt.
+/// Returns the key as well so that it can be reused for saving.
+pub fn decrypt(data: &[u8], passphrase: &str) -> Result<(Vec<u8>, EncryptionKey), Error> {
+    let (salt, _) = split_header(data)?;
+    let key = EncryptionKey::derive_with_salt(passphrase, salt)?;
+    let plaintext = key.decrypt(data)?;
+    Ok((plaintext, key))
+}
+
+/// Salt in the header and the whole header after checking the format
+fn split_header(data: &[u8]) -> Result<([u8; SALT_LEN], &[u8]), Error> {
+    if !is_encrypted(data) {
+        return Err(Error::CorruptedEncryptedFile("missing header".to_string()));
+    }
+    if data.len() < HEADER_LEN + NONCE_LEN {
+        return Err(Error::CorruptedEncryptedFile("truncated".to_string()));
+    }
+    let version = data[MAGIC.len()];
+    if version != VERSION {
+        return Err(Error::CorruptedEncryptedFile(format!(
+            "unsupported version: {version}"
+        )));
+    }
+
+    let salt_start = MAGIC.len() + 1;
+    let mut salt = [0u8; SALT_LEN];
+    salt.copy_from_slice(&data[salt_start..salt_start + SALT_LEN]);
+    Ok((salt, &data[..HEADER_LEN]))
+}
+
+impl EncryptionKey {
+    /// Decrypt data encrypted with this key, e.g. another file of the same collection.
+    /// Data encrypted with another passphrase or salt fails with [`Error::WrongPassphrase`].
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, Error> {
+        let (salt, header) = split_header(data)?;
+        if salt != self.salt || header[header.len() - CHECK_LEN..] != self.check {
+            return Err(Error::WrongPassphrase());
+        }
+
+        let (nonce, ciphertext) = data[HEADER_LEN..].split_at(NONCE_LEN);
+        let cipher = XChaCha20Poly1305::new(&self.key.into());
+        cipher
+            .decrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: header,
+                },
+            )
+            .map_err(|_| Error::CorruptedEncryptedFile("authentication failed".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_and_decrypt() {
+        let key = EncryptionKey::derive("passphrase").unwrap();
+        let data = key.encrypt(b"bookmarks").unwrap();
+        assert!(is_encrypted(&data));
+
+        let (plaintext, _) = decrypt(&data, "passphrase").unwrap();
+        assert_eq!(plaintext, b"bookmarks");
+
+        assert!(matches!(
+            decrypt(&data, "wrong"),
+            Err(Error::WrongPassphrase())
+        ));
+
+        // flip a byte of the ciphertext
+        let mut corrupted = data.clone();
+        let last = corrupted.len() - 1;
+        corrupted[last] ^= 1;
+        assert!(matches!(
+            decrypt(&corrupted, "passphrase"),
+            Err(Error::CorruptedEncryptedFile(_))
+        ));
+
+        // truncated
+        assert!(matches!(
+            decrypt(&data[..HEADER_LEN], "passphrase"),
+            Err(Error::CorruptedEncryptedFile(_))
+        ));
+
+        // the kept key decrypts without the passphrase, a key with another salt doesn't
+        assert_eq!(key.decrypt(&data).unwrap(), b"bookmarks");
+        let other = EncryptionKey::derive("passphrase").unwrap();
+        assert!(matches!(
+            other.decrypt(&data),
+            Err(Error::WrongPassphrase())
+        ));
+    }
+}
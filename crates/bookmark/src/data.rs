@@ -1,7 +1,13 @@
 use serde::{Deserialize, Serialize};
 use url::Url;
+use uuid::Uuid;
 
-use crate::{error::Error, utils::get_unix_timestamp};
+use crate::{
+    error::Error,
+    query::SmartQuery,
+    scheme::{host_of, is_bookmarklet, SchemePolicy},
+    utils::{get_unix_timestamp, DAY_IN_MILLIS},
+};
 
 /// https://developer.mozilla.org/en-US/docs/Mozilla/Add-ons/WebExtensions/API/bookmarks/BookmarkTreeNodeType
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -10,11 +16,16 @@ pub enum NodeType {
     Bookmark,
     Folder,
     Separator,
+    /// Virtual folder whose children are computed from a saved query
+    SmartFolder,
 }
 
 /// https://developer.mozilla.org/en-US/docs/Mozilla/Add-ons/WebExtensions/API/bookmarks/BookmarkTreeNode
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct BookmarkData {
+    /// Stable identity across bookmark files, this field will be None for nodes created by older versions
+    #[serde(default)]
+    pub guid: Option<String>,
     /// Title
     pub title: String,
     /// URL this field will be None if the node type is a folder or separator node
@@ -28,8 +39,31 @@ pub struct BookmarkData {
     /// Whether bookmark is open
     #[serde(default = "default_is_open")]
     pub is_open: bool,
+    /// Unix timestamp in milliseconds of the last modification (title, position)
+    #[serde(default)]
+    pub date_modified: Option<u64>,
+    /// Unix timestamp in milliseconds of the last visit
+    #[serde(default)]
+    pub last_visited: Option<u64>,
+    /// Number of visits to the URL
+    #[serde(default)]
+    pub visit_count: u64,
+    /// User defined tags
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Saved query, this field will be None unless the node type is a smart folder
+    #[serde(default)]
+    pub query: Option<SmartQuery>,
+    /// Keyword for quick navigation from the address bar (e.g. `rs` for `https://docs.rs/%s`)
+    #[serde(default)]
+    pub keyword: Option<String>,
+    /// Whether the reading list item has been read
+    #[serde(default)]
+    pub is_read: bool,
 }
 
+pub const READING_LIST_FOLDER_TITLE: &str = "Reading List";
+
 fn default_is_open() -> bool {
     true
 }
@@ -37,12 +71,20 @@ fn default_is_open() -> bool {
 impl BookmarkData {
     fn new(title: &str, url: Option<Url>, node_type: NodeType) -> Self {
         Self {
+            guid: Some(Uuid::new_v4().to_string()),
             title: title.to_string(),
             url: url.clone(),
-            host: url.and_then(|u| u.host_str().map(|s| s.to_string())),
+            host: url.as_ref().and_then(host_of),
             node_type,
             date_added: get_unix_timestamp(),
             is_open: default_is_open(),
+            date_modified: None,
+            last_visited: None,
+            visit_count: 0,
+            tags: Vec::new(),
+            query: None,
+            keyword: None,
+            is_read: false,
         }
     }
 
@@ -54,13 +96,27 @@ impl BookmarkData {
         Self::new(title, None, NodeType::Folder)
     }
 
+    pub fn new_smart_folder(title: &str, query: SmartQuery) -> Self {
+        Self {
+            query: Some(query),
+            ..Self::new(title, None, NodeType::SmartFolder)
+        }
+    }
+
+    /// Bookmark of a web URL
     pub fn try_new_bookmark(title: &str, url: &str) -> Result<Self, Error> {
+        Self::try_new_bookmark_with_policy(title, url, &SchemePolicy::default())
+    }
+
+    /// Bookmark of a URL whose scheme is allowed by the policy
+    pub fn try_new_bookmark_with_policy(
+        title: &str,
+        url: &str,
+        policy: &SchemePolicy,
+    ) -> Result<Self, Error> {
         let parsed_url = Url::parse(url)?;
-        if parsed_url.scheme() == "http" || parsed_url.scheme() == "https" {
-            Ok(Self::new(title, Some(parsed_url), NodeType::Bookmark))
-        } else {
-            Err(Error::NotWebUrl(parsed_url.to_string()))
-        }
+        policy.check(&parsed_url)?;
+        Ok(Self::new(title, Some(parsed_url), NodeType::Bookmark))
     }
 
     pub fn is_root(&self) -> bool {
@@ -71,13 +127,74 @@ impl BookmarkData {
         self.node_type == NodeType::Bookmark
     }
 
+    /// Bookmark of a `javascript:` URL, which is run in the page instead of navigating
+    pub fn is_bookmarklet(&self) -> bool {
+        self.is_bookmark() && self.url.as_ref().is_some_and(is_bookmarklet)
+    }
+
+    /// Host for display, IDN hosts are shown in Unicode
+    pub fn display_host(&self) -> Option<String> {
+        self.host.as_deref().map(parus_url::display_host)
+    }
+
+    /// Key to compare hosts, which ignores `www.` and letter case
+    pub fn host_key(&self) -> Option<String> {
+        self.host.as_deref().map(parus_url::host_key)
+    }
+
     pub fn is_folder(&self) -> bool {
         self.node_type == NodeType::Folder
     }
 
+    pub fn is_smart_folder(&self) -> bool {
+        self.node_type == NodeType::SmartFolder
+    }
+
+    /// Root and folders can contain children, smart folders as well but their children are computed.
+    /// Bookmarks and separators are leaf nodes.
+    pub fn is_container(&self) -> bool {
+        matches!(
+            self.node_type,
+            NodeType::Root | NodeType::Folder | NodeType::SmartFolder
+        )
+    }
+
     pub fn is_toolbar_folder(&self) -> bool {
         self.is_folder() && self.title == "Toolbar"
     }
+
+    pub fn is_reading_list_folder(&self) -> bool {
+        self.is_folder() && self.title == READING_LIST_FOLDER_TITLE
+    }
+
+    /// Set date_modified to now
+    pub(crate) fn touch(&mut self) {
+        self.date_modified = get_unix_timestamp();
+    }
+
+    /// Record a visit at the given unix timestamp in milliseconds
+    pub(crate) fn record_visit(&mut self, timestamp: u64) {
+        self.visit_count += 1;
+        self.last_visited = Some(timestamp);
+    }
+
+    /// Frecency score based on the visit count and the recency of the last visit.
+    /// This is a simplified version of Firefox's frecency algorithm.
+    /// https://developer.mozilla.org/en-US/docs/Mozilla/Tech/Places/Frecency_algorithm
+    pub fn frecency(&self, now: u64) -> u64 {
+        let Some(last_visited) = self.last_visited else {
+            return 0;
+        };
+        let age_in_days = now.saturating_sub(last_visited) / DAY_IN_MILLIS;
+        let weight = match age_in_days {
+            0..=4 => 100,
+            5..=14 => 70,
+            15..=31 => 50,
+            32..=90 => 30,
+            _ => 10,
+        };
+        self.visit_count * weight
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -100,7 +217,44 @@ pub struct ToolbarBookmarkData {
     pub index: usize,
     pub title: String,
     pub url: String,
-    pub host: String,
+    pub host: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchResultData {
+    pub index: usize,
+    pub title: String,
+    pub url: String,
+    pub host: Option<String>,
+    pub frecency: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReadingListItemData {
+    pub index: usize,
+    pub title: String,
+    pub url: String,
+    pub host: Option<String>,
+    pub date_added: Option<u64>,
+    pub is_read: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DomainBookmarkData {
+    pub index: usize,
+    pub title: String,
+    pub url: String,
+    pub host: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DomainGroupData {
+    /// Host or registrable domain in ASCII
+    pub domain: String,
+    /// Domain in Unicode for display
+    pub display_domain: String,
+    pub count: usize,
+    pub bookmarks: Vec<DomainBookmarkData>,
 }
 
 #[cfg(test)]
@@ -128,7 +282,21 @@ mod tests {
         // invalid url case
         let url = "ftp://abc.example.com";
         let err = BookmarkData::try_new_bookmark("test", url).unwrap_err();
-        assert_eq!(err.to_string(), "Not Web URL: ftp://abc.example.com/");
+        assert_eq!(
+            err.to_string(),
+            "URL scheme is not allowed: ftp://abc.example.com/"
+        );
+
+        // allowed by the policy
+        let policy = SchemePolicy::new(["https", "about", "javascript"]);
+        let bookmark =
+            BookmarkData::try_new_bookmark_with_policy("blank", "about:blank", &policy).unwrap();
+        assert_eq!(bookmark.host, None);
+        let bookmarklet =
+            BookmarkData::try_new_bookmark_with_policy("alert", "javascript:alert(1)", &policy)
+                .unwrap();
+        assert!(bookmarklet.is_bookmarklet());
+        assert!(!bookmark.is_bookmarklet());
 
         // no scheme case
         let url = "abc.example.com";
@@ -140,4 +308,37 @@ mod tests {
         let result = BookmarkData::try_new_bookmark("test", url);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_hosts() {
+        let bookmark =
+            BookmarkData::try_new_bookmark("test", "https://www.Bücher.example/").unwrap();
+        // stored in ASCII as the URL
+        assert_eq!(bookmark.host.as_deref(), Some("www.xn--bcher-kva.example"));
+        assert_eq!(
+            bookmark.display_host().as_deref(),
+            Some("www.bücher.example")
+        );
+        assert_eq!(
+            bookmark.host_key().as_deref(),
+            Some("xn--bcher-kva.example")
+        );
+    }
+
+    #[test]
+    fn test_frecency() {
+        let now = 1_000 * DAY_IN_MILLIS;
+        let mut bookmark = BookmarkData::try_new_bookmark("test", "https://docs.rs").unwrap();
+        // never visited
+        assert_eq!(bookmark.frecency(now), 0);
+
+        bookmark.record_visit(now - DAY_IN_MILLIS);
+        bookmark.record_visit(now - DAY_IN_MILLIS);
+        assert_eq!(bookmark.visit_count, 2);
+        assert_eq!(bookmark.frecency(now), 200);
+
+        // older visits weigh less
+        assert_eq!(bookmark.frecency(now + 10 * DAY_IN_MILLIS), 140);
+        assert_eq!(bookmark.frecency(now + 365 * DAY_IN_MILLIS), 20);
+    }
 }
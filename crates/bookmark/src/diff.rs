@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+
+use indextree::NodeId;
+use serde::Serialize;
+
+use crate::{bookmarks::Bookmarks, data::BookmarkData};
+
+/// Identity of a node used to match nodes between two bookmark files.
+/// Nodes are matched by guid when available, otherwise bookmarks are identified by their URL
+/// and other nodes by their path. The number is the occurrence of the same URL or path in tree order.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize)]
+pub enum NodeKey {
+    Root,
+    /// Toolbar and Reading List folders directly under the root, shared by all bookmark files
+    WellKnown(String),
+    Guid(String),
+    Url(String, usize),
+    Path(String, usize),
+}
+
+/// Summary of a node in a diff
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct NodeSummary {
+    pub key: NodeKey,
+    pub title: String,
+    pub url: Option<String>,
+    /// Titles of the ancestors excluding root
+    pub path: Vec<String>,
+}
+
+/// An operation to transform one bookmark file into another
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub enum DiffOperation {
+    Added(NodeSummary),
+    Removed(NodeSummary),
+    /// Moved to another parent, `from` is the previous path
+    Moved {
+        node: NodeSummary,
+        from: Vec<String>,
+    },
+    /// Title changed, `from` is the previous title
+    Renamed {
+        node: NodeSummary,
+        from: String,
+    },
+}
+
+pub(crate) struct SnapshotEntry {
+    pub(crate) data: BookmarkData,
+    pub(crate) parent: Option<NodeKey>,
+    pub(crate) path: Vec<String>,
+    /// Position in tree order (pre-order)
+    pub(crate) position: usize,
+}
+
+impl SnapshotEntry {
+    pub(crate) fn summary(&self, key: &NodeKey) -> NodeSummary {
+        NodeSummary {
+            key: key.clone(),
+            title: self.data.title.clone(),
+            url: self.data.url.as_ref().map(|url| url.to_string()),
+            path: self.path.clone(),
+        }
+    }
+}
+
+/// Flattened view of the live nodes in a bookmark file keyed by NodeKey
+pub(crate) struct Snapshot {
+    pub(crate) entries: HashMap<NodeKey, SnapshotEntry>,
+    /// Keys in tree order (pre-order)
+    pub(crate) order: Vec<NodeKey>,
+}
+
+impl Snapshot {
+    pub(crate) fn new(bookmarks: &Bookmarks) -> Self {
+        let mut snapshot = Self {
+            entries: HashMap::new(),
+            order: Vec::new(),
+        };
+        if let Ok(root_id) = bookmarks.get_root_node_id() {
+            let mut occurrences: HashMap<String, usize> = HashMap::new();
+            snapshot.collect(bookmarks, root_id, None, Vec::new(), &mut occurrences);
+        }
+        snapshot
+    }
+
+    fn collect(
+        &mut self,
+        bookmarks: &Bookmarks,
+        node_id: NodeId,
+        parent: Option<NodeKey>,
+        path: Vec<String>,
+        occurrences: &mut HashMap<String, usize>,
+    ) {
+        let Ok(node) = bookmarks.find_node_by_node_id(node_id) else {
+            return;
+        };
+        let data = node.get();
+
+        let key = match (&parent, &data.guid, &data.url) {
+            (None, _, _) => NodeKey::Root,
+            (Some(NodeKey::Root), _, _)
+                if (data.is_toolbar_folder() || data.is_reading_list_folder())
+                    && next_occurrence(occurrences, format!("well-known:{}", data.title)) == 1 =>
+            {
+                NodeKey::WellKnown(data.title.clone())
+            }
+            (_, Some(guid), _) => NodeKey::Guid(guid.clone()),
+            (_, None, Some(url)) if data.is_bookmark() => {
+                let url = url.to_string();
+                let n = next_occurrence(occurrences, format!("url:{url}"));
+                NodeKey::Url(url, n)
+            }
+            _ => {
+                let path = path
+                    .iter()
+                    .chain(std::iter::once(&data.title))
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join("/");
+                let n = next_occurrence(occurrences, format!("path:{path}"));
+                NodeKey::Path(path, n)
+            }
+        };
+
+        let child_path = match parent {
+            None => Vec::new(),
+            Some(_) => {
+                let mut child_path = path.clone();
+                child_path.push(data.title.clone());
+                child_path
+            }
+        };
+
+        self.entries.insert(
+            key.clone(),
+            SnapshotEntry {
+                data: data.clone(),
+                parent,
+                path,
+                position: self.order.len(),
+            },
+        );
+        self.order.push(key.clone());
+
+        let children = node_id.children(bookmarks.arena()).collect::<Vec<_>>();
+        for child in children {
+            self.collect(
+                bookmarks,
+                child,
+                Some(key.clone()),
+                child_path.clone(),
+                occurrences,
+            );
+        }
+    }
+}
+
+fn next_occurrence(occurrences: &mut HashMap<String, usize>, key: String) -> usize {
+    let n = occurrences.entry(key).or_default();
+    *n += 1;
+    *n
+}
+
+/// Diff
+impl Bookmarks {
+    /// Compare with another bookmark file and return the operations to transform self into other
+    pub fn diff(&self, other: &Bookmarks) -> Vec<DiffOperation> {
+        let old = Snapshot::new(self);
+        let new = Snapshot::new(other);
+        let mut operations = Vec::new();
+
+        for key in old.order.iter() {
+            if !new.entries.contains_key(key) {
+                operations.push(DiffOperation::Removed(old.entries[key].summary(key)));
+            }
+        }
+
+        for key in new.order.iter() {
+            let new_entry = &new.entries[key];
+            let Some(old_entry) = old.entries.get(key) else {
+                operations.push(DiffOperation::Added(new_entry.summary(key)));
+                continue;
+            };
+            if old_entry.parent != new_entry.parent {
+                operations.push(DiffOperation::Moved {
+                    node: new_entry.summary(key),
+                    from: old_entry.path.clone(),
+                });
+            }
+            if old_entry.data.title != new_entry.data.title {
+                operations.push(DiffOperation::Renamed {
+                    node: new_entry.summary(key),
+                    from: old_entry.data.title.clone(),
+                });
+            }
+        }
+
+        operations
+    }
+
+    /// Whether both bookmark files have the same nodes with the same data in the same order.
+    /// Unlike `diff`, all fields and sibling order are compared while arena indexes are ignored.
+    pub fn is_equivalent(&self, other: &Bookmarks) -> bool {
+        let a = Snapshot::new(self);
+        let b = Snapshot::new(other);
+        a.order == b.order
+            && a.order.iter().all(|key| {
+                let (a, b) = (&a.entries[key], &b.entries[key]);
+                a.parent == b.parent && a.data == b.data
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indextree::{macros::tree, Arena};
+
+    /// Bookmarks created by older versions have no guid
+    fn create_bookmarks_without_guid() -> Bookmarks {
+        let mut arena = Arena::new();
+        let mut nodes = vec![
+            BookmarkData::new_root(),
+            BookmarkData::new_folder("Rust"),
+            BookmarkData::try_new_bookmark("docs.rs", "https://docs.rs/").unwrap(),
+            BookmarkData::try_new_bookmark("crates.io", "https://crates.io/").unwrap(),
+        ];
+        for node in nodes.iter_mut() {
+            node.guid = None;
+        }
+        let [root, rust, docs, crates] = nodes.try_into().unwrap();
+        tree!(&mut arena,
+            root => {
+                rust => {
+                    docs,
+                },
+                crates,
+            }
+        );
+        Bookmarks::new(arena)
+    }
+
+    #[test]
+    fn test_snapshot_keys() {
+        let bookmarks = create_bookmarks_without_guid();
+        let snapshot = Snapshot::new(&bookmarks);
+        assert_eq!(
+            snapshot.order,
+            vec![
+                NodeKey::Root,
+                NodeKey::Path("Rust".to_string(), 1),
+                NodeKey::Url("https://docs.rs/".to_string(), 1),
+                NodeKey::Url("https://crates.io/".to_string(), 1),
+            ]
+        );
+        let docs = &snapshot.entries[&NodeKey::Url("https://docs.rs/".to_string(), 1)];
+        assert_eq!(docs.path, vec!["Rust".to_string()]);
+    }
+
+    #[test]
+    fn test_diff_without_guid() {
+        let old = create_bookmarks_without_guid();
+        let mut new = create_bookmarks_without_guid();
+        // move crates.io into Rust folder, rename docs.rs
+        new.append_to_child(4, 2).unwrap();
+        new.update_title(3, "Docs".to_string()).unwrap();
+
+        let operations = old.diff(&new);
+        assert_eq!(operations.len(), 2);
+        assert!(matches!(
+            &operations[0],
+            DiffOperation::Renamed { node, from } if node.title == "Docs" && from == "docs.rs"
+        ));
+        assert!(matches!(
+            &operations[1],
+            DiffOperation::Moved { node, from } if node.path == vec!["Rust"] && from.is_empty()
+        ));
+    }
+}
@@ -26,8 +26,8 @@ pub enum Error {
     #[error("Node not found: {0}")]
     NodeNotFound(usize),
 
-    #[error("Not Web URL: {0}")]
-    NotWebUrl(String),
+    #[error("URL scheme is not allowed: {0}")]
+    SchemeNotAllowed(String),
 
     #[error("Cannot be a base")]
     CannotBeBase(),
@@ -49,4 +49,34 @@ pub enum Error {
 
     #[error("Cannot prepend as a first child")]
     CannotPrependAsFirstChild(),
+
+    #[error("Smart folder is read-only")]
+    SmartFolderIsReadOnly(),
+
+    #[error("Not a smart folder: {0}")]
+    NotSmartFolder(usize),
+
+    #[error("Cannot contain children: {0}")]
+    NotContainer(usize),
+
+    #[error("Not a bookmark: {0}")]
+    NotBookmark(usize),
+
+    #[error("Invalid keyword: {0}")]
+    InvalidKeyword(String),
+
+    #[error("Keyword already used: {0}")]
+    KeywordAlreadyUsed(String),
+
+    #[error("Reading List Folder Not Found")]
+    ReadingListFolderNotFound(),
+
+    #[error("Wrong passphrase")]
+    WrongPassphrase(),
+
+    #[error("Encrypted file is corrupted: {0}")]
+    CorruptedEncryptedFile(String),
+
+    #[error("Key derivation failed: {0}")]
+    KeyDerivation(String),
 }
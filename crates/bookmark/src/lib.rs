@@ -1,6 +1,11 @@
 pub mod bookmarks;
+pub mod crypto;
 pub mod data;
+pub mod diff;
 pub mod error;
+pub mod merge;
+pub mod query;
+pub mod scheme;
 pub mod serialize;
 mod utils;
 
@@ -9,3 +14,4 @@ pub use indextree::Arena;
 
 pub use bookmarks::{Bookmarks, NestedBookmark};
 pub use error::Error;
+pub use utils::get_unix_timestamp;
@@ -0,0 +1,336 @@
+use std::collections::{HashMap, HashSet};
+
+use indextree::{Arena, NodeId};
+use serde::Serialize;
+
+use crate::{
+    bookmarks::Bookmarks,
+    data::BookmarkData,
+    diff::{NodeKey, Snapshot, SnapshotEntry},
+};
+
+/// Kind of a conflict found while merging
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub enum ConflictKind {
+    /// Both sides renamed the node differently, ours is kept
+    Title { ours: String, theirs: String },
+    /// Both sides changed the URL differently, ours is kept
+    Url { ours: String, theirs: String },
+    /// Both sides moved the node to different parents, ours is kept.
+    /// Values are the titles of the ancestors excluding root.
+    Parent {
+        ours: Vec<String>,
+        theirs: Vec<String>,
+    },
+    /// Removed by ours but modified by theirs, the node is kept
+    RemovedByOurs,
+    /// Removed by theirs but modified by ours, the node is kept
+    RemovedByTheirs,
+    /// The parent was removed, the node is moved to the root
+    Orphaned,
+    /// Moves on both sides made a cycle, the node is moved to the root
+    Cycle,
+}
+
+/// A conflict found while merging
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct MergeConflict {
+    pub key: NodeKey,
+    /// Title of the node in the merged result
+    pub title: String,
+    pub kind: ConflictKind,
+}
+
+/// Result of a three-way merge
+pub struct MergeResult {
+    pub bookmarks: Bookmarks,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+struct MergedEntry {
+    data: BookmarkData,
+    parent: NodeKey,
+    /// Sort key between siblings
+    order: (usize, usize, usize),
+}
+
+/// Merge one field changed on either side
+fn merge_field<T: PartialEq + Clone>(base: Option<&T>, ours: &T, theirs: &T) -> (T, bool) {
+    if ours == theirs || base == Some(theirs) {
+        (ours.clone(), false)
+    } else if base == Some(ours) {
+        (theirs.clone(), false)
+    } else {
+        (ours.clone(), true)
+    }
+}
+
+/// Whether the user changed the node compared with the common ancestor.
+/// Visits, the open state and timestamps are not edits, so they never keep a removed node.
+fn is_modified(base: &SnapshotEntry, entry: &SnapshotEntry) -> bool {
+    base.parent != entry.parent
+        || base.data.title != entry.data.title
+        || base.data.url != entry.data.url
+        || base.data.keyword != entry.data.keyword
+        || base.data.tags != entry.data.tags
+}
+
+/// Merge
+impl Bookmarks {
+    /// Merge two bookmark files derived from the common ancestor `base`.
+    /// Changes made on only one side are applied. When both sides changed the same node differently,
+    /// ours is kept and the conflict is reported.
+    pub fn three_way_merge(base: &Bookmarks, ours: &Bookmarks, theirs: &Bookmarks) -> MergeResult {
+        let base = Snapshot::new(base);
+        let ours = Snapshot::new(ours);
+        let theirs = Snapshot::new(theirs);
+        let mut conflicts = Vec::new();
+        let mut merged: HashMap<NodeKey, MergedEntry> = HashMap::new();
+
+        // theirs-only nodes are placed after the nearest preceding node that exists in ours
+        let mut anchor = 0;
+        let mut theirs_anchors = HashMap::new();
+        for key in theirs.order.iter() {
+            match ours.entries.get(key) {
+                Some(entry) => anchor = entry.position,
+                None => {
+                    theirs_anchors.insert(key, anchor);
+                }
+            }
+        }
+
+        let mut keys = ours.order.iter().collect::<Vec<_>>();
+        keys.extend(
+            theirs
+                .order
+                .iter()
+                .filter(|key| !ours.entries.contains_key(*key)),
+        );
+
+        for key in keys {
+            let base_entry = base.entries.get(key);
+            let (data, parent, order) = match (ours.entries.get(key), theirs.entries.get(key)) {
+                (Some(o), Some(t)) => {
+                    let (title, title_conflict) = merge_field(
+                        base_entry.map(|b| &b.data.title),
+                        &o.data.title,
+                        &t.data.title,
+                    );
+                    if title_conflict {
+                        conflicts.push(MergeConflict {
+                            key: key.clone(),
+                            title: title.clone(),
+                            kind: ConflictKind::Title {
+                                ours: o.data.title.clone(),
+                                theirs: t.data.title.clone(),
+                            },
+                        });
+                    }
+                    let (url, url_conflict) =
+                        merge_field(base_entry.map(|b| &b.data.url), &o.data.url, &t.data.url);
+                    if url_conflict {
+                        conflicts.push(MergeConflict {
+                            key: key.clone(),
+                            title: title.clone(),
+                            kind: ConflictKind::Url {
+                                ours: o
+                                    .data
+                                    .url
+                                    .as_ref()
+                                    .map(|u| u.to_string())
+                                    .unwrap_or_default(),
+                                theirs: t
+                                    .data
+                                    .url
+                                    .as_ref()
+                                    .map(|u| u.to_string())
+                                    .unwrap_or_default(),
+                            },
+                        });
+                    }
+                    let (parent, parent_conflict) =
+                        merge_field(base_entry.map(|b| &b.parent), &o.parent, &t.parent);
+                    if parent_conflict {
+                        conflicts.push(MergeConflict {
+                            key: key.clone(),
+                            title: title.clone(),
+                            kind: ConflictKind::Parent {
+                                ours: o.path.clone(),
+                                theirs: t.path.clone(),
+                            },
+                        });
+                    }
+
+                    // other fields are taken from the side that changed them, preferring ours
+                    let mut data = match base_entry {
+                        Some(b) if b.data == o.data => t.data.clone(),
+                        _ => o.data.clone(),
+                    };
+                    data.title = title;
+                    data.url = url;
+                    if data.url.is_none() || o.data.url == data.url {
+                        data.host = o.data.host.clone();
+                    } else {
+                        data.host = t.data.host.clone();
+                    }
+
+                    // keep ours order unless only theirs moved the node
+                    let order = if parent == o.parent {
+                        (o.position, 0, 0)
+                    } else {
+                        (ours_anchor(&ours, &theirs, key), 1, t.position)
+                    };
+                    (data, parent, order)
+                }
+                (Some(o), None) => match base_entry {
+                    Some(b) if !is_modified(b, o) => continue,
+                    Some(_) => {
+                        conflicts.push(MergeConflict {
+                            key: key.clone(),
+                            title: o.data.title.clone(),
+                            kind: ConflictKind::RemovedByTheirs,
+                        });
+                        (o.data.clone(), o.parent.clone(), (o.position, 0, 0))
+                    }
+                    None => (o.data.clone(), o.parent.clone(), (o.position, 0, 0)),
+                },
+                (None, Some(t)) => {
+                    let order = (theirs_anchors[key], 1, t.position);
+                    match base_entry {
+                        Some(b) if !is_modified(b, t) => continue,
+                        Some(_) => {
+                            conflicts.push(MergeConflict {
+                                key: key.clone(),
+                                title: t.data.title.clone(),
+                                kind: ConflictKind::RemovedByOurs,
+                            });
+                            (t.data.clone(), t.parent.clone(), order)
+                        }
+                        None => (t.data.clone(), t.parent.clone(), order),
+                    }
+                }
+                (None, None) => continue,
+            };
+            // the root has no parent, NodeKey::Root is used as a placeholder
+            merged.insert(
+                key.clone(),
+                MergedEntry {
+                    data,
+                    parent: parent.unwrap_or(NodeKey::Root),
+                    order,
+                },
+            );
+        }
+
+        let bookmarks = build_merged_bookmarks(merged, &mut conflicts);
+        MergeResult {
+            bookmarks,
+            conflicts,
+        }
+    }
+}
+
+/// Position of the nearest preceding node in theirs tree order that exists in ours
+fn ours_anchor(ours: &Snapshot, theirs: &Snapshot, key: &NodeKey) -> usize {
+    let position = theirs.entries[key].position;
+    theirs.order[..position]
+        .iter()
+        .rev()
+        .find_map(|k| ours.entries.get(k).map(|entry| entry.position))
+        .unwrap_or_default()
+}
+
+/// Build the merged tree, reattaching orphaned nodes and nodes in cycles to the root
+fn build_merged_bookmarks(
+    mut merged: HashMap<NodeKey, MergedEntry>,
+    conflicts: &mut Vec<MergeConflict>,
+) -> Bookmarks {
+    let mut arena = Arena::new();
+    let root_data = merged
+        .remove(&NodeKey::Root)
+        .map(|entry| entry.data)
+        .unwrap_or_else(BookmarkData::new_root);
+    let root_id = arena.new_node(root_data);
+
+    // reattach nodes whose parent was removed
+    let keys = merged.keys().cloned().collect::<Vec<_>>();
+    for key in keys {
+        let parent = &merged[&key].parent;
+        if *parent != NodeKey::Root && !merged.contains_key(parent) {
+            let entry = merged.get_mut(&key).expect("key exists");
+            entry.parent = NodeKey::Root;
+            conflicts.push(MergeConflict {
+                key,
+                title: entry.data.title.clone(),
+                kind: ConflictKind::Orphaned,
+            });
+        }
+    }
+
+    let mut children: HashMap<NodeKey, Vec<NodeKey>> = HashMap::new();
+    for (key, entry) in merged.iter() {
+        children
+            .entry(entry.parent.clone())
+            .or_default()
+            .push(key.clone());
+    }
+    for siblings in children.values_mut() {
+        siblings.sort_by_key(|key| merged[key].order);
+    }
+
+    let mut visited = HashSet::new();
+    append_children(
+        &mut arena,
+        root_id,
+        &NodeKey::Root,
+        &children,
+        &merged,
+        &mut visited,
+    );
+
+    // nodes not reachable from the root are in a cycle
+    let mut unreachable = merged
+        .keys()
+        .filter(|key| !visited.contains(*key))
+        .cloned()
+        .collect::<Vec<_>>();
+    unreachable.sort_by_key(|key| merged[key].order);
+    for key in unreachable {
+        if visited.contains(&key) {
+            continue;
+        }
+        let entry = &merged[&key];
+        conflicts.push(MergeConflict {
+            key: key.clone(),
+            title: entry.data.title.clone(),
+            kind: ConflictKind::Cycle,
+        });
+        let node_id = arena.new_node(entry.data.clone());
+        root_id.append(node_id, &mut arena);
+        visited.insert(key.clone());
+        append_children(&mut arena, node_id, &key, &children, &merged, &mut visited);
+    }
+
+    Bookmarks::new(arena)
+}
+
+fn append_children(
+    arena: &mut Arena<BookmarkData>,
+    parent_id: NodeId,
+    parent_key: &NodeKey,
+    children: &HashMap<NodeKey, Vec<NodeKey>>,
+    merged: &HashMap<NodeKey, MergedEntry>,
+    visited: &mut HashSet<NodeKey>,
+) {
+    let Some(keys) = children.get(parent_key) else {
+        return;
+    };
+    for key in keys {
+        if !visited.insert(key.clone()) {
+            continue;
+        }
+        let node_id = arena.new_node(merged[key].data.clone());
+        parent_id.append(node_id, arena);
+        append_children(arena, node_id, key, children, merged, visited);
+    }
+}
@@ -0,0 +1,111 @@
+use std::num::NonZeroUsize;
+
+use indextree::{Arena, NodeId};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    data::BookmarkData,
+    utils::{glob_match, DAY_IN_MILLIS},
+};
+
+/// A condition of a smart folder query
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum QueryCondition {
+    /// Host equals the given value (case-insensitive)
+    HostEquals(String),
+    /// URL matches the given glob pattern (`*` and `?`)
+    UrlMatches(String),
+    /// Has the given tag (case-insensitive)
+    Tag(String),
+    /// Added within the given number of days
+    AddedWithinDays(u64),
+    /// Never visited
+    NeverVisited,
+}
+
+impl QueryCondition {
+    /// Whether the bookmark satisfies the condition at the given unix timestamp in milliseconds
+    pub fn matches(&self, data: &BookmarkData, now: u64) -> bool {
+        match self {
+            // `www.` and IDN hosts in Unicode are the same host
+            Self::HostEquals(host) => data
+                .host_key()
+                .is_some_and(|key| key == parus_url::host_key(host)),
+            Self::UrlMatches(pattern) => data
+                .url
+                .as_ref()
+                .is_some_and(|url| glob_match(pattern, url.as_str())),
+            Self::Tag(tag) => data
+                .tags
+                .iter()
+                .any(|t| t.to_lowercase() == tag.to_lowercase()),
+            Self::AddedWithinDays(days) => data
+                .date_added
+                .is_some_and(|added| added >= now.saturating_sub(days * DAY_IN_MILLIS)),
+            Self::NeverVisited => data.visit_count == 0,
+        }
+    }
+}
+
+/// Saved query of a smart folder. A bookmark matches when it satisfies all conditions.
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct SmartQuery {
+    pub conditions: Vec<QueryCondition>,
+}
+
+impl SmartQuery {
+    pub fn new(conditions: Vec<QueryCondition>) -> Self {
+        Self { conditions }
+    }
+
+    /// Whether the node is a bookmark and satisfies all conditions
+    pub fn matches(&self, data: &BookmarkData, now: u64) -> bool {
+        data.is_bookmark() && self.conditions.iter().all(|c| c.matches(data, now))
+    }
+
+    /// Find all bookmarks under the root matching the query in tree order
+    pub(crate) fn find_matches(&self, arena: &Arena<BookmarkData>, now: u64) -> Vec<NodeId> {
+        let root_id = NonZeroUsize::new(1).and_then(|id| arena.get_node_id_at(id));
+        let Some(root_id) = root_id else {
+            return Vec::new();
+        };
+
+        root_id
+            .descendants(arena)
+            .filter(|node_id| {
+                arena
+                    .get(*node_id)
+                    .is_some_and(|node| !node.is_removed() && self.matches(node.get(), now))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conditions() {
+        let now = 100 * DAY_IN_MILLIS;
+        let mut data = BookmarkData::try_new_bookmark("test", "https://docs.rs/tauri").unwrap();
+        data.date_added = Some(now - 3 * DAY_IN_MILLIS);
+        data.tags = vec!["Rust".to_string()];
+
+        assert!(QueryCondition::HostEquals("DOCS.rs".to_string()).matches(&data, now));
+        assert!(QueryCondition::HostEquals("www.docs.rs".to_string()).matches(&data, now));
+        assert!(!QueryCondition::HostEquals("crates.io".to_string()).matches(&data, now));
+        assert!(QueryCondition::UrlMatches("https://docs.rs/*".to_string()).matches(&data, now));
+        assert!(!QueryCondition::UrlMatches("*crates.io*".to_string()).matches(&data, now));
+        assert!(QueryCondition::Tag("rust".to_string()).matches(&data, now));
+        assert!(!QueryCondition::Tag("go".to_string()).matches(&data, now));
+        assert!(QueryCondition::AddedWithinDays(7).matches(&data, now));
+        assert!(!QueryCondition::AddedWithinDays(1).matches(&data, now));
+        assert!(QueryCondition::NeverVisited.matches(&data, now));
+
+        // folders never match
+        let folder = BookmarkData::new_folder("folder");
+        assert!(!SmartQuery::default().matches(&folder, now));
+        assert!(SmartQuery::default().matches(&data, now));
+    }
+}
@@ -0,0 +1,120 @@
+use percent_encoding::percent_decode_str;
+use url::Url;
+
+use crate::error::Error;
+
+/// Scheme of bookmarklets, which run a script in the current page instead of navigating
+pub const BOOKMARKLET_SCHEME: &str = "javascript";
+
+/// URL schemes which can be bookmarked. Only web URLs are allowed by default.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemePolicy {
+    allowed: Vec<String>,
+}
+
+impl Default for SchemePolicy {
+    fn default() -> Self {
+        Self::new(["http", "https"])
+    }
+}
+
+impl SchemePolicy {
+    /// Schemes are compared in lower case, empty entries are ignored
+    pub fn new<I, S>(schemes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut allowed: Vec<String> = Vec::new();
+        for scheme in schemes {
+            let scheme = scheme.as_ref().trim().trim_end_matches(':').to_lowercase();
+            if !scheme.is_empty() && !allowed.contains(&scheme) {
+                allowed.push(scheme);
+            }
+        }
+        Self { allowed }
+    }
+
+    pub fn allowed(&self) -> &[String] {
+        &self.allowed
+    }
+
+    pub fn is_allowed(&self, url: &Url) -> bool {
+        self.allowed.iter().any(|scheme| scheme == url.scheme())
+    }
+
+    pub fn check(&self, url: &Url) -> Result<(), Error> {
+        if self.is_allowed(url) {
+            Ok(())
+        } else {
+            Err(Error::SchemeNotAllowed(url.to_string()))
+        }
+    }
+}
+
+/// Host name of the URL.
+/// None for schemes without a host, e.g. `javascript:`, `about:` and local `file:` URLs.
+pub fn host_of(url: &Url) -> Option<String> {
+    url.host_str()
+        .filter(|host| !host.is_empty())
+        .map(|host| host.to_string())
+}
+
+pub fn is_bookmarklet(url: &Url) -> bool {
+    url.scheme() == BOOKMARKLET_SCHEME
+}
+
+/// Decoded script of a bookmarklet, None if the URL is not a bookmarklet
+pub fn bookmarklet_script(url: &Url) -> Option<String> {
+    if !is_bookmarklet(url) {
+        return None;
+    }
+    let script = &url.as_str()[BOOKMARKLET_SCHEME.len() + 1..];
+    Some(percent_decode_str(script).decode_utf8_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scheme_policy() {
+        let web = SchemePolicy::default();
+        assert!(web.is_allowed(&Url::parse("https://docs.rs/").unwrap()));
+        assert!(!web.is_allowed(&Url::parse("file:///usr/share/doc/index.html").unwrap()));
+
+        let policy = SchemePolicy::new(["HTTPS", "file:", " ", "https"]);
+        assert_eq!(policy.allowed(), ["https", "file"]);
+        assert!(policy.is_allowed(&Url::parse("file:///usr/share/doc/index.html").unwrap()));
+        assert!(policy
+            .check(&Url::parse("javascript:alert(1)").unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn test_host_of() {
+        let host = |url: &str| host_of(&Url::parse(url).unwrap());
+        assert_eq!(host("https://docs.rs/tauri"), Some("docs.rs".to_string()));
+        assert_eq!(host("file:///usr/share/doc/index.html"), None);
+        assert_eq!(
+            host("file://server/share/index.html"),
+            Some("server".to_string())
+        );
+        assert_eq!(host("about:blank"), None);
+        assert_eq!(host("javascript:alert(1)"), None);
+    }
+
+    #[test]
+    fn test_bookmarklet_script() {
+        let url = Url::parse("javascript:alert(%22hello%20world%22)").unwrap();
+        assert!(is_bookmarklet(&url));
+        assert_eq!(
+            bookmarklet_script(&url),
+            Some("alert(\"hello world\")".to_string())
+        );
+        assert_eq!(
+            bookmarklet_script(&Url::parse("https://docs.rs/").unwrap()),
+            None
+        );
+    }
+}
@@ -1,5 +1,9 @@
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use url::Url;
+
+pub(crate) const DAY_IN_MILLIS: u64 = 24 * 60 * 60 * 1000;
+
 // write function generate unix timestamp in milliseconds
 pub fn get_unix_timestamp() -> Option<u64> {
     let now = SystemTime::now();
@@ -8,3 +12,92 @@ pub fn get_unix_timestamp() -> Option<u64> {
         Err(_) => None,
     }
 }
+
+/// Compare URLs by `parus_url::url_key`, which ignores the fragment, `www.` and http or https
+pub(crate) fn is_same_document(a: &Url, b: &Url) -> bool {
+    parus_url::url_key(a) == parus_url::url_key(b)
+}
+
+/// Match text against a glob pattern, `*` matches any sequence and `?` matches any single character
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+    let (mut p, mut t) = (0, 0);
+    // position of the last `*` in pattern and the position in text when it was found
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    backtrack = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Year and month like `2025-03` of a unix timestamp in milliseconds, in UTC
+pub(crate) fn year_month(timestamp: u64) -> String {
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = (timestamp / DAY_IN_MILLIS) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_year_month() {
+        assert_eq!(year_month(0), "1970-01");
+        // 2024-02-29T23:59:59Z
+        assert_eq!(year_month(1_709_251_199_000), "2024-02");
+        // 2024-03-01T00:00:00Z
+        assert_eq!(year_month(1_709_251_200_000), "2024-03");
+        // 2025-12-31T12:00:00Z
+        assert_eq!(year_month(1_767_182_400_000), "2025-12");
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match(
+            "https://docs.rs/*",
+            "https://docs.rs/tauri/latest/"
+        ));
+        assert!(glob_match(
+            "*github.com*",
+            "https://github.com/andots/parus"
+        ));
+        assert!(glob_match("https://?.com/", "https://x.com/"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("https://docs.rs/*", "https://crates.io/"));
+        assert!(!glob_match("https://?.com/", "https://xy.com/"));
+        assert!(!glob_match("", "abc"));
+    }
+}
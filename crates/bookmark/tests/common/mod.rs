@@ -34,11 +34,11 @@ pub fn create_simple_bookmarks() -> Bookmarks {
 pub fn create_test_bookmarks() -> Bookmarks {
     let mut arena = Arena::new();
     let n_1 = BookmarkData::new_root();
-    let n_2 = BookmarkData::try_new_bookmark("n_2", "https://docs.rs/abc").unwrap();
+    let n_2 = BookmarkData::new_folder("n_2");
     let n_3 = BookmarkData::try_new_bookmark("n_3", "https://docs.rs/abc").unwrap();
-    let n_4 = BookmarkData::try_new_bookmark("n_4", "https://docs.rs/abc").unwrap();
+    let n_4 = BookmarkData::new_folder("n_4");
     let n_5 = BookmarkData::try_new_bookmark("n_5", "https://docs.rs/abc").unwrap();
-    let n_6 = BookmarkData::try_new_bookmark("n_6", "https://docs.rs/abc").unwrap();
+    let n_6 = BookmarkData::new_folder("n_6");
     let n_7 = BookmarkData::try_new_bookmark("n_7", "https://docs.rs/abc").unwrap();
     let n_8 = BookmarkData::try_new_bookmark("n_8", "https://docs.rs/abc").unwrap();
     tree!(&mut arena,
@@ -4,7 +4,13 @@ mod common;
 mod tests {
     use indextree::{macros::tree, Arena};
     use parus_bookmark::{
+        bookmarks::{domain::DomainGrouping, validate::Problem},
+        crypto::EncryptionKey,
         data::{BookmarkData, NodeType},
+        diff::DiffOperation,
+        merge::ConflictKind,
+        query::{QueryCondition, SmartQuery},
+        scheme::SchemePolicy,
         Bookmarks, Error,
     };
 
@@ -394,19 +400,21 @@ mod tests {
         let root = BookmarkData::new_root();
         tree!(&mut arena,
             root => {
-               BookmarkData::try_new_bookmark("abc", "https://docs.rs/abc").unwrap(),
+                BookmarkData::new_folder("docs") => {
+                    BookmarkData::try_new_bookmark("abc", "https://docs.rs/abc").unwrap(),
+                }
             }
         );
         let mut bookmarks = Bookmarks::new(arena);
 
         let top_level_index = 1;
-        let b3 = bookmarks.add_bookmark("abc/cdf", "https://docs.rs/abc/cdf", top_level_index)?;
-        assert_eq!(b3, 3);
+        let b4 = bookmarks.add_bookmark("abc/cdf", "https://docs.rs/abc/cdf", top_level_index)?;
+        assert_eq!(b4, 4);
 
         // close all
-        bookmarks.set_is_open(1, false)?;
-        bookmarks.set_is_open(2, false)?;
-        bookmarks.set_is_open(3, false)?;
+        for index in 1..=4 {
+            bookmarks.set_is_open(index, false)?;
+        }
 
         // make sure all nodes are closed
         for node in bookmarks.arena().iter() {
@@ -415,17 +423,202 @@ mod tests {
         }
 
         // add_bookmark will open all ancestors
-        let b4 = bookmarks.add_bookmark(
+        let b5 = bookmarks.add_bookmark(
             "abc/cdf/efg",
             "https://docs.rs/abc/cdf/efg",
             top_level_index,
         )?;
-        assert_eq!(b4, 4);
+        assert_eq!(b5, 5);
 
-        for node in bookmarks.arena().iter() {
-            let data = node.get();
-            assert!(data.is_open);
-        }
+        assert!(bookmarks.find_node_by_index(1)?.get().is_open);
+        assert!(bookmarks.find_node_by_index(2)?.get().is_open);
+        assert!(bookmarks.find_node_by_index(5)?.get().is_open);
+        assert!(!bookmarks.find_node_by_index(3)?.get().is_open);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_bookmark_next_to_bookmark() -> anyhow::Result<()> {
+        let mut bookmarks = create_simple_bookmarks();
+        let index = bookmarks.add_bookmark("abc/cdf", "https://docs.rs/abc/cdf", 1)?;
+        assert_eq!(index, 3);
+
+        // the new bookmark is a sibling of the matching bookmark, not its child
+        let root = bookmarks.get_root_node_id()?;
+        let children = root
+            .children(bookmarks.arena())
+            .map(Into::<usize>::into)
+            .collect::<Vec<_>>();
+        assert_eq!(children, vec![2, 3]);
+
+        // bookmarks can't contain children
+        assert_eq!(
+            bookmarks.append_to_child(3, 2).unwrap_err().to_string(),
+            Error::NotContainer(2).to_string()
+        );
+        assert_eq!(
+            bookmarks.prepend_to_child(3, 2).unwrap_err().to_string(),
+            Error::NotContainer(2).to_string()
+        );
+        assert_eq!(
+            bookmarks.add_folder(2, "folder").unwrap_err().to_string(),
+            Error::NotContainer(2).to_string()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_scheme_policy() -> anyhow::Result<()> {
+        let mut bookmarks = Bookmarks::default();
+        let bookmarklet = "javascript:alert(document.title)";
+        assert_eq!(
+            bookmarks
+                .append_bookmark(2, "title", bookmarklet)
+                .unwrap_err()
+                .to_string(),
+            Error::SchemeNotAllowed(bookmarklet.to_string()).to_string()
+        );
+
+        bookmarks.set_scheme_policy(SchemePolicy::new(["https", "file", "about", "javascript"]));
+        let index = bookmarks.append_bookmark(2, "title", bookmarklet)?;
+        assert!(bookmarks.find_node_by_index(index)?.get().is_bookmarklet());
+        // URLs without a path are added to the top level node
+        let index = bookmarks.add_bookmark("blank", "about:blank", 1)?;
+        assert_eq!(bookmarks.find_node_by_index(index)?.get().host, None);
+        let index = bookmarks.add_bookmark("docs", "file:///usr/share/doc/index.html", 1)?;
+        assert_eq!(bookmarks.find_node_by_index(index)?.get().host, None);
+        assert!(bookmarks.validate().is_empty());
+
+        // the toolbar lists bookmarks without a host
+        let toolbar = bookmarks.get_toolbar_bookmarks();
+        assert_eq!(toolbar[0].url, bookmarklet);
+        assert_eq!(toolbar[0].host, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_group_by_domain() -> anyhow::Result<()> {
+        let bookmarks = create_realistic_bookmarks();
+        let groups = bookmarks.group_by_domain(1, DomainGrouping::Host)?;
+        let counts = groups
+            .iter()
+            .map(|group| (group.domain.as_str(), group.count))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            counts,
+            vec![
+                ("docs.rs", 7),
+                ("github.com", 3),
+                ("v2.tauri.app", 3),
+                ("google.com", 1),
+                ("solid-ui.com", 1),
+                ("youtube.com", 1),
+                ("zustand.docs.pmnd.rs", 1),
+            ]
+        );
+        assert_eq!(groups[1].bookmarks[0].index, 4);
+        assert_eq!(
+            groups.iter().map(|group| group.count).sum::<usize>(),
+            bookmarks.count_bookmarks()
+        );
+
+        let groups = bookmarks.group_by_domain(19, DomainGrouping::RegistrableDomain)?;
+        let domains = groups
+            .iter()
+            .map(|group| group.domain.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(domains, vec!["github.com", "pmnd.rs", "solid-ui.com"]);
+
+        let urls = bookmarks.favicon_urls()?;
+        assert_eq!(urls.len(), counts.len());
+        assert!(urls[0].starts_with("https://docs.rs/"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_organize_by_domain() -> anyhow::Result<()> {
+        let mut bookmarks = create_realistic_bookmarks();
+        let moved = bookmarks.organize_by_domain(19, DomainGrouping::RegistrableDomain)?;
+        assert_eq!(moved, 3);
+        let frontend = bookmarks.find_node_id_by_index(19)?;
+        let titles = frontend
+            .children(bookmarks.arena())
+            .map(|node_id| bookmarks.arena()[node_id].get().title.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(titles, vec!["github.com", "pmnd.rs", "solid-ui.com"]);
+
+        // an existing subfolder is reused
+        let index = bookmarks.append_bookmark(19, "GitHub", "https://www.github.com/")?;
+        assert_eq!(bookmarks.organize_by_domain(19, DomainGrouping::Host)?, 1);
+        let bookmark = bookmarks.find_node_by_index(index)?;
+        let parent = bookmarks.find_node_by_node_id(bookmark.parent().unwrap())?;
+        assert_eq!(parent.get().title, "github.com");
+        assert_eq!(frontend.children(bookmarks.arena()).count(), 3);
+
+        // bookmarks can't be organized
+        assert!(bookmarks
+            .organize_by_domain(index, DomainGrouping::Host)
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_stats() -> anyhow::Result<()> {
+        let mut bookmarks = create_realistic_bookmarks();
+        bookmarks.record_visit_at("https://github.com/", 1_700_000_000_000)?;
+        bookmarks.remove_subtree(16)?;
+
+        let stats = bookmarks.stats()?;
+        assert_eq!(stats.bookmarks, 14);
+        assert_eq!(stats.folders, 4);
+        assert_eq!(stats.never_visited, 13);
+        let depths = stats
+            .depths
+            .iter()
+            .map(|depth| (depth.depth, depth.count))
+            .collect::<Vec<_>>();
+        // nested bookmarks are at depth 3
+        assert_eq!(depths, vec![(2, 9), (3, 5)]);
+        assert_eq!(stats.largest_folders[0].title, "Toolbar");
+        assert_eq!(stats.largest_folders[0].children, 5);
+        assert_eq!(
+            stats
+                .additions_per_month
+                .iter()
+                .map(|month| month.count)
+                .sum::<usize>(),
+            14
+        );
+        assert_eq!(stats.top_hosts[0].host, "docs.rs");
+        assert_eq!(stats.top_hosts[0].count, 4);
+        assert_eq!(stats.total_nodes, 22);
+        assert_eq!(stats.garbage_nodes, 3);
+
+        bookmarks.compact()?;
+        assert_eq!(bookmarks.stats()?.garbage_ratio, 0.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lift_nested_bookmarks() -> anyhow::Result<()> {
+        let mut bookmarks = create_realistic_bookmarks();
+        assert_eq!(bookmarks.lift_nested_bookmarks()?, 3);
+        assert!(bookmarks.validate().is_empty());
+        assert_eq!(bookmarks.count_bookmarks(), 17);
+        assert_eq!(bookmarks.lift_nested_bookmarks()?, 0);
+
+        // the bookmark is the first child of the folder with the same title
+        let tauri = bookmarks.find_node_by_index(9)?;
+        let folder = bookmarks.find_node_by_node_id(tauri.parent().unwrap())?;
+        assert_eq!(folder.get().node_type, NodeType::Folder);
+        assert_eq!(folder.get().title, "tauri - Rust");
+        assert_eq!(folder.first_child().map(Into::<usize>::into), Some(9));
 
         Ok(())
     }
@@ -573,4 +766,752 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_record_visit() -> anyhow::Result<()> {
+        let mut bookmarks = create_realistic_bookmarks();
+
+        // GitHub (index 4) is visited, fragment is ignored
+        let updated = bookmarks.record_visit_at("https://github.com/#readme", 1_000)?;
+        assert_eq!(updated, vec![4]);
+        let github = bookmarks.find_node_by_index(4)?.get();
+        assert_eq!(github.visit_count, 1);
+        assert_eq!(github.last_visited, Some(1_000));
+
+        bookmarks.record_visit_at("https://github.com/", 2_000)?;
+        let github = bookmarks.find_node_by_index(4)?.get();
+        assert_eq!(github.visit_count, 2);
+        assert_eq!(github.last_visited, Some(2_000));
+
+        // `www.` and http are the same URL
+        let updated = bookmarks.record_visit_at("http://www.github.com", 2_500)?;
+        assert_eq!(updated, vec![4]);
+
+        // not bookmarked URL updates nothing
+        let updated = bookmarks.record_visit_at("https://example.com/", 3_000)?;
+        assert!(updated.is_empty());
+
+        // removed bookmarks are not updated
+        bookmarks.remove_subtree(4)?;
+        let updated = bookmarks.record_visit_at("https://github.com/", 4_000)?;
+        assert!(updated.is_empty());
+
+        // invalid url must be error
+        assert!(bookmarks.record_visit_at("abc", 5_000).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_by_frecency() -> anyhow::Result<()> {
+        let mut bookmarks = create_realistic_bookmarks();
+        bookmarks.record_visit("https://github.com/search")?;
+        bookmarks.record_visit("https://github.com/unplugin/unplugin-icons")?;
+        bookmarks.record_visit("https://github.com/unplugin/unplugin-icons")?;
+
+        let results = bookmarks.search("GITHUB")?;
+        let indexes = results.iter().map(|r| r.index).collect::<Vec<_>>();
+        assert_eq!(indexes, vec![22, 6, 4]);
+        assert!(results[0].frecency > results[1].frecency);
+        assert_eq!(results[2].frecency, 0);
+
+        // empty query matches all bookmarks
+        let results = bookmarks.search("")?;
+        assert_eq!(results.len(), bookmarks.count_bookmarks());
+        assert_eq!(results[0].index, 22);
+
+        assert!(bookmarks.search("not found")?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_by_frecency() -> anyhow::Result<()> {
+        let mut bookmarks = create_realistic_bookmarks();
+        bookmarks.record_visit("https://www.youtube.com/")?;
+        bookmarks.record_visit("https://github.com/")?;
+        bookmarks.record_visit("https://github.com/")?;
+
+        bookmarks.sort_by_frecency(2)?;
+        let toolbar = bookmarks.get_toolbar_node_id()?;
+        let children: Vec<usize> = toolbar
+            .children(bookmarks.arena())
+            .map(|node_id| node_id.into())
+            .collect();
+        // folder first, then GitHub, YouTube, and the rest in original order
+        assert_eq!(children, vec![7, 4, 5, 3, 6]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_by_frecency_keeps_separators() -> anyhow::Result<()> {
+        let mut arena = Arena::new();
+        let root = BookmarkData::new_root();
+        let separator = BookmarkData {
+            node_type: NodeType::Separator,
+            ..BookmarkData::new_folder("")
+        };
+        tree!(&mut arena,
+            root => {
+                BookmarkData::try_new_bookmark("Google", "https://www.google.com/")?,
+                separator,
+                BookmarkData::try_new_bookmark("GitHub", "https://github.com/")?,
+                BookmarkData::new_folder("Folder"),
+            }
+        );
+        let mut bookmarks = Bookmarks::new(arena);
+        bookmarks.record_visit("https://github.com/")?;
+
+        bookmarks.sort_by_frecency(1)?;
+        let root = bookmarks.get_root_node_id()?;
+        let children: Vec<usize> = root
+            .children(bookmarks.arena())
+            .map(|node_id| node_id.into())
+            .collect();
+        // the separator stays second, the folder comes first and GitHub before Google
+        assert_eq!(children, vec![5, 3, 4, 2]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_date_modified() -> anyhow::Result<()> {
+        let mut bookmarks = create_test_bookmarks();
+        assert_eq!(bookmarks.find_node_by_index(2)?.get().date_modified, None);
+
+        bookmarks.update_title(2, "new title".to_string())?;
+        assert!(bookmarks
+            .find_node_by_index(2)?
+            .get()
+            .date_modified
+            .is_some());
+
+        bookmarks.append_to_child(3, 4)?;
+        assert!(bookmarks
+            .find_node_by_index(3)?
+            .get()
+            .date_modified
+            .is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_smart_folder() -> anyhow::Result<()> {
+        let mut bookmarks = create_realistic_bookmarks();
+        let query = SmartQuery::new(vec![QueryCondition::HostEquals("github.com".to_string())]);
+        let index = bookmarks.add_smart_folder(1, "GitHub", query)?;
+        assert_eq!(
+            bookmarks.find_node_by_index(index)?.get().node_type,
+            NodeType::SmartFolder
+        );
+
+        // children are computed from the query
+        let nested = serde_json::to_value(bookmarks.to_nested_bookmark(index)?)?;
+        let children = nested["children"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|child| child["index"].as_u64().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(children, vec![4, 6, 22]);
+        assert_eq!(
+            bookmarks
+                .find_bookmarks_by_query(&SmartQuery::default())
+                .len(),
+            17
+        );
+
+        // smart folder is read-only for moves
+        let err = bookmarks.append_to_child(3, index).unwrap_err();
+        assert_eq!(err.to_string(), Error::SmartFolderIsReadOnly().to_string());
+        let err = bookmarks.prepend_to_child(3, index).unwrap_err();
+        assert_eq!(err.to_string(), Error::SmartFolderIsReadOnly().to_string());
+        assert!(bookmarks.add_folder(index, "folder").is_err());
+        let err = bookmarks
+            .add_bookmark("Example", "https://example.com/", index)
+            .unwrap_err();
+        assert_eq!(err.to_string(), Error::SmartFolderIsReadOnly().to_string());
+
+        // smart folder itself can be moved
+        bookmarks.append_to_child(index, 8)?;
+
+        // update the query
+        let query = SmartQuery::new(vec![
+            QueryCondition::UrlMatches("https://docs.rs/*".to_string()),
+            QueryCondition::NeverVisited,
+        ]);
+        bookmarks.update_smart_query(index, query.clone())?;
+        bookmarks.record_visit("https://docs.rs/indextree/latest/indextree/")?;
+        let matches = bookmarks.find_bookmarks_by_query(&query);
+        assert_eq!(matches, vec![9, 10, 11, 12, 17, 18]);
+        assert!(bookmarks.update_smart_query(8, query).is_err());
+
+        // tags
+        bookmarks.set_tags(20, vec!["State".to_string()])?;
+        let query = SmartQuery::new(vec![QueryCondition::Tag("state".to_string())]);
+        assert_eq!(bookmarks.find_bookmarks_by_query(&query), vec![20]);
+
+        // serialize alongside regular nodes
+        let arena = serde_json::from_str(&bookmarks.to_json()?)?;
+        let restored = Bookmarks::new(arena);
+        let data = restored.find_node_by_index(index)?.get();
+        assert_eq!(data.node_type, NodeType::SmartFolder);
+        assert_eq!(data.query.as_ref().unwrap().conditions.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_smart_folder_children_are_not_destinations() -> anyhow::Result<()> {
+        // a real child under a smart folder, as saved by older versions
+        let mut arena = Arena::new();
+        let root = BookmarkData::new_root();
+        let query = SmartQuery::new(vec![QueryCondition::HostEquals("github.com".to_string())]);
+        tree!(&mut arena,
+            root => {
+                BookmarkData::try_new_bookmark("Google", "https://www.google.com/")?,
+                BookmarkData::new_smart_folder("GitHub", query) => {
+                    BookmarkData::try_new_bookmark("GitHub", "https://github.com/")?,
+                },
+            }
+        );
+        let mut bookmarks = Bookmarks::new(arena);
+
+        let err = bookmarks.insert_after(2, 4).unwrap_err();
+        assert_eq!(err.to_string(), Error::SmartFolderIsReadOnly().to_string());
+        let err = bookmarks.insert_before(2, 4).unwrap_err();
+        assert_eq!(err.to_string(), Error::SmartFolderIsReadOnly().to_string());
+        // next to the smart folder itself is fine
+        bookmarks.insert_after(2, 3)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_keyword() -> anyhow::Result<()> {
+        let mut bookmarks = create_realistic_bookmarks();
+        let rs = bookmarks.add_bookmark("docs.rs", "https://docs.rs/%s", 1)?;
+        let gh = bookmarks.add_bookmark("search", "https://github.com/search?q=%s&raw=%S", 1)?;
+
+        bookmarks.set_keyword(rs, Some("RS".to_string()))?;
+        bookmarks.set_keyword(gh, Some("gh".to_string()))?;
+        bookmarks.set_keyword(3, Some("g".to_string()))?;
+        assert_eq!(
+            bookmarks.find_node_by_index(rs)?.get().keyword,
+            Some("rs".to_string())
+        );
+
+        // resolve
+        assert_eq!(
+            bookmarks.resolve_keyword("rs serde"),
+            Some("https://docs.rs/serde".to_string())
+        );
+        assert_eq!(
+            bookmarks.resolve_keyword(" gh  tauri plugin "),
+            Some("https://github.com/search?q=tauri+plugin&raw=tauri plugin".to_string())
+        );
+        assert_eq!(
+            bookmarks.resolve_keyword("g"),
+            Some("https://www.google.com/".to_string())
+        );
+        assert_eq!(bookmarks.resolve_keyword("unknown serde"), None);
+        assert_eq!(bookmarks.resolve_keyword("https://docs.rs/"), None);
+        assert_eq!(bookmarks.resolve_keyword(""), None);
+
+        // uniqueness
+        let err = bookmarks
+            .set_keyword(4, Some("Rs".to_string()))
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Keyword already used: rs");
+        // setting the same keyword again to the same bookmark is fine
+        bookmarks.set_keyword(rs, Some("rs".to_string()))?;
+
+        // validation
+        assert!(bookmarks.set_keyword(4, Some("".to_string())).is_err());
+        assert!(bookmarks.set_keyword(4, Some("a b".to_string())).is_err());
+        let err = bookmarks
+            .set_keyword(8, Some("rust".to_string()))
+            .unwrap_err();
+        assert_eq!(err.to_string(), Error::NotBookmark(8).to_string());
+
+        // clear
+        bookmarks.set_keyword(rs, None)?;
+        assert_eq!(bookmarks.resolve_keyword("rs serde"), None);
+        bookmarks.set_keyword(4, Some("rs".to_string()))?;
+
+        // non-ASCII keywords are case-insensitive as well
+        bookmarks.set_keyword(gh, Some("Ärger".to_string()))?;
+        assert_eq!(bookmarks.find_index_by_keyword("ÄRGER"), Some(gh));
+        assert_eq!(
+            bookmarks.resolve_keyword("ärger tauri"),
+            Some("https://github.com/search?q=tauri&raw=tauri".to_string())
+        );
+        let err = bookmarks
+            .set_keyword(3, Some("ÄrGeR".to_string()))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            Error::KeywordAlreadyUsed("ärger".to_string()).to_string()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reading_list() -> anyhow::Result<()> {
+        let mut bookmarks = Bookmarks::default();
+        assert!(bookmarks.get_reading_list_node_id().is_err());
+        assert!(bookmarks.get_reading_list(false).is_empty());
+
+        // reading list folder is created under root on first add
+        let a = bookmarks.add_to_reading_list("a", "https://example.com/a")?;
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let b = bookmarks.add_to_reading_list("b", "https://example.com/b")?;
+        let reading_list_id = bookmarks.get_reading_list_node_id()?;
+        assert_eq!(reading_list_id.children(bookmarks.arena()).count(), 2);
+
+        // newest first
+        let items = bookmarks.get_reading_list(true);
+        assert_eq!(
+            items.iter().map(|i| i.index).collect::<Vec<_>>(),
+            vec![b, a]
+        );
+
+        // auto-mark-read by URL, fragment is ignored
+        let updated = bookmarks.mark_read_by_url("https://example.com/a#end")?;
+        assert_eq!(updated, vec![a]);
+        assert!(bookmarks
+            .mark_read_by_url("https://example.com/a")?
+            .is_empty());
+        let items = bookmarks.get_reading_list(true);
+        assert_eq!(items.iter().map(|i| i.index).collect::<Vec<_>>(), vec![b]);
+        assert_eq!(bookmarks.get_reading_list(false).len(), 2);
+
+        // adding the same page again marks it as unread
+        assert_eq!(
+            bookmarks.add_to_reading_list("a", "https://example.com/a")?,
+            a
+        );
+        assert_eq!(bookmarks.get_reading_list(true).len(), 2);
+
+        // manual toggle
+        bookmarks.set_is_read(b, true)?;
+        assert_eq!(bookmarks.get_reading_list(true).len(), 1);
+        assert!(bookmarks.set_is_read(1, true).is_err());
+
+        // bookmarks outside the reading list are not marked
+        bookmarks.append_bookmark_to_toolbar("c", "https://example.com/c")?;
+        assert!(bookmarks
+            .mark_read_by_url("https://example.com/c")?
+            .is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff() -> anyhow::Result<()> {
+        let old = create_realistic_bookmarks();
+        let mut new = Bookmarks::new(old.arena().clone());
+        assert!(old.diff(&new).is_empty());
+
+        new.remove_subtree(5)?;
+        new.append_to_child(4, 8)?;
+        new.update_title(20, "zustand".to_string())?;
+        new.append_bookmark_to_toolbar("Docs", "https://docs.rs/")?;
+
+        let operations = old.diff(&new);
+        assert_eq!(operations.len(), 4);
+        assert!(matches!(
+            &operations[0],
+            DiffOperation::Removed(node) if node.title == "YouTube"
+        ));
+        assert!(matches!(
+            &operations[1],
+            DiffOperation::Added(node) if node.title == "Docs" && node.path == vec!["Toolbar"]
+        ));
+        assert!(matches!(
+            &operations[2],
+            DiffOperation::Moved { node, from }
+                if node.title == "GitHub" && node.path == vec!["Rust"] && from == &vec!["Toolbar"]
+        ));
+        assert!(matches!(
+            &operations[3],
+            DiffOperation::Renamed { node, from } if node.title == "zustand" && from == "Introduction - Zustand"
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_three_way_merge() -> anyhow::Result<()> {
+        let base = create_realistic_bookmarks();
+        let mut ours = Bookmarks::new(base.arena().clone());
+        let mut theirs = Bookmarks::new(base.arena().clone());
+
+        ours.update_title(3, "Google Search".to_string())?;
+        ours.append_to_child(4, 8)?;
+        theirs.update_title(3, "G".to_string())?;
+        theirs.remove_subtree(5)?;
+        theirs.add_folder(1, "New")?;
+        theirs.update_title(21, "SolidUI".to_string())?;
+
+        let result = Bookmarks::three_way_merge(&base, &ours, &theirs);
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(
+            result.conflicts[0].kind,
+            ConflictKind::Title {
+                ours: "Google Search".to_string(),
+                theirs: "G".to_string()
+            }
+        );
+
+        let merged = result.bookmarks;
+        let arena = merged.arena();
+        let parent_title = |title: &str| {
+            arena
+                .iter()
+                .find(|node| node.get().title == title)
+                .and_then(|node| node.parent())
+                .map(|parent| arena[parent].get().title.clone())
+        };
+        assert_eq!(parent_title("Google Search"), Some("Toolbar".to_string()));
+        assert_eq!(parent_title("GitHub"), Some("Rust".to_string()));
+        assert_eq!(parent_title("YouTube"), None);
+        assert_eq!(parent_title("New"), Some("All Bookmarks".to_string()));
+        assert_eq!(parent_title("SolidUI"), Some("Frontend".to_string()));
+        assert_eq!(merged.count_bookmarks(), base.count_bookmarks() - 1);
+
+        // sibling order of ours is kept
+        let titles = merged
+            .get_toolbar_node_id()?
+            .children(arena)
+            .map(|node_id| arena[node_id].get().title.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            titles,
+            vec!["Google Search", "Github Search", "Inside Toolbar"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_three_way_merge_removed_and_modified() -> anyhow::Result<()> {
+        let base = create_realistic_bookmarks();
+        let mut ours = Bookmarks::new(base.arena().clone());
+        let mut theirs = Bookmarks::new(base.arena().clone());
+
+        // ours removes Frontend folder, theirs renames Zustand inside it
+        ours.remove_subtree(19)?;
+        theirs.update_title(20, "zustand".to_string())?;
+
+        let result = Bookmarks::three_way_merge(&base, &ours, &theirs);
+        let kinds = result
+            .conflicts
+            .iter()
+            .map(|conflict| (conflict.title.as_str(), conflict.kind.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                ("zustand", ConflictKind::RemovedByOurs),
+                ("zustand", ConflictKind::Orphaned)
+            ]
+        );
+        let arena = result.bookmarks.arena();
+        let root_children = result
+            .bookmarks
+            .get_root_node_id()?
+            .children(arena)
+            .map(|node_id| arena[node_id].get().title.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(root_children, vec!["Toolbar", "Rust", "zustand"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_three_way_merge_removed_and_visited() -> anyhow::Result<()> {
+        let base = create_realistic_bookmarks();
+        let mut ours = Bookmarks::new(base.arena().clone());
+        let mut theirs = Bookmarks::new(base.arena().clone());
+
+        // ours removes Zustand, theirs only visits it and collapses its folder
+        let url = base.find_node_by_index(20)?.get().url.clone().unwrap();
+        ours.remove_subtree(20)?;
+        theirs.record_visit(url.as_str())?;
+        theirs.toggle_is_open(19)?;
+
+        let result = Bookmarks::three_way_merge(&base, &ours, &theirs);
+        assert!(result.conflicts.is_empty());
+        assert_eq!(
+            result.bookmarks.count_bookmarks(),
+            base.count_bookmarks() - 1
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_encrypted_file() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("bookmarks");
+        let bookmarks = create_realistic_bookmarks();
+
+        bookmarks.save_to_file(&path)?;
+        assert!(!Bookmarks::is_encrypted_file(&path)?);
+
+        let key = EncryptionKey::derive("correct horse")?;
+        bookmarks.save_to_encrypted_file(&path, &key)?;
+        assert!(Bookmarks::is_encrypted_file(&path)?);
+        assert!(Bookmarks::load_from_file(&path).is_err());
+
+        let (loaded, key) = Bookmarks::load_from_encrypted_file(&path, "correct horse")?;
+        assert_eq!(loaded.to_json()?, bookmarks.to_json()?);
+
+        // saving again with the returned key keeps the passphrase
+        loaded.save_to_encrypted_file(&path, &key)?;
+        assert!(Bookmarks::load_from_encrypted_file(&path, "correct horse").is_ok());
+        let loaded = Bookmarks::load_from_encrypted_file_with_key(&path, &key)?;
+        assert_eq!(loaded.to_json()?, bookmarks.to_json()?);
+
+        assert!(matches!(
+            Bookmarks::load_from_encrypted_file(&path, "wrong"),
+            Err(Error::WrongPassphrase())
+        ));
+
+        let mut data = std::fs::read(&path)?;
+        let middle = data.len() - 10;
+        data[middle] ^= 0xff;
+        std::fs::write(&path, data)?;
+        assert!(matches!(
+            Bookmarks::load_from_encrypted_file(&path, "correct horse"),
+            Err(Error::CorruptedEncryptedFile(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_compact() -> anyhow::Result<()> {
+        let mut bookmarks = create_realistic_bookmarks();
+        // remove "Rust" folder (8-18) and move "Frontend" to the toolbar
+        bookmarks.remove_subtree(8)?;
+        bookmarks.append_to_child(19, 2)?;
+        assert_eq!(bookmarks.count_all_nodes(), 22);
+
+        let dropped = bookmarks.compact()?;
+        assert_eq!(dropped, 11);
+        assert_eq!(bookmarks.count_all_nodes(), 11);
+        assert_eq!(bookmarks.count_bookmarks(), 7);
+
+        // indexes are renumbered in depth-first order
+        let root = bookmarks.get_root_node_id()?;
+        let titles = root
+            .descendants(bookmarks.arena())
+            .enumerate()
+            .map(|(i, node_id)| {
+                let index: usize = node_id.into();
+                assert_eq!(index, i + 1);
+                bookmarks.arena()[node_id].get().title.clone()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(titles[1], "Toolbar");
+        assert_eq!(titles[7], "Frontend");
+        assert_eq!(titles[8], "Introduction - Zustand");
+        assert_eq!(bookmarks.compact()?, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_html() -> anyhow::Result<()> {
+        let mut bookmarks = create_realistic_bookmarks();
+        bookmarks.set_tags(3, vec!["search".to_string(), "daily".to_string()])?;
+        bookmarks.set_keyword(3, Some("g".to_string()))?;
+        let html = bookmarks.to_html(1)?;
+        assert!(html.starts_with("<!DOCTYPE NETSCAPE-Bookmark-file-1>"));
+        assert!(html.contains("PERSONAL_TOOLBAR_FOLDER=\"true\">Toolbar</H3>"));
+        assert!(html.contains("TAGS=\"search,daily\" SHORTCUTURL=\"g\">Google</A>"));
+
+        // bookmarks having children are exported as folders
+        assert!(html.contains("\">tauri - Rust</H3>"));
+
+        // the toolbar of the file is merged into the existing toolbar
+        let mut imported = Bookmarks::default();
+        let summary = imported.import_html(&html, 1)?;
+        assert_eq!(summary.bookmarks, bookmarks.count_bookmarks());
+        assert_eq!(summary.folders, 6);
+        assert_eq!(summary.skipped, 0);
+        assert!(imported.validate().is_empty());
+        assert_eq!(imported.to_html(1)?, html);
+        bookmarks.lift_nested_bookmarks()?;
+        assert_eq!(imported.count_all_nodes(), bookmarks.count_all_nodes());
+        assert_eq!(bookmarks.to_html(1)?, html);
+
+        // other browsers' files
+        let html = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<TITLE>Bookmarks</TITLE>
+<H1>Bookmarks</H1>
+<DL><p>
+    <DT><H3 ADD_DATE="1700000000">Docs &amp; Guides</H3>
+    <DL><p>
+        <DT><A HREF="https://doc.rust-lang.org/book/" ADD_DATE="1700000001">The Book</A>
+        <DD>description is ignored
+        <DT><A HREF="javascript:alert(1)">Bookmarklet</A>
+    </DL><p>
+    <DT><a href="https://crates.io/">crates.io</a>
+</DL><p>
+"#;
+        let mut imported = Bookmarks::default();
+        let toolbar = imported.get_toolbar_node_id()?;
+        let summary = imported.import_html(html, toolbar.into())?;
+        assert_eq!(summary.bookmarks, 2);
+        assert_eq!(summary.folders, 1);
+        assert_eq!(summary.skipped, 1);
+        let folder = imported.find_node_by_index(3)?.get();
+        assert_eq!(folder.title, "Docs & Guides");
+        assert_eq!(folder.date_added, Some(1_700_000_000_000));
+        let book = imported.find_node_by_index(4)?;
+        assert_eq!(book.get().title, "The Book");
+        assert_eq!(book.parent(), imported.find_node_id_by_index(3).ok());
+        let crates = imported.find_node_by_index(5)?;
+        assert_eq!(crates.parent(), Some(toolbar));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_and_repair() -> anyhow::Result<()> {
+        let mut realistic = create_realistic_bookmarks();
+        assert_eq!(
+            realistic.validate(),
+            vec![
+                Problem::BookmarkHasChildren { index: 9 },
+                Problem::BookmarkHasChildren { index: 13 },
+                Problem::BookmarkHasChildren { index: 16 },
+            ]
+        );
+        realistic.repair()?;
+        assert!(realistic.validate().is_empty());
+        assert_eq!(realistic.count_bookmarks(), 17);
+        assert!(Bookmarks::default().validate().is_empty());
+
+        let mut folder_with_url = BookmarkData::new_folder("Folder");
+        folder_with_url.url = Some("https://example.com/".parse()?);
+        let mut wrong_host = BookmarkData::try_new_bookmark("Rust", "https://www.rust-lang.org/")?;
+        wrong_host.host = Some("example.com".to_string());
+        let mut no_url = BookmarkData::try_new_bookmark("No URL", "https://example.com/")?;
+        no_url.url = None;
+        let mut arena = Arena::new();
+        let root = BookmarkData::new_root();
+        tree!(&mut arena,
+            root => {
+                folder_with_url,
+                wrong_host,
+                BookmarkData::try_new_bookmark("Parent", "https://docs.rs/")? => {
+                    BookmarkData::try_new_bookmark("Child 1", "https://docs.rs/a")?,
+                    BookmarkData::try_new_bookmark("Child 2", "https://docs.rs/b")?,
+                },
+                no_url,
+            }
+        );
+        let mut bookmarks = Bookmarks::new(arena);
+
+        let problems = bookmarks.validate();
+        assert_eq!(
+            problems,
+            vec![
+                Problem::UnexpectedUrl {
+                    index: 2,
+                    node_type: NodeType::Folder
+                },
+                Problem::HostMismatch {
+                    index: 3,
+                    expected: Some("www.rust-lang.org".to_string()),
+                    actual: Some("example.com".to_string()),
+                },
+                Problem::BookmarkHasChildren { index: 4 },
+                Problem::MissingUrl { index: 7 },
+                Problem::ToolbarNotFound,
+            ]
+        );
+
+        assert_eq!(bookmarks.repair()?, problems);
+        assert!(bookmarks.validate().is_empty());
+        let root = bookmarks.get_root_node_id()?;
+        let titles = root
+            .children(bookmarks.arena())
+            .map(|node_id| bookmarks.arena()[node_id].get().title.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            titles,
+            vec!["Toolbar", "Folder", "Rust", "Parent", "No URL"]
+        );
+        // the bookmark is lifted into a folder with its children
+        let parent = bookmarks.find_node_by_index(4)?;
+        assert!(parent.get().is_bookmark());
+        let folder = parent.parent().expect("lifted into a folder");
+        assert!(bookmarks.arena()[folder].get().is_folder());
+        assert_eq!(folder.children(bookmarks.arena()).count(), 3);
+
+        // a bookmark without URL having children becomes a folder only once
+        let mut no_url = BookmarkData::try_new_bookmark("No URL", "https://example.com/")?;
+        no_url.url = None;
+        let mut arena = Arena::new();
+        let root = BookmarkData::new_root();
+        tree!(&mut arena,
+            root => {
+                BookmarkData::new_folder("Toolbar"),
+                no_url => {
+                    BookmarkData::try_new_bookmark("Child", "https://example.com/a")?,
+                },
+            }
+        );
+        let mut bookmarks = Bookmarks::new(arena);
+        let problems = bookmarks.repair()?;
+        assert!(problems.contains(&Problem::MissingUrl { index: 3 }));
+        assert!(problems.contains(&Problem::BookmarkHasChildren { index: 3 }));
+        assert!(bookmarks.validate().is_empty());
+        let folder = bookmarks.find_node_by_index(3)?;
+        assert!(folder.get().is_folder());
+        assert_eq!(folder.parent(), Some(bookmarks.get_root_node_id()?));
+        assert_eq!(bookmarks.count_bookmarks(), 1);
+
+        // duplicate toolbars are merged into the first one
+        let mut bookmarks = Bookmarks::default();
+        bookmarks.append_bookmark_to_toolbar("Google", "https://www.google.com/")?;
+        let second = bookmarks.add_folder(1, "Toolbar")?;
+        bookmarks.append_bookmark(second, "GitHub", "https://github.com/")?;
+        assert_eq!(
+            bookmarks.validate(),
+            vec![Problem::DuplicateToolbar {
+                indexes: vec![2, second]
+            }]
+        );
+        bookmarks.repair()?;
+        assert!(bookmarks.validate().is_empty());
+        assert_eq!(bookmarks.get_toolbar_bookmarks().len(), 2);
+
+        // index 1 is not a root
+        let mut arena = Arena::new();
+        let folder = BookmarkData::new_folder("Folder");
+        tree!(&mut arena, folder => { BookmarkData::new_folder("Toolbar") });
+        let bookmarks = Bookmarks::new(arena);
+        assert_eq!(
+            bookmarks.validate(),
+            vec![Problem::NotRoot {
+                index: 1,
+                node_type: NodeType::Folder
+            }]
+        );
+        assert_eq!(
+            Bookmarks::new(Arena::new()).validate(),
+            vec![Problem::RootNotFound]
+        );
+
+        Ok(())
+    }
 }
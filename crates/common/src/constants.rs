@@ -12,3 +12,6 @@ pub const DEFAULT_WINDOW_X: f64 = 50.0;
 pub const DEFAULT_WINDOW_Y: f64 = 50.0;
 pub const DEFAULT_SIDEBAR_WIDTH: f64 = 200.0;
 pub const DEFAULT_HEADER_HEIGHT: f64 = 40.0;
+
+/// Id of the bookmark collection stored in the original bookmarks file
+pub const DEFAULT_BOOKMARK_COLLECTION_ID: &str = "default";
@@ -14,6 +14,15 @@ pub enum Error {
     #[error(transparent)]
     Tauri(#[from] tauri::Error),
 
+    #[error("bookmarks are locked")]
+    BookmarksLocked,
+
+    #[error("collection not found: {0}")]
+    CollectionNotFound(String),
+
+    #[error("invalid collection: {0}")]
+    InvalidCollection(String),
+
     #[error("Mutex Error: {0}")]
     Mutex(String),
 
@@ -23,6 +32,9 @@ pub enum Error {
     #[error(transparent)]
     Core(#[from] parus_bookmark::Error),
 
+    #[error(transparent)]
+    Sync(#[from] parus_sync::Error),
+
     #[error("other error: {0}")]
     Other(String),
 }
@@ -35,8 +47,12 @@ pub enum ErrorKind {
     Utf8(String),
     Json(String),
     WebviewNotFound(String),
+    BookmarksLocked(String),
+    CollectionNotFound(String),
+    InvalidCollection(String),
     Tauri(String),
     Core(String),
+    Sync(String),
     Mutex(String),
     Other(String),
 }
@@ -52,8 +68,12 @@ impl serde::Serialize for Error {
             Self::Utf8(_) => ErrorKind::Utf8(error_message),
             Self::Json(_) => ErrorKind::Json(error_message),
             Self::WebviewNotFound => ErrorKind::WebviewNotFound(error_message),
+            Self::BookmarksLocked => ErrorKind::BookmarksLocked(error_message),
+            Self::CollectionNotFound(_) => ErrorKind::CollectionNotFound(error_message),
+            Self::InvalidCollection(_) => ErrorKind::InvalidCollection(error_message),
             Self::Tauri(_) => ErrorKind::Tauri(error_message),
             Self::Core(_) => ErrorKind::Core(error_message),
+            Self::Sync(_) => ErrorKind::Sync(error_message),
             Self::Mutex(_) => ErrorKind::Mutex(error_message),
             Self::Other(_) => ErrorKind::Other(error_message),
         };
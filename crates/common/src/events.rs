@@ -16,4 +16,7 @@ pub enum AppEvent {
 
     #[strum(serialize = "external://url-changed")]
     ExternalUrlChanged,
+
+    #[strum(serialize = "external://page-read")]
+    ExternalPageRead,
 }
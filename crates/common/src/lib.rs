@@ -20,6 +20,12 @@ use utils::create_dir_if_not_exists;
 pub enum FileName {
     #[strum(serialize = "dev-bookmarks.json")]
     Bookmarks,
+    #[strum(serialize = "dev-bookmarks_sync.json")]
+    BookmarksSync,
+    #[strum(serialize = "dev-bookmarks_sync_base.json")]
+    BookmarksSyncBase,
+    #[strum(serialize = "dev-bookmark_collections.json")]
+    BookmarkCollections,
     #[strum(serialize = "dev-app_settings.json")]
     AppSettings,
     #[strum(serialize = "dev-window_geometry.json")]
@@ -38,6 +44,12 @@ pub enum FileName {
 pub enum FileName {
     #[strum(serialize = ".bookmarks")]
     Bookmarks,
+    #[strum(serialize = ".bookmarks_sync")]
+    BookmarksSync,
+    #[strum(serialize = ".bookmarks_sync_base")]
+    BookmarksSyncBase,
+    #[strum(serialize = ".bookmark_collections")]
+    BookmarkCollections,
     #[strum(serialize = ".app_settings")]
     AppSettings,
     #[strum(serialize = ".window_geometry")]
@@ -76,6 +88,9 @@ pub trait AppHandlePathExt {
     /// Get user scripts dir
     fn get_user_scripts_dir(&self) -> PathBuf;
 
+    /// Get the directory of bookmark collections other than the default one
+    fn get_bookmark_collections_dir(&self) -> PathBuf;
+
     /// Get file path in application directory
     fn get_file_path_in_app_dir(&self, file_name: FileName) -> PathBuf;
 
@@ -88,6 +103,15 @@ pub trait AppHandlePathExt {
     /// Bookmarks file path
     fn bookmarks_path(&self) -> PathBuf;
 
+    /// Bookmarks sync settings file path
+    fn bookmarks_sync_path(&self) -> PathBuf;
+
+    /// Bookmarks at the last sync, used as the common ancestor of the next sync
+    fn bookmarks_sync_base_path(&self) -> PathBuf;
+
+    /// List of bookmark collections file path
+    fn bookmark_collections_path(&self) -> PathBuf;
+
     /// Window geometry file path
     fn window_geometry_path(&self) -> PathBuf;
 
@@ -111,6 +135,17 @@ impl<R: tauri::Runtime> AppHandlePathExt for tauri::AppHandle<R> {
         path
     }
 
+    fn get_bookmark_collections_dir(&self) -> PathBuf {
+        let name = if cfg!(debug_assertions) {
+            "dev-collections"
+        } else {
+            "collections"
+        };
+        let path = self.get_app_dir().join(name);
+        create_dir_if_not_exists(&path);
+        path
+    }
+
     fn get_file_path_in_app_dir(&self, file_name: FileName) -> PathBuf {
         self.get_app_dir().join(file_name.as_ref())
     }
@@ -131,6 +166,18 @@ impl<R: tauri::Runtime> AppHandlePathExt for tauri::AppHandle<R> {
         self.get_file_path_in_app_dir(FileName::Bookmarks)
     }
 
+    fn bookmarks_sync_path(&self) -> PathBuf {
+        self.get_file_path_in_app_dir(FileName::BookmarksSync)
+    }
+
+    fn bookmarks_sync_base_path(&self) -> PathBuf {
+        self.get_file_path_in_app_dir(FileName::BookmarksSyncBase)
+    }
+
+    fn bookmark_collections_path(&self) -> PathBuf {
+        self.get_file_path_in_app_dir(FileName::BookmarkCollections)
+    }
+
     fn favicon_database_path(&self) -> PathBuf {
         self.get_file_path_in_app_dir(FileName::FaviconDatabase)
     }
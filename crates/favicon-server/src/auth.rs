@@ -0,0 +1,67 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use reqwest::StatusCode;
+
+/// Header of the token, for `fetch` requests
+pub const TOKEN_HEADER: &str = "x-favicon-token";
+
+/// Query parameter of the token, for `<img>` which can't set headers
+pub const TOKEN_QUERY: &str = "token";
+
+/// Random token generated on each launch
+pub(crate) fn generate_token() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+/// Reject requests without the token of this launch so that other local processes can't use the server
+pub(crate) async fn require_token(
+    State(token): State<Arc<str>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let header = request
+        .headers()
+        .get(TOKEN_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let query = request.uri().query().and_then(|query| {
+        url::form_urlencoded::parse(query.as_bytes())
+            .find(|(key, _)| key == TOKEN_QUERY)
+            .map(|(_, value)| value.into_owned())
+    });
+    let authorized = [header, query]
+        .iter()
+        .flatten()
+        .any(|given| constant_time_eq(given.as_bytes(), token.as_bytes()));
+    if authorized {
+        next.run(request).await
+    } else {
+        (StatusCode::UNAUTHORIZED, "Invalid token").into_response()
+    }
+}
+
+/// Compare without returning early not to leak the token by timing
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token() {
+        let token = generate_token();
+        assert_eq!(token.len(), 32);
+        assert_ne!(token, generate_token());
+
+        assert!(constant_time_eq(token.as_bytes(), token.as_bytes()));
+        assert!(!constant_time_eq(token.as_bytes(), b"guess"));
+        assert!(!constant_time_eq(b"", token.as_bytes()));
+    }
+}
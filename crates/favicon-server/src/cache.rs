@@ -0,0 +1,163 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::favicon::Favicon;
+
+/// How long cached favicons and failures are used
+#[derive(Debug, Clone)]
+pub struct CachePolicy {
+    /// Favicons fetched within this duration are served without refreshing
+    pub ttl: Duration,
+    /// Failed fetches are not retried within this duration
+    pub negative_ttl: Duration,
+    /// Expired favicons are served while they are refreshed in the background
+    /// until they are older than `ttl + max_stale`
+    pub max_stale: Duration,
+}
+
+impl Default for CachePolicy {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(7 * 24 * 60 * 60),
+            negative_ttl: Duration::from_secs(60 * 60),
+            max_stale: Duration::from_secs(30 * 24 * 60 * 60),
+        }
+    }
+}
+
+/// Result of the last fetch of a host
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct FetchRecord {
+    /// Seconds since the UNIX epoch
+    pub fetched_at: u64,
+    pub failed: bool,
+}
+
+impl FetchRecord {
+    pub(crate) fn succeeded_now() -> Self {
+        Self {
+            fetched_at: now(),
+            failed: false,
+        }
+    }
+
+    pub(crate) fn failed_now() -> Self {
+        Self {
+            fetched_at: now(),
+            failed: true,
+        }
+    }
+}
+
+/// Cached favicon of a host.
+/// A failure keeps the previous favicon, a host which never succeeded has no favicon.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CacheEntry {
+    pub favicon: Option<Favicon>,
+    /// None for favicons cached before fetches were recorded
+    pub record: Option<FetchRecord>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Freshness {
+    /// Use as is, a negative entry means the host is not fetched again yet
+    Fresh,
+    /// Serve the favicon and refresh it in the background
+    Stale,
+    /// Fetch again before responding
+    Expired,
+}
+
+impl CacheEntry {
+    pub(crate) fn freshness(&self, policy: &CachePolicy, now: u64) -> Freshness {
+        let Some(record) = self.record else {
+            return Freshness::Stale;
+        };
+        let age = Duration::from_secs(now.saturating_sub(record.fetched_at));
+        let ttl = if record.failed {
+            policy.negative_ttl
+        } else {
+            policy.ttl
+        };
+        if age < ttl {
+            Freshness::Fresh
+        } else if self.favicon.is_some() && age < policy.ttl + policy.max_stale {
+            Freshness::Stale
+        } else {
+            Freshness::Expired
+        }
+    }
+}
+
+/// Key of the favicon of the host in the size, `www.` variants share the favicon
+pub(crate) fn cache_key(host: &str, size: u32) -> String {
+    format!("{}@{size}", parus_url::host_key(host))
+}
+
+/// Host key and the size of a cache key, keys before sizes were supported are of 16px
+pub(crate) fn parse_cache_key(key: &str) -> (&str, u32) {
+    match key.rsplit_once('@') {
+        Some((host, size)) => (host, size.parse().unwrap_or(16)),
+        None => (key, 16),
+    }
+}
+
+/// Seconds since the UNIX epoch
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::favicon::PNG;
+
+    #[test]
+    fn test_cache_key() {
+        assert_eq!(cache_key("www.Docs.rs", 32), "docs.rs@32");
+        assert_eq!(parse_cache_key("docs.rs@32"), ("docs.rs", 32));
+        assert_eq!(parse_cache_key("docs.rs"), ("docs.rs", 16));
+    }
+
+    #[test]
+    fn test_freshness() {
+        let policy = CachePolicy {
+            ttl: Duration::from_secs(100),
+            negative_ttl: Duration::from_secs(10),
+            max_stale: Duration::from_secs(1000),
+        };
+        let favicon = Favicon {
+            mime_type: PNG.to_string(),
+            data: vec![1],
+        };
+        let entry = |favicon: Option<Favicon>, failed| CacheEntry {
+            favicon,
+            record: Some(FetchRecord {
+                fetched_at: 1000,
+                failed,
+            }),
+        };
+
+        let cached = entry(Some(favicon.clone()), false);
+        assert_eq!(cached.freshness(&policy, 1050), Freshness::Fresh);
+        assert_eq!(cached.freshness(&policy, 1100), Freshness::Stale);
+        assert_eq!(cached.freshness(&policy, 2100), Freshness::Expired);
+
+        let negative = entry(None, true);
+        assert_eq!(negative.freshness(&policy, 1005), Freshness::Fresh);
+        assert_eq!(negative.freshness(&policy, 1010), Freshness::Expired);
+
+        // the previous favicon is served while the failed host is retried
+        let failed_refresh = entry(Some(favicon.clone()), true);
+        assert_eq!(failed_refresh.freshness(&policy, 1005), Freshness::Fresh);
+        assert_eq!(failed_refresh.freshness(&policy, 1010), Freshness::Stale);
+
+        let unrecorded = CacheEntry {
+            favicon: Some(favicon),
+            record: None,
+        };
+        assert_eq!(unrecorded.freshness(&policy, 1000), Freshness::Stale);
+    }
+}
@@ -0,0 +1,137 @@
+use std::{fs::File, io::Read};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use url::Url;
+
+use crate::{
+    cache::cache_key,
+    db,
+    error::Error,
+    favicon::{Favicon, SIZES},
+    AppState,
+};
+
+/// Files larger than this are rejected without reading them to the end
+const MAX_IMAGE_FILE_SIZE: u64 = 4 * 1024 * 1024;
+
+/// Bytes of an image given as a data URL, a `file://` URL or a file path.
+/// This blocks while reading the file, so async code should call it on a blocking thread.
+pub fn read_image_source(source: &str) -> Result<Vec<u8>, Error> {
+    let source = source.trim();
+    if let Some(data_url) = source.strip_prefix("data:") {
+        return decode_data_url(data_url);
+    }
+    let path = match Url::parse(source) {
+        Ok(url) if url.scheme() == "file" => url
+            .to_file_path()
+            .map_err(|_| Error::InvalidImage(format!("Invalid file URL: {source}")))?,
+        _ => source.into(),
+    };
+    let mut data = Vec::new();
+    File::open(path)?
+        .take(MAX_IMAGE_FILE_SIZE + 1)
+        .read_to_end(&mut data)?;
+    if data.len() as u64 > MAX_IMAGE_FILE_SIZE {
+        return Err(Error::InvalidImage(format!(
+            "Image file is larger than {} MB",
+            MAX_IMAGE_FILE_SIZE / 1024 / 1024
+        )));
+    }
+    Ok(data)
+}
+
+/// Data of a data URL without the `data:` prefix, base64 or percent-encoded
+fn decode_data_url(data_url: &str) -> Result<Vec<u8>, Error> {
+    let (header, data) = data_url
+        .split_once(',')
+        .ok_or_else(|| Error::InvalidImage("Data URL has no data".to_string()))?;
+    if header.ends_with(";base64") {
+        // whitespace is allowed in pasted data URLs
+        let data = data
+            .chars()
+            .filter(|c| !c.is_ascii_whitespace())
+            .collect::<String>();
+        let data = percent_encoding::percent_decode_str(&data).collect::<Vec<_>>();
+        STANDARD
+            .decode(data)
+            .map_err(|e| Error::InvalidImage(format!("Invalid base64: {e}")))
+    } else {
+        Ok(percent_encoding::percent_decode_str(data).collect())
+    }
+}
+
+/// Set the image as the favicon of the host in all sizes.
+/// Custom favicons are stored apart from the cache, so refreshes never overwrite them.
+pub(crate) async fn set_custom(state: &AppState, host: &str, data: Vec<u8>) -> Result<(), Error> {
+    let favicon = Favicon::sniff(data)
+        .ok_or_else(|| Error::InvalidImage(format!("Unsupported image for {host}")))?;
+    let favicons = SIZES
+        .iter()
+        .map(|size| {
+            let mut favicon = favicon.clone();
+            if state.config.ico_to_png {
+                favicon = favicon.ico_to_png(*size);
+            }
+            (cache_key(host, *size), favicon.downscale(*size))
+        })
+        .collect::<Vec<_>>();
+    state
+        .write_db(move |db| db::insert_custom(db, &favicons))
+        .await
+}
+
+/// Remove the custom favicon of the host to fetch it automatically again.
+/// Returns false if the host has no custom favicon.
+pub(crate) async fn reset_custom(state: &AppState, host: &str) -> Result<bool, Error> {
+    let keys = SIZES
+        .iter()
+        .map(|size| cache_key(host, *size))
+        .collect::<Vec<_>>();
+    let removed = state
+        .write_db(move |db| db::remove_custom(db, &keys))
+        .await?;
+    Ok(removed > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::favicon::tests::ico_data;
+
+    #[test]
+    fn test_read_image_source() {
+        let svg = "<svg xmlns=\"http://www.w3.org/2000/svg\"/>";
+        assert_eq!(
+            read_image_source(
+                "data:image/svg+xml,%3Csvg%20xmlns=%22http://www.w3.org/2000/svg%22/%3E"
+            )
+            .unwrap(),
+            svg.as_bytes()
+        );
+        let base64 = format!("data:image/svg+xml;base64,{}", STANDARD.encode(svg));
+        assert_eq!(read_image_source(&base64).unwrap(), svg.as_bytes());
+        assert!(matches!(
+            read_image_source("data:image/png;base64,!!!"),
+            Err(Error::InvalidImage(_))
+        ));
+
+        let path = std::env::temp_dir().join("parus-custom-favicon-test.ico");
+        std::fs::write(&path, ico_data()).unwrap();
+        assert_eq!(
+            read_image_source(path.to_str().unwrap()).unwrap(),
+            ico_data()
+        );
+        let file_url = Url::from_file_path(&path).unwrap();
+        assert_eq!(read_image_source(file_url.as_str()).unwrap(), ico_data());
+        std::fs::write(&path, vec![0; MAX_IMAGE_FILE_SIZE as usize + 1]).unwrap();
+        assert!(matches!(
+            read_image_source(path.to_str().unwrap()),
+            Err(Error::InvalidImage(_))
+        ));
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            read_image_source(path.to_str().unwrap()),
+            Err(Error::Io(_))
+        ));
+    }
+}
@@ -1,45 +1,385 @@
-use redb::{Database, ReadableTable};
+//! Tables of the cache. Writes block while committing to the disk,
+//! so async code runs them on a blocking thread by `AppState::write_db`.
 
-use crate::{error::Error, FAVICON_TABLE};
+#[cfg(any(feature = "http", test))]
+use std::collections::{BTreeMap, BTreeSet};
 
-pub(crate) async fn find(
-    db: &Database,
-    key: &str,
-) -> Result<Option<redb::AccessGuard<'static, &'static [u8]>>, Error> {
+use redb::{Database, ReadableTable, TableHandle};
+
+use crate::{
+    cache::{cache_key, parse_cache_key, CacheEntry, FetchRecord},
+    error::Error,
+    favicon::{sniff_mime_type, Favicon},
+    CUSTOM_FAVICON_TABLE, FAVICON_TABLE, FETCH_TABLE, LEGACY_FAVICON_TABLE,
+};
+
+/// Create the tables and move favicons from the legacy table which stored only the bytes.
+/// Legacy entries which are not images are dropped to be fetched again.
+/// Keys before sizes were supported are renamed into the keys of 16px.
+pub(crate) fn init(db: &Database) -> Result<usize, Error> {
+    let write_txn = db.begin_write()?;
+    let mut migrated = 0;
+    {
+        let mut table = write_txn.open_table(FAVICON_TABLE)?;
+        let has_legacy = write_txn
+            .list_tables()?
+            .any(|handle| handle.name() == LEGACY_FAVICON_TABLE.name());
+        if has_legacy {
+            let legacy = write_txn.open_table(LEGACY_FAVICON_TABLE)?;
+            for entry in legacy.iter()? {
+                let (key, value) = entry?;
+                if let Some(mime_type) = sniff_mime_type(value.value()) {
+                    table.insert(key.value(), (mime_type, value.value()))?;
+                    migrated += 1;
+                }
+            }
+            drop(legacy);
+            write_txn.delete_table(LEGACY_FAVICON_TABLE)?;
+            log::info!("Migrated {} favicons", migrated);
+        }
+
+        for key in unsized_keys(table.iter()?)? {
+            let value = table.remove(key.as_str())?.map(|value| {
+                let (mime_type, data) = value.value();
+                (mime_type.to_string(), data.to_vec())
+            });
+            if let Some((mime_type, data)) = value {
+                table.insert(
+                    sized_key(&key).as_str(),
+                    (mime_type.as_str(), data.as_slice()),
+                )?;
+            }
+        }
+        let mut fetch_table = write_txn.open_table(FETCH_TABLE)?;
+        for key in unsized_keys(fetch_table.iter()?)? {
+            let record = fetch_table.remove(key.as_str())?.map(|value| value.value());
+            if let Some(record) = record {
+                fetch_table.insert(sized_key(&key).as_str(), record)?;
+            }
+        }
+        write_txn.open_table(CUSTOM_FAVICON_TABLE)?;
+    }
+    write_txn.commit()?;
+    Ok(migrated)
+}
+
+fn unsized_keys<V: redb::Value>(
+    iter: redb::Range<'_, &'static str, V>,
+) -> Result<Vec<String>, Error> {
+    let mut keys = Vec::new();
+    for entry in iter {
+        let key = entry?.0.value().to_string();
+        if !key.contains('@') {
+            keys.push(key);
+        }
+    }
+    Ok(keys)
+}
+
+/// Legacy keys are raw hosts like `www.github.com`, normalized as `cache_key` does
+fn sized_key(key: &str) -> String {
+    let (host, size) = parse_cache_key(key);
+    cache_key(host, size)
+}
+
+/// Cached favicon and the last fetch of the key, None if the key has never been fetched
+pub(crate) async fn find(db: &Database, key: &str) -> Result<Option<CacheEntry>, Error> {
     let read_txn = db.begin_read()?;
     let table = read_txn.open_table(FAVICON_TABLE)?;
-    let value = table.get(key)?;
-    Ok(value)
+    let favicon = table.get(key)?.map(|value| {
+        let (mime_type, data) = value.value();
+        Favicon {
+            mime_type: mime_type.to_string(),
+            data: data.to_vec(),
+        }
+    });
+    let fetch_table = read_txn.open_table(FETCH_TABLE)?;
+    let record = fetch_table.get(key)?.map(|value| {
+        let (fetched_at, failed) = value.value();
+        FetchRecord { fetched_at, failed }
+    });
+    if favicon.is_none() && record.is_none() {
+        return Ok(None);
+    }
+    Ok(Some(CacheEntry { favicon, record }))
 }
 
+/// Keys of favicons and failed fetches.
+/// The cache is managed only through the HTTP server.
+#[cfg(any(feature = "http", test))]
 pub(crate) async fn keys(db: &Database) -> Result<Vec<String>, Error> {
     let read_txn = db.begin_read()?;
     let table = read_txn.open_table(FAVICON_TABLE)?;
-    let iter = table.iter()?;
-    let keys = iter
-        .filter_map(|r| r.ok())
-        .map(|r| r.0.value().to_string())
-        .collect::<Vec<_>>();
+    let fetch_table = read_txn.open_table(FETCH_TABLE)?;
+    let mut keys = BTreeSet::new();
+    for entry in table.iter()?.filter_map(|r| r.ok()) {
+        keys.insert(entry.0.value().to_string());
+    }
+    for entry in fetch_table.iter()?.filter_map(|r| r.ok()) {
+        keys.insert(entry.0.value().to_string());
+    }
 
-    Ok(keys)
+    Ok(keys.into_iter().collect())
+}
+
+/// Cached entry without the bytes of the favicon
+#[cfg(any(feature = "http", test))]
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct EntrySummary {
+    pub key: String,
+    /// None for a host which has never been fetched successfully
+    pub mime_type: Option<String>,
+    pub bytes: usize,
+    pub record: Option<FetchRecord>,
+}
+
+/// Summaries of all entries ordered by key
+#[cfg(any(feature = "http", test))]
+pub(crate) async fn summaries(db: &Database) -> Result<Vec<EntrySummary>, Error> {
+    let read_txn = db.begin_read()?;
+    let table = read_txn.open_table(FAVICON_TABLE)?;
+    let fetch_table = read_txn.open_table(FETCH_TABLE)?;
+    let mut summaries: BTreeMap<String, EntrySummary> = BTreeMap::new();
+    for entry in table.iter()? {
+        let (key, value) = entry?;
+        let (mime_type, data) = value.value();
+        summaries.insert(
+            key.value().to_string(),
+            EntrySummary {
+                key: key.value().to_string(),
+                mime_type: Some(mime_type.to_string()),
+                bytes: data.len(),
+                record: None,
+            },
+        );
+    }
+    for entry in fetch_table.iter()? {
+        let (key, value) = entry?;
+        let (fetched_at, failed) = value.value();
+        summaries
+            .entry(key.value().to_string())
+            .or_insert_with(|| EntrySummary {
+                key: key.value().to_string(),
+                mime_type: None,
+                bytes: 0,
+                record: None,
+            })
+            .record = Some(FetchRecord { fetched_at, failed });
+    }
+    Ok(summaries.into_values().collect())
+}
+
+/// Remove the keys in a transaction, returns the number of removed entries
+#[cfg(any(feature = "http", test))]
+pub(crate) fn remove_keys(db: &Database, keys: &[String]) -> Result<usize, Error> {
+    let write_txn = db.begin_write()?;
+    let mut removed = 0;
+    {
+        let mut table = write_txn.open_table(FAVICON_TABLE)?;
+        let mut fetch_table = write_txn.open_table(FETCH_TABLE)?;
+        for key in keys {
+            let had_favicon = table.remove(key.as_str())?.is_some();
+            let had_record = fetch_table.remove(key.as_str())?.is_some();
+            if had_favicon || had_record {
+                removed += 1;
+            }
+        }
+    }
+    write_txn.commit()?;
+    Ok(removed)
 }
 
-pub(crate) async fn remove(db: &Database, key: &str) -> Result<(), Error> {
+/// Remove all entries in a transaction
+#[cfg(any(feature = "http", test))]
+pub(crate) fn clear(db: &Database) -> Result<(), Error> {
     let write_txn = db.begin_write()?;
     {
         let mut table = write_txn.open_table(FAVICON_TABLE)?;
-        table.remove(key)?;
+        table.retain(|_, _| false)?;
+        let mut fetch_table = write_txn.open_table(FETCH_TABLE)?;
+        fetch_table.retain(|_, _| false)?;
     }
     write_txn.commit()?;
     Ok(())
 }
 
-pub(crate) async fn insert(db: &Database, key: &str, value: &[u8]) -> Result<(), Error> {
+pub(crate) fn insert(
+    db: &Database,
+    key: &str,
+    favicon: &Favicon,
+    record: FetchRecord,
+) -> Result<(), Error> {
     let write_txn = db.begin_write()?;
     {
         let mut table = write_txn.open_table(FAVICON_TABLE)?;
-        table.insert(key, value)?;
+        table.insert(key, (favicon.mime_type.as_str(), favicon.data.as_slice()))?;
+        let mut fetch_table = write_txn.open_table(FETCH_TABLE)?;
+        fetch_table.insert(key, (record.fetched_at, record.failed))?;
     }
     write_txn.commit()?;
     Ok(())
 }
+
+/// Record a failed fetch, the cached favicon if any is kept
+pub(crate) fn insert_failure(db: &Database, key: &str, record: FetchRecord) -> Result<(), Error> {
+    let write_txn = db.begin_write()?;
+    {
+        let mut fetch_table = write_txn.open_table(FETCH_TABLE)?;
+        fetch_table.insert(key, (record.fetched_at, record.failed))?;
+    }
+    write_txn.commit()?;
+    Ok(())
+}
+
+/// Favicon set by the user for the key, which is served instead of the cached one
+pub(crate) async fn find_custom(db: &Database, key: &str) -> Result<Option<Favicon>, Error> {
+    let read_txn = db.begin_read()?;
+    let table = read_txn.open_table(CUSTOM_FAVICON_TABLE)?;
+    let favicon = table.get(key)?.map(|value| {
+        let (mime_type, data) = value.value();
+        Favicon {
+            mime_type: mime_type.to_string(),
+            data: data.to_vec(),
+        }
+    });
+    Ok(favicon)
+}
+
+/// Set the favicons of the keys in a transaction, replacing the previous custom ones
+pub(crate) fn insert_custom(db: &Database, favicons: &[(String, Favicon)]) -> Result<(), Error> {
+    let write_txn = db.begin_write()?;
+    {
+        let mut table = write_txn.open_table(CUSTOM_FAVICON_TABLE)?;
+        for (key, favicon) in favicons {
+            table.insert(
+                key.as_str(),
+                (favicon.mime_type.as_str(), favicon.data.as_slice()),
+            )?;
+        }
+    }
+    write_txn.commit()?;
+    Ok(())
+}
+
+/// Remove the custom favicons of the keys, returns the number of removed ones
+pub(crate) fn remove_custom(db: &Database, keys: &[String]) -> Result<usize, Error> {
+    let write_txn = db.begin_write()?;
+    let mut removed = 0;
+    {
+        let mut table = write_txn.open_table(CUSTOM_FAVICON_TABLE)?;
+        for key in keys {
+            if table.remove(key.as_str())?.is_some() {
+                removed += 1;
+            }
+        }
+    }
+    write_txn.commit()?;
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use redb::backends::InMemoryBackend;
+
+    use super::*;
+    use crate::favicon::{tests::ico_data, ICO};
+
+    #[tokio::test]
+    async fn test_migrate_legacy_table() {
+        let db = Database::builder()
+            .create_with_backend(InMemoryBackend::new())
+            .unwrap();
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut legacy = write_txn.open_table(LEGACY_FAVICON_TABLE).unwrap();
+            legacy.insert("docs.rs", ico_data().as_slice()).unwrap();
+            legacy
+                .insert("www.GitHub.com", ico_data().as_slice())
+                .unwrap();
+            legacy
+                .insert("broken.example", b"<html>".as_slice())
+                .unwrap();
+        }
+        write_txn.commit().unwrap();
+
+        assert_eq!(init(&db).unwrap(), 2);
+        let entry = find(&db, "docs.rs@16").await.unwrap().unwrap();
+        let favicon = entry.favicon.unwrap();
+        assert_eq!(favicon.mime_type, ICO);
+        assert_eq!(favicon.data, ico_data());
+        // migrated favicons have never been fetched with a record
+        assert_eq!(entry.record, None);
+        // migrated keys are normalized like the keys of new favicons
+        assert_eq!(keys(&db).await.unwrap(), ["docs.rs@16", "github.com@16"]);
+
+        // the legacy table is gone
+        assert_eq!(init(&db).unwrap(), 0);
+        assert_eq!(keys(&db).await.unwrap(), ["docs.rs@16", "github.com@16"]);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_records() {
+        let db = Database::builder()
+            .create_with_backend(InMemoryBackend::new())
+            .unwrap();
+        init(&db).unwrap();
+        let favicon = Favicon::sniff(ico_data()).unwrap();
+        let record = |failed| FetchRecord {
+            fetched_at: 100,
+            failed,
+        };
+
+        insert_failure(&db, "dead.example", record(true)).unwrap();
+        let negative = find(&db, "dead.example").await.unwrap().unwrap();
+        assert_eq!(negative.favicon, None);
+        assert_eq!(negative.record, Some(record(true)));
+
+        insert(&db, "docs.rs", &favicon, record(false)).unwrap();
+        insert_failure(&db, "docs.rs", record(true)).unwrap();
+        let failed_refresh = find(&db, "docs.rs").await.unwrap().unwrap();
+        assert_eq!(failed_refresh.favicon, Some(favicon));
+        assert_eq!(failed_refresh.record, Some(record(true)));
+
+        assert_eq!(keys(&db).await.unwrap(), ["dead.example", "docs.rs"]);
+        let summaries = summaries(&db).await.unwrap();
+        assert_eq!(summaries[0].mime_type, None);
+        assert_eq!(summaries[1].bytes, ico_data().len());
+        assert_eq!(summaries[1].record, Some(record(true)));
+
+        let keys_to_remove = ["dead.example".to_string(), "unknown.example".to_string()];
+        assert_eq!(remove_keys(&db, &keys_to_remove).unwrap(), 1);
+        assert_eq!(find(&db, "dead.example").await.unwrap(), None);
+        assert_eq!(find(&db, "unknown.example").await.unwrap(), None);
+
+        clear(&db).unwrap();
+        assert!(keys(&db).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_custom_favicons() {
+        let db = Database::builder()
+            .create_with_backend(InMemoryBackend::new())
+            .unwrap();
+        init(&db).unwrap();
+        let cached = Favicon::sniff(ico_data()).unwrap();
+        let custom = Favicon::sniff(b"GIF89a\x01\0".to_vec()).unwrap();
+
+        insert(&db, "docs.rs@16", &cached, FetchRecord::succeeded_now()).unwrap();
+        let favicons = [
+            ("docs.rs@16".to_string(), custom.clone()),
+            ("docs.rs@32".to_string(), custom.clone()),
+        ];
+        insert_custom(&db, &favicons).unwrap();
+        assert_eq!(find_custom(&db, "docs.rs@16").await.unwrap(), Some(custom));
+
+        // the cache is managed separately from the custom favicons
+        clear(&db).unwrap();
+        assert!(find_custom(&db, "docs.rs@32").await.unwrap().is_some());
+        assert!(keys(&db).await.unwrap().is_empty());
+
+        let keys_to_remove = ["docs.rs@16".to_string(), "docs.rs@32".to_string()];
+        assert_eq!(remove_custom(&db, &keys_to_remove).unwrap(), 2);
+        assert_eq!(find_custom(&db, "docs.rs@16").await.unwrap(), None);
+        assert_eq!(remove_custom(&db, &keys_to_remove).unwrap(), 0);
+    }
+}
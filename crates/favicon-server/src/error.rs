@@ -1,9 +1,5 @@
-use std::fmt::Display;
-
-use axum::{http::StatusCode, response::IntoResponse, Json};
-use serde::Serialize;
-
-use crate::response::create_default_image_response;
+#[cfg(feature = "http")]
+pub use crate::response::ErrorResponse;
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -19,103 +15,44 @@ pub enum Error {
     #[error("URL parse error: {0}")]
     UrlParse(#[from] url::ParseError),
 
+    /// Boxed because redb errors are large, which would make every `Result<_, Error>` large
     #[error("Redb error: {0}")]
-    Redb(#[from] redb::Error),
+    Redb(Box<redb::Error>),
 
-    #[error("Redb transaction error: {0}")]
-    TransactionError(#[from] redb::TransactionError),
+    #[error("PNG decoding error: {0}")]
+    PngDecoding(#[from] png::DecodingError),
 
-    #[error("Redb table error: {0}")]
-    TableError(#[from] redb::TableError),
+    #[error("PNG encoding error: {0}")]
+    PngEncoding(#[from] png::EncodingError),
 
-    #[error("Redb storage error: {0}")]
-    StorageError(#[from] redb::StorageError),
+    #[error("Favicon not found: {0}")]
+    FaviconNotFound(String),
 
-    #[error("Redb commit error: {0}")]
-    CommitError(#[from] redb::CommitError),
+    #[error("Invalid image: {0}")]
+    InvalidImage(String),
 
     #[error("Other: {0}")]
     Other(String),
 }
 
-#[derive(Serialize, Clone, Debug)]
-struct ErrorDetail {
-    #[serde(with = "http_serde::status_code")]
-    pub code: StatusCode,
-    pub message: String,
-}
-
-#[derive(Serialize, Clone, Debug)]
-pub struct ErrorResponse {
-    error: ErrorDetail,
-}
-
-impl ErrorResponse {
-    pub fn new(code: StatusCode, message: impl Into<String>) -> Self {
-        Self {
-            error: ErrorDetail {
-                code,
-                message: message.into(),
-            },
-        }
-    }
-
-    pub fn to_json(&self) -> Json<Self> {
-        Json(self.clone())
-    }
-}
-
-impl Default for ErrorResponse {
-    fn default() -> Self {
-        Self {
-            error: ErrorDetail {
-                code: StatusCode::INTERNAL_SERVER_ERROR,
-                message: "Unknown error".to_string(),
-            },
-        }
-    }
-}
-
-impl Display for ErrorResponse {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}: {}", self.error.code, self.error.message)
-    }
-}
-
-impl IntoResponse for ErrorResponse {
-    fn into_response(self) -> axum::response::Response {
-        log::warn!("Error: {}", self);
-        // (self.error.code, self.to_json()).into_response()
-        // this service needs to return image even if there is an error, so we return default image
-        create_default_image_response()
-    }
-}
-
-impl From<Error> for ErrorResponse {
-    fn from(err: Error) -> Self {
-        match err {
-            Error::Io(err) => Self::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
-            Error::AddrParse(err) => Self::new(StatusCode::BAD_REQUEST, err.to_string()),
-            Error::Reqwest(err) => Self::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
-            Error::UrlParse(err) => Self::new(StatusCode::BAD_REQUEST, err.to_string()),
-            Error::Redb(err) => Self::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
-            Error::TransactionError(err) => {
-                Self::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+/// All redb errors are converted into [`redb::Error`] and boxed
+macro_rules! impl_from_redb_error {
+    ($($error:ty),*) => {
+        $(
+            impl From<$error> for Error {
+                fn from(err: $error) -> Self {
+                    Self::Redb(Box::new(err.into()))
+                }
             }
-            Error::TableError(err) => Self::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
-            Error::StorageError(err) => {
-                Self::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
-            }
-            Error::CommitError(err) => {
-                Self::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
-            }
-            Error::Other(err) => Self::new(StatusCode::INTERNAL_SERVER_ERROR, err),
-        }
-    }
-}
-
-impl From<url::ParseError> for ErrorResponse {
-    fn from(err: url::ParseError) -> Self {
-        Self::new(StatusCode::BAD_REQUEST, err.to_string())
-    }
-}
+        )*
+    };
+}
+
+impl_from_redb_error!(
+    redb::Error,
+    redb::DatabaseError,
+    redb::TransactionError,
+    redb::TableError,
+    redb::StorageError,
+    redb::CommitError
+);
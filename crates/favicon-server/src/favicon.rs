@@ -0,0 +1,312 @@
+use std::io::Cursor;
+
+use crate::error::Error;
+
+pub(crate) const PNG: &str = "image/png";
+pub(crate) const ICO: &str = "image/x-icon";
+pub(crate) const SVG: &str = "image/svg+xml";
+
+/// Sizes served in pixels, 1x to 8x of the favicons in the sidebar
+pub(crate) const SIZES: [u32; 4] = [16, 32, 64, 128];
+
+/// Supported size for the requested one, rounded up and capped at the largest
+pub(crate) fn normalize_size(size: Option<u32>) -> u32 {
+    let size = size.unwrap_or(SIZES[0]);
+    SIZES
+        .iter()
+        .copied()
+        .find(|supported| *supported >= size)
+        .unwrap_or(SIZES[SIZES.len() - 1])
+}
+
+/// Image bytes with the MIME type detected from them
+#[derive(Debug, Clone, PartialEq)]
+pub struct Favicon {
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+impl Favicon {
+    /// Globe icon shown for sites without a favicon
+    pub fn fallback() -> Self {
+        // https://icons8.com/icon/3685/globe
+        // #339AF0
+        // https://icons8.com/icon/NyuxPErq0tu2/globe-africa
+        // #5C7CFA
+        Self {
+            mime_type: PNG.to_string(),
+            data: include_bytes!("assets/default.png").to_vec(),
+        }
+    }
+
+    /// None if the data is not an image, e.g. an error page
+    pub(crate) fn sniff(data: Vec<u8>) -> Option<Self> {
+        let mime_type = sniff_mime_type(&data)?;
+        Some(Self {
+            mime_type: mime_type.to_string(),
+            data,
+        })
+    }
+
+    /// Convert ICO into PNG of the entry closest to the size, other formats are returned as is.
+    /// The ICO is kept if it can't be decoded.
+    pub(crate) fn ico_to_png(self, size: u32) -> Self {
+        if self.mime_type != ICO {
+            return self;
+        }
+        match ico_to_png(&self.data, size) {
+            Ok(data) => Self {
+                mime_type: PNG.to_string(),
+                data,
+            },
+            Err(e) => {
+                log::debug!("Failed to convert ICO into PNG: {e}");
+                self
+            }
+        }
+    }
+
+    /// Downscale PNG larger than the size keeping the aspect ratio.
+    /// Smaller images and other formats are returned as is, SVG scales by itself.
+    pub(crate) fn downscale(self, size: u32) -> Self {
+        if self.mime_type != PNG {
+            return self;
+        }
+        match downscale_png(&self.data, size) {
+            Ok(Some(data)) => Self {
+                mime_type: PNG.to_string(),
+                data,
+            },
+            Ok(None) => self,
+            Err(e) => {
+                log::debug!("Failed to downscale PNG: {e}");
+                self
+            }
+        }
+    }
+}
+
+/// MIME type of an image detected by its magic bytes
+pub(crate) fn sniff_mime_type(data: &[u8]) -> Option<&'static str> {
+    match data {
+        [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n', ..] => Some(PNG),
+        // icon and cursor
+        [0, 0, 1 | 2, 0, ..] => Some(ICO),
+        [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => Some("image/gif"),
+        [0xff, 0xd8, 0xff, ..] => Some("image/jpeg"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("image/webp"),
+        [b'B', b'M', ..] => Some("image/bmp"),
+        [_, _, _, _, b'f', b't', b'y', b'p', b'a', b'v', b'i', b'f', ..] => Some("image/avif"),
+        _ if is_svg(data) => Some(SVG),
+        _ => None,
+    }
+}
+
+/// SVG is text, so the head of the document is checked for the `<svg` element
+fn is_svg(data: &[u8]) -> bool {
+    let head = &data[..data.len().min(1024)];
+    let head = String::from_utf8_lossy(head).to_ascii_lowercase();
+    let head = head.trim_start_matches('\u{feff}').trim_start();
+    ["<svg", "<?xml", "<!--", "<!doctype svg"]
+        .iter()
+        .any(|prefix| head.starts_with(prefix))
+        && head.contains("<svg")
+}
+
+/// Decode the ICO entry closest to the size (the smallest one not smaller than it) into PNG
+fn ico_to_png(data: &[u8], size: u32) -> Result<Vec<u8>, Error> {
+    let icon_dir = ico::IconDir::read(Cursor::new(data))?;
+    let entry = icon_dir
+        .entries()
+        .iter()
+        .min_by_key(|entry| {
+            let width = entry.width();
+            if width >= size {
+                (false, width - size)
+            } else {
+                (true, size - width)
+            }
+        })
+        .ok_or_else(|| Error::Other("ICO has no images".to_string()))?;
+    let mut png = Vec::new();
+    entry.decode()?.write_png(&mut png)?;
+    Ok(png)
+}
+
+/// Downscaled PNG, None if the image is not larger than the size
+fn downscale_png(data: &[u8], size: u32) -> Result<Option<Vec<u8>>, Error> {
+    let (rgba, width, height) = decode_png(data)?;
+    if width <= size && height <= size {
+        return Ok(None);
+    }
+    let (scaled_width, scaled_height) = if width >= height {
+        (size, (height * size / width).max(1))
+    } else {
+        ((width * size / height).max(1), size)
+    };
+    let scaled = resize_rgba(&rgba, width, height, scaled_width, scaled_height);
+    Ok(Some(encode_png(&scaled, scaled_width, scaled_height)?))
+}
+
+/// RGBA pixels, the width and the height of PNG
+fn decode_png(data: &[u8]) -> Result<(Vec<u8>, u32, u32), Error> {
+    let mut decoder = png::Decoder::new(Cursor::new(data));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+    let pixels = &buffer[..info.buffer_size()];
+    let rgba = match info.color_type {
+        png::ColorType::Rgba => pixels.to_vec(),
+        png::ColorType::Rgb => pixels
+            .chunks_exact(3)
+            .flat_map(|p| [p[0], p[1], p[2], 0xff])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => pixels
+            .chunks_exact(2)
+            .flat_map(|p| [p[0], p[0], p[0], p[1]])
+            .collect(),
+        png::ColorType::Grayscale => pixels.iter().flat_map(|p| [*p, *p, *p, 0xff]).collect(),
+        png::ColorType::Indexed => {
+            return Err(Error::Other("Indexed PNG is not expanded".to_string()));
+        }
+    };
+    Ok((rgba, info.width, info.height))
+}
+
+fn encode_png(rgba: &[u8], width: u32, height: u32) -> Result<Vec<u8>, Error> {
+    let mut data = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut data, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(rgba)?;
+    }
+    Ok(data)
+}
+
+/// Resize by averaging the source pixels covered by each pixel.
+/// Colors are weighted by alpha so that transparent pixels don't darken the edges.
+fn resize_rgba(rgba: &[u8], width: u32, height: u32, to_width: u32, to_height: u32) -> Vec<u8> {
+    let mut scaled = Vec::with_capacity((to_width * to_height * 4) as usize);
+    for y in 0..to_height {
+        let y0 = y * height / to_height;
+        let y1 = ((y + 1) * height / to_height).max(y0 + 1);
+        for x in 0..to_width {
+            let x0 = x * width / to_width;
+            let x1 = ((x + 1) * width / to_width).max(x0 + 1);
+            let mut sum = [0u64; 4];
+            let mut count = 0u64;
+            for sy in y0..y1 {
+                for sx in x0..x1 {
+                    let i = ((sy * width + sx) * 4) as usize;
+                    let alpha = rgba[i + 3] as u64;
+                    for (channel, value) in sum.iter_mut().zip(&rgba[i..i + 3]) {
+                        *channel += *value as u64 * alpha;
+                    }
+                    sum[3] += alpha;
+                    count += 1;
+                }
+            }
+            // fully transparent pixels have no color
+            let color = |channel: u64| channel.checked_div(sum[3]).unwrap_or_default() as u8;
+            scaled.extend([
+                color(sum[0]),
+                color(sum[1]),
+                color(sum[2]),
+                (sum[3] / count) as u8,
+            ]);
+        }
+    }
+    scaled
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// ICO of a 1x1 and a 32x32 image
+    pub(crate) fn ico_data() -> Vec<u8> {
+        let mut icon_dir = ico::IconDir::new(ico::ResourceType::Icon);
+        for size in [1, 32] {
+            let rgba = vec![0xff; (size * size * 4) as usize];
+            let image = ico::IconImage::from_rgba_data(size, size, rgba);
+            icon_dir.add_entry(ico::IconDirEntry::encode(&image).unwrap());
+        }
+        let mut data = Vec::new();
+        icon_dir.write(&mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn test_sniff_mime_type() {
+        assert_eq!(sniff_mime_type(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"), Some(PNG));
+        assert_eq!(sniff_mime_type(&ico_data()), Some(ICO));
+        assert_eq!(sniff_mime_type(b"GIF89a\x01\0"), Some("image/gif"));
+        assert_eq!(sniff_mime_type(b"\xff\xd8\xff\xe0"), Some("image/jpeg"));
+        assert_eq!(
+            sniff_mime_type(b"RIFF\x24\0\0\0WEBPVP8 "),
+            Some("image/webp")
+        );
+        assert_eq!(
+            sniff_mime_type(b"\xef\xbb\xbf <?xml version=\"1.0\"?>\n<svg xmlns=\"http://www.w3.org/2000/svg\"/>"),
+            Some(SVG)
+        );
+        assert_eq!(sniff_mime_type(b"<!DOCTYPE html><html></html>"), None);
+        assert_eq!(sniff_mime_type(b""), None);
+    }
+
+    #[test]
+    fn test_ico_to_png() {
+        let favicon = Favicon::sniff(ico_data()).unwrap().ico_to_png(16);
+        assert_eq!(favicon.mime_type, PNG);
+        assert_eq!(sniff_mime_type(&favicon.data), Some(PNG));
+        let png = ico::IconImage::read_png(Cursor::new(&favicon.data)).unwrap();
+        assert_eq!(png.width(), 32);
+
+        // broken ICO is kept
+        let broken = Favicon::sniff(vec![0, 0, 1, 0, 9]).unwrap().ico_to_png(16);
+        assert_eq!(broken.mime_type, ICO);
+    }
+
+    #[test]
+    fn test_normalize_size() {
+        assert_eq!(normalize_size(None), 16);
+        assert_eq!(normalize_size(Some(1)), 16);
+        assert_eq!(normalize_size(Some(32)), 32);
+        assert_eq!(normalize_size(Some(48)), 64);
+        assert_eq!(normalize_size(Some(512)), 128);
+    }
+
+    #[test]
+    fn test_downscale() {
+        // left half opaque red, right half transparent
+        let (width, height) = (64, 32);
+        let rgba = (0..width * height)
+            .flat_map(|i| {
+                if i % width < width / 2 {
+                    [0xff, 0, 0, 0xff]
+                } else {
+                    [0, 0, 0, 0]
+                }
+            })
+            .collect::<Vec<u8>>();
+        let favicon = Favicon {
+            mime_type: PNG.to_string(),
+            data: encode_png(&rgba, width, height).unwrap(),
+        };
+
+        let scaled = favicon.clone().downscale(16);
+        let (pixels, scaled_width, scaled_height) = decode_png(&scaled.data).unwrap();
+        assert_eq!((scaled_width, scaled_height), (16, 8));
+        assert_eq!(&pixels[..4], [0xff, 0, 0, 0xff]);
+        assert_eq!(&pixels[pixels.len() - 4..], [0, 0, 0, 0]);
+
+        // not upscaled
+        assert_eq!(favicon.clone().downscale(128), favicon);
+        // other formats are kept
+        let ico = Favicon::sniff(ico_data()).unwrap();
+        assert_eq!(ico.clone().downscale(16), ico);
+    }
+}
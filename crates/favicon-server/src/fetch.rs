@@ -0,0 +1,112 @@
+use std::sync::Arc;
+
+use url::Url;
+
+use crate::{
+    cache::{self, FetchRecord, Freshness},
+    db,
+    error::Error,
+    favicon::{normalize_size, Favicon},
+    provider::fetch_favicon,
+    AppState,
+};
+
+/// Favicon of the site in the size, the custom one if set by the user.
+/// A favicon not cached or expired is fetched, a stale one is returned while it's refreshed
+/// in the background (stale-while-revalidate).
+pub(crate) async fn find_or_fetch(
+    state: &Arc<AppState>,
+    url: &str,
+    size: Option<u32>,
+) -> Result<Favicon, Error> {
+    let url = Url::parse(url)?;
+    let host = url.host_str().ok_or(url::ParseError::EmptyHost)?;
+    let size = normalize_size(size);
+    let key = cache::cache_key(host, size);
+
+    // not to hold the database while fetching
+    let entry = {
+        let db = state.db.read().await;
+        if let Some(favicon) = db::find_custom(&db, &key).await? {
+            return Ok(favicon);
+        }
+        db::find(&db, &key).await?
+    };
+
+    if let Some(entry) = entry {
+        match (
+            entry.freshness(&state.config.cache, cache::now()),
+            entry.favicon,
+        ) {
+            (Freshness::Fresh, Some(favicon)) => return Ok(favicon),
+            (Freshness::Fresh, None) => return Err(Error::FaviconNotFound(key)),
+            (Freshness::Stale, Some(favicon)) => {
+                spawn_refresh(state.clone(), key, url, size);
+                return Ok(favicon);
+            }
+            _ => {}
+        }
+    }
+
+    refresh(state, &key, &url, size).await
+}
+
+/// Fetch the favicon and cache it, a failure is cached as well not to fetch the host again soon.
+/// Concurrent refreshes of the same key share one fetch.
+pub(crate) async fn refresh(
+    state: &AppState,
+    key: &str,
+    url: &Url,
+    size: u32,
+) -> Result<Favicon, Error> {
+    state
+        .fetches
+        .run(key, || fetch_and_store(state, key, url, size))
+        .await
+        .ok_or_else(|| Error::FaviconNotFound(key.to_string()))
+}
+
+async fn fetch_and_store(state: &AppState, key: &str, url: &Url, size: u32) -> Option<Favicon> {
+    let fetched = {
+        let _permit = state.upstream.acquire().await.ok()?;
+        fetch_favicon(&state.client, &state.config, url, size).await
+    };
+    let stored = {
+        let key = key.to_string();
+        match &fetched {
+            Ok(favicon) => {
+                let favicon = favicon.clone();
+                let record = FetchRecord::succeeded_now();
+                state
+                    .write_db(move |db| db::insert(db, &key, &favicon, record))
+                    .await
+            }
+            Err(_) => {
+                let record = FetchRecord::failed_now();
+                state
+                    .write_db(move |db| db::insert_failure(db, &key, record))
+                    .await
+            }
+        }
+    };
+    if let Err(e) = stored {
+        log::warn!("Failed to cache the favicon of {key}: {e}");
+    }
+    match fetched {
+        Ok(favicon) => Some(favicon),
+        Err(e) => {
+            log::debug!("Failed to fetch the favicon of {key}: {e}");
+            None
+        }
+    }
+}
+
+/// Refresh in the background unless the key is already being fetched
+fn spawn_refresh(state: Arc<AppState>, key: String, url: Url, size: u32) {
+    if state.fetches.contains(&key) {
+        return;
+    }
+    tokio::spawn(async move {
+        let _ = refresh(&state, &key, &url, size).await;
+    });
+}
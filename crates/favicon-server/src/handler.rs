@@ -1,114 +1,230 @@
 use axum::{
     extract::{Query, State},
-    http,
     response::{IntoResponse, Response},
 };
-use reqwest::{Client, StatusCode};
+use reqwest::StatusCode;
 use serde::Deserialize;
 use std::sync::Arc;
-use url::Url;
 
 use crate::{
     db,
-    error::{Error, ErrorResponse},
-    response::create_image_response,
+    fetch::find_or_fetch,
+    response::{create_image_response, ErrorResponse},
     AppState,
 };
 
 #[derive(Deserialize)]
 pub struct UrlQuery {
     url: String,
-}
-
-const GSTATIC_URL: &str =
-    "https://t0.gstatic.com/faviconV2?client=SOCIAL&type=FAVICON&fallback_opts=TYPE,SIZE,URL";
-
-async fn fetch_favicon(client: &Client, host: &str, size: u8) -> Result<Vec<u8>, Error> {
-    let favicon_url = format!("{GSTATIC_URL}&size={size}&url=https://{host}");
-    log::debug!("Fetching favicon from: {}", favicon_url);
-
-    let res = client.get(&favicon_url).send().await?;
-    if res.status().is_success() {
-        let bytes = res.bytes().await?;
-        Ok(bytes.to_vec())
-    } else {
-        Err(Error::Other("Failed to fetch favicon from API".to_string()))
-    }
+    /// Size in pixels, rounded up to 16, 32, 64 or 128. 16 if omitted.
+    /// Use `srcset` with `2x` of the size for HiDPI screens.
+    size: Option<u32>,
 }
 
 pub async fn get_favicon(
     State(state): State<Arc<AppState>>,
     Query(query): Query<UrlQuery>,
 ) -> Response {
-    let url = match Url::parse(&query.url) {
-        Ok(url) => url,
-        Err(e) => {
-            return ErrorResponse::from(e).into_response();
-        }
-    };
+    match find_or_fetch(&state, &query.url, query.size).await {
+        Ok(favicon) => create_image_response(favicon),
+        Err(e) => ErrorResponse::from(e).into_response(),
+    }
+}
 
-    // key is the host part of the URL
-    let host_str = match url.host_str() {
-        Some(host) => host,
-        None => {
-            return ErrorResponse::new(http::StatusCode::BAD_REQUEST, "Invalid URL: missing host")
-                .into_response();
-        }
+pub async fn remove_all_favicons(State(state): State<Arc<AppState>>) -> Response {
+    match state.write_db(db::clear).await {
+        Ok(_) => (StatusCode::OK, "All favicons removed from database").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+pub async fn health_check() -> Response {
+    (StatusCode::OK, "OK").into_response()
+}
+
+pub async fn handler_404() -> Response {
+    (StatusCode::NOT_FOUND, "Not Found").into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::SocketAddr,
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
     };
 
-    let db = state.db.lock().await;
+    use axum::{body::to_bytes, routing::get, Router};
+    use redb::{backends::InMemoryBackend, Database};
 
-    let value = match db::find(&db, host_str).await {
-        Ok(v) => v,
-        Err(e) => {
-            return ErrorResponse::from(e).into_response();
-        }
+    use super::*;
+    use crate::{
+        cache::{self, FetchRecord},
+        favicon::{Favicon, PNG},
+        FaviconConfig,
     };
 
-    if let Some(inner) = value {
-        // log::debug!("Favicon found in database for: {}", host_str);
-        create_image_response(inner.value().to_vec())
-    } else {
-        let size = 16;
-        let favicon_data = match fetch_favicon(&state.client, host_str, size).await {
-            Ok(data) => data,
-            Err(e) => {
-                return ErrorResponse::from(e).into_response();
-            }
+    fn png(marker: &str) -> Vec<u8> {
+        [b"\x89PNG\r\n\x1a\n".as_slice(), marker.as_bytes()].concat()
+    }
+
+    /// Site serving `/favicon.ico` if `found`, returns the address and the request counter
+    async fn serve_site(found: bool) -> (SocketAddr, Arc<AtomicUsize>) {
+        serve_slow_site(found, Duration::ZERO).await
+    }
+
+    /// Site which takes the delay to respond
+    async fn serve_slow_site(found: bool, delay: Duration) -> (SocketAddr, Arc<AtomicUsize>) {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        let router = Router::new().route(
+            "/favicon.ico",
+            get(move || async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(delay).await;
+                if found {
+                    (StatusCode::OK, png("fresh"))
+                } else {
+                    (StatusCode::NOT_FOUND, Vec::new())
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        (addr, hits)
+    }
+
+    fn app_state() -> Arc<AppState> {
+        let db = Database::builder()
+            .create_with_backend(InMemoryBackend::new())
+            .unwrap();
+        db::init(&db).unwrap();
+        Arc::new(AppState::new(db, FaviconConfig::default()))
+    }
+
+    async fn request(state: &Arc<AppState>, addr: SocketAddr) -> Vec<u8> {
+        request_url(state, &format!("http://{addr}/page")).await
+    }
+
+    async fn request_url(state: &Arc<AppState>, url: &str) -> Vec<u8> {
+        let query = UrlQuery {
+            url: url.to_string(),
+            size: None,
         };
+        let response = get_favicon(State(state.clone()), Query(query)).await;
+        to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap()
+            .to_vec()
+    }
 
-        match db::insert(&db, host_str, favicon_data.as_slice()).await {
-            Ok(_) => create_image_response(favicon_data),
-            Err(e) => ErrorResponse::from(e).into_response(),
-        }
+    #[tokio::test]
+    async fn test_negative_cache() {
+        let (addr, hits) = serve_site(false).await;
+        let state = app_state();
+
+        request(&state, addr).await;
+        let after_first = hits.load(Ordering::SeqCst);
+        assert_eq!(after_first, 1);
+        // the failure is cached
+        request(&state, addr).await;
+        assert_eq!(hits.load(Ordering::SeqCst), after_first);
     }
-}
 
-pub async fn remove_all_favicons(State(state): State<Arc<AppState>>) -> Response {
-    let db = state.db.lock().await;
-    let keys = match db::keys(&db).await {
-        Ok(keys) => keys,
-        Err(e) => {
-            return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
-        }
-    };
+    #[tokio::test]
+    async fn test_custom_favicon_is_not_refreshed() {
+        let (addr, hits) = serve_site(true).await;
+        let state = app_state();
+        let custom = png("custom");
+        crate::custom::set_custom(&state, &addr.ip().to_string(), custom.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(request(&state, addr).await, custom);
+        assert_eq!(hits.load(Ordering::SeqCst), 0);
+
+        // reset to the automatic favicon
+        crate::custom::reset_custom(&state, &addr.ip().to_string())
+            .await
+            .unwrap();
+        assert_eq!(request(&state, addr).await, png("fresh"));
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
 
-    for key in keys {
-        match db::remove(&db, &key).await {
-            Ok(_) => (),
-            Err(e) => {
-                return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+    #[tokio::test]
+    async fn test_stale_while_revalidate() {
+        let (addr, hits) = serve_site(true).await;
+        let state = app_state();
+        let key = cache::cache_key(&addr.ip().to_string(), 16);
+        let stale = Favicon {
+            mime_type: PNG.to_string(),
+            data: png("stale"),
+        };
+        let fetched_at = cache::now() - state.config.cache.ttl.as_secs() - 1;
+        let record = FetchRecord {
+            fetched_at,
+            failed: false,
+        };
+        db::insert(&*state.db.read().await, &key, &stale, record).unwrap();
+
+        // the stale favicon is served without waiting for the site
+        assert_eq!(request(&state, addr).await, png("stale"));
+
+        let mut refreshed = None;
+        for _ in 0..100 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            let entry = db::find(&*state.db.read().await, &key)
+                .await
+                .unwrap()
+                .unwrap();
+            if entry.record.unwrap().fetched_at > fetched_at {
+                refreshed = entry.favicon;
+                break;
             }
         }
+        assert_eq!(refreshed.unwrap().data, png("fresh"));
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+        assert_eq!(request(&state, addr).await, png("fresh"));
     }
 
-    (StatusCode::OK, "All favicons removed from database").into_response()
-}
-
-pub async fn health_check() -> Response {
-    (StatusCode::OK, "OK").into_response()
-}
-
-pub async fn handler_404() -> Response {
-    (StatusCode::NOT_FOUND, "Not Found").into_response()
+    #[tokio::test]
+    async fn test_parallel_requests_are_not_serialized() {
+        let (addr, hits) = serve_slow_site(true, Duration::from_secs(1)).await;
+        let state = app_state();
+        let cached = Favicon {
+            mime_type: PNG.to_string(),
+            data: png("cached"),
+        };
+        db::insert(
+            &*state.db.read().await,
+            "docs.rs@16",
+            &cached,
+            FetchRecord::succeeded_now(),
+        )
+        .unwrap();
+
+        // requests of the slow host are coalesced into one fetch
+        let slow = (0..5)
+            .map(|_| {
+                let state = state.clone();
+                tokio::spawn(async move { request(&state, addr).await })
+            })
+            .collect::<Vec<_>>();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // a cached favicon doesn't wait for the slow fetch
+        let fast = tokio::time::timeout(
+            Duration::from_millis(300),
+            request_url(&state, "https://docs.rs/tauri"),
+        )
+        .await
+        .expect("cached favicon is blocked by the slow fetch");
+        assert_eq!(fast, png("cached"));
+
+        for handle in slow {
+            assert_eq!(handle.await.unwrap(), png("fresh"));
+        }
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
 }
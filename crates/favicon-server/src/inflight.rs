@@ -0,0 +1,94 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::OnceCell;
+
+/// Coalesces concurrent tasks of the same key, the callers which come while a task
+/// is running wait for it and get its result instead of running their own
+pub(crate) struct Inflight<T> {
+    tasks: Mutex<HashMap<String, Arc<OnceCell<T>>>>,
+}
+
+impl<T: Clone> Inflight<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            tasks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub(crate) fn contains(&self, key: &str) -> bool {
+        self.tasks
+            .lock()
+            .map(|tasks| tasks.contains_key(key))
+            .unwrap_or_default()
+    }
+
+    /// Run the task unless a task of the key is running, the result is shared with its waiters.
+    /// If the running caller is cancelled, one of the waiters runs its task instead.
+    pub(crate) async fn run<F, Fut>(&self, key: &str, task: F) -> T
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = T>,
+    {
+        let cell = match self.tasks.lock() {
+            Ok(mut tasks) => tasks.entry(key.to_string()).or_default().clone(),
+            // not coalesced, still run the task
+            Err(_) => Arc::new(OnceCell::new()),
+        };
+        let value = cell.get_or_init(task).await.clone();
+
+        if let Ok(mut tasks) = self.tasks.lock() {
+            // a later task of the key may have started after this one finished
+            if tasks
+                .get(key)
+                .is_some_and(|current| Arc::ptr_eq(current, &cell))
+            {
+                tasks.remove(key);
+            }
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_coalesce() {
+        let inflight = Arc::new(Inflight::new());
+        let runs = Arc::new(AtomicUsize::new(0));
+
+        let handles = (0..5)
+            .map(|_| {
+                let inflight = inflight.clone();
+                let runs = runs.clone();
+                tokio::spawn(async move {
+                    inflight
+                        .run("docs.rs", || async {
+                            tokio::time::sleep(Duration::from_millis(100)).await;
+                            runs.fetch_add(1, Ordering::SeqCst)
+                        })
+                        .await
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            assert_eq!(handle.await.unwrap(), 0);
+        }
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert!(!inflight.contains("docs.rs"));
+
+        // finished tasks are not cached
+        let value = inflight.run("docs.rs", || async { 1 }).await;
+        assert_eq!(value, 1);
+    }
+}
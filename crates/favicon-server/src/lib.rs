@@ -1,85 +1,159 @@
+#[cfg(feature = "http")]
+mod auth;
+mod cache;
+mod custom;
 mod db;
 pub mod error;
+mod favicon;
+mod fetch;
+#[cfg(feature = "http")]
 mod handler;
+mod inflight;
+#[cfg(feature = "http")]
+mod manage;
+mod prefetch;
+mod provider;
+#[cfg(feature = "http")]
 mod response;
+#[cfg(feature = "http")]
+mod server;
 
-use axum::{
-    http::{header, HeaderValue, Method},
-    routing::delete,
-    routing::get,
-    Router,
-};
 use redb::{Database, TableDefinition};
 use reqwest::Client;
+use serde::Serialize;
 use std::sync::Arc;
 use std::{net::SocketAddr, time::Duration};
-use tokio::sync::Mutex;
-use tower_http::cors::CorsLayer;
+use tokio::sync::{RwLock, Semaphore};
 
-use handler::{get_favicon, handler_404, health_check, remove_all_favicons};
+use error::Error;
+use inflight::Inflight;
+
+#[cfg(feature = "http")]
+pub use auth::{TOKEN_HEADER, TOKEN_QUERY};
+pub use cache::CachePolicy;
+pub use custom::read_image_source;
+pub use favicon::Favicon;
+pub use prefetch::PrefetchProgress;
+pub use provider::{FaviconConfig, DUCKDUCKGO_PROVIDER, GSTATIC_PROVIDER};
+#[cfg(feature = "http")]
+pub use server::{run, serve};
 
 const USER_AGENT: &str =
     "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:123.0) Gecko/20100101 Firefox/123.0";
 
-pub const FAVICON_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("favicons");
+/// Host key to the MIME type and the bytes of the favicon
+pub const FAVICON_TABLE: TableDefinition<&str, (&str, &[u8])> = TableDefinition::new("favicons_v2");
+
+/// Host key to the last fetch, seconds since the UNIX epoch and whether it failed
+pub const FETCH_TABLE: TableDefinition<&str, (u64, bool)> = TableDefinition::new("favicon_fetches");
+
+/// Key to the MIME type and the bytes of the favicon set by the user, which is never refreshed
+pub const CUSTOM_FAVICON_TABLE: TableDefinition<&str, (&str, &[u8])> =
+    TableDefinition::new("custom_favicons");
 
+/// Host key to the bytes of the favicon, migrated into [`FAVICON_TABLE`] on start
+pub const LEGACY_FAVICON_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("favicons");
+
+/// Shared by the handlers. redb allows concurrent reads and serializes writes by itself,
+/// so the database is not locked while favicons are fetched.
 pub struct AppState {
-    db: Arc<Mutex<Database>>,
+    /// Locked for writing only to compact the file
+    db: Arc<RwLock<Database>>,
     client: Client,
+    config: FaviconConfig,
+    /// Fetches in flight by key, None for a failure
+    fetches: Inflight<Option<Favicon>>,
+    /// Limits the number of hosts fetched at once
+    upstream: Semaphore,
+    prefetch: std::sync::Mutex<PrefetchProgress>,
+}
+
+impl AppState {
+    fn new(db: Database, config: FaviconConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::new(30, 0))
+            .user_agent(USER_AGENT)
+            .build()
+            .unwrap();
+        let upstream = Semaphore::new(config.max_concurrent_fetches.max(1));
+        Self {
+            db: Arc::new(RwLock::new(db)),
+            client,
+            config,
+            fetches: Inflight::new(),
+            upstream,
+            prefetch: std::sync::Mutex::new(PrefetchProgress::default()),
+        }
+    }
+
+    /// Run the write transaction on a blocking thread not to block the runtime while committing
+    pub(crate) async fn write_db<T, F>(&self, f: F) -> Result<T, Error>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Database) -> Result<T, Error> + Send + 'static,
+    {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || f(&db.blocking_read()))
+            .await
+            .map_err(|e| Error::Other(e.to_string()))?
+    }
+}
+
+/// Favicon cache shared by the HTTP server and the app, e.g. a custom URI scheme
+#[derive(Clone)]
+pub struct Favicons {
+    state: Arc<AppState>,
 }
 
-pub async fn run<P>(path: P, port: u16, allow_origins: Vec<&str>)
-where
-    P: AsRef<std::path::Path>,
-{
-    let db = Database::create(path).expect("Failed to create database");
-    let write_txn = db.begin_write().expect("Failed to begin write transaction");
+impl Favicons {
+    /// Open the database, creating and migrating the tables
+    pub fn open<P>(path: P, config: FaviconConfig) -> Result<Self, Error>
+    where
+        P: AsRef<std::path::Path>,
     {
-        let mut _table = write_txn
-            .open_table(FAVICON_TABLE)
-            .expect("Failed to open table");
+        let db = Database::create(path)?;
+        db::init(&db)?;
+        Ok(Self {
+            state: Arc::new(AppState::new(db, config)),
+        })
     }
-    write_txn
-        .commit()
-        .expect("Failed to commit write transaction");
-
-    let db = Arc::new(Mutex::new(db));
-
-    let client = reqwest::Client::builder()
-        .timeout(Duration::new(30, 0))
-        .user_agent(USER_AGENT)
-        .build()
-        .unwrap();
-
-    let app_state = Arc::new(AppState { db, client });
-
-    let origins = allow_origins
-        .iter()
-        .map(|addr| addr.parse::<HeaderValue>().unwrap())
-        .collect::<Vec<_>>();
-
-    let cors = CorsLayer::new()
-        .allow_methods([Method::GET, Method::DELETE])
-        .allow_headers([
-            header::AUTHORIZATION,
-            header::ACCEPT,
-            header::CONTENT_TYPE,
-            header::ACCESS_CONTROL_REQUEST_HEADERS,
-        ])
-        .allow_origin(origins);
-
-    let app = Router::new()
-        .route("/", get(health_check))
-        .route("/favicon", get(get_favicon))
-        .route("/favicon", delete(remove_all_favicons))
-        .layer(cors)
-        .with_state(app_state)
-        .fallback(handler_404);
-
-    let addr = SocketAddr::from(([127, 0, 0, 1], port));
-
-    log::info!("Favicon server starts on http://{}", addr);
-
-    let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+
+    /// Use the image as the favicon of the host instead of the fetched one
+    pub async fn set_custom(&self, host: &str, data: Vec<u8>) -> Result<(), Error> {
+        custom::set_custom(&self.state, host, data).await
+    }
+
+    /// Fetch the favicon of the host automatically again, false if it has no custom favicon
+    pub async fn reset_custom(&self, host: &str) -> Result<bool, Error> {
+        custom::reset_custom(&self.state, host).await
+    }
+
+    /// Favicon of the site in the size (rounded up to 16, 32, 64 or 128, 16 if None).
+    /// Must be called in a tokio runtime, a stale favicon is refreshed in the background.
+    pub async fn get(&self, url: &str, size: Option<u32>) -> Result<Favicon, Error> {
+        fetch::find_or_fetch(&self.state, url, size).await
+    }
+
+    /// Start fetching the favicons of the sites in the sizes (16 if empty) not cached yet in the background.
+    /// The progress of the running job is returned as the error while a job is running.
+    pub fn prefetch(
+        &self,
+        urls: &[String],
+        sizes: &[u32],
+    ) -> Result<PrefetchProgress, PrefetchProgress> {
+        prefetch::start(&self.state, urls, sizes)
+    }
+
+    /// Progress of the running prefetch job or the last one
+    pub fn prefetch_progress(&self) -> PrefetchProgress {
+        prefetch::progress(&self.state)
+    }
+}
+
+/// Address and token of a running server, which are different on each launch
+#[derive(Debug, Clone, Serialize)]
+pub struct FaviconServer {
+    pub addr: SocketAddr,
+    /// Sent as the `x-favicon-token` header or the `token` query parameter
+    pub token: String,
 }
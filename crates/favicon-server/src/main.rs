@@ -1,6 +1,6 @@
 #[tokio::main]
 async fn main() {
-    favicon_server::run(
+    let server = favicon_server::run(
         "outs/favicons.db",
         7853,
         vec![
@@ -8,6 +8,10 @@ async fn main() {
             "http://tauri.localhost/",
             "http://localhost:1420/",
         ],
+        favicon_server::FaviconConfig::default(),
     )
-    .await;
+    .await
+    .expect("Failed to start favicon server");
+    println!("http://{}/?token={}", server.addr, server.token);
+    std::future::pending::<()>().await;
 }
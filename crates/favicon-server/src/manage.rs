@@ -0,0 +1,400 @@
+use std::{collections::HashSet, sync::Arc};
+
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    response::{IntoResponse, Response},
+    Json,
+};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cache::{self, parse_cache_key},
+    custom,
+    db::{self, EntrySummary},
+    error::Error,
+    prefetch, AppState,
+};
+
+/// Cached favicon or failure of a host in a size
+#[derive(Serialize)]
+pub struct CachedFavicon {
+    /// Host key, e.g. `docs.rs` for `www.docs.rs`
+    host: String,
+    /// Host in Unicode
+    display_host: String,
+    /// Size in pixels
+    size: u32,
+    /// None if the host has never been fetched successfully
+    mime_type: Option<String>,
+    bytes: usize,
+    /// Seconds since the UNIX epoch, None for favicons cached before fetches were recorded
+    fetched_at: Option<u64>,
+    /// Seconds since fetched
+    age: Option<u64>,
+    /// Whether the last fetch failed
+    failed: bool,
+}
+
+impl CachedFavicon {
+    fn new(summary: EntrySummary, now: u64) -> Self {
+        let (host, size) = parse_cache_key(&summary.key);
+        Self {
+            host: host.to_string(),
+            display_host: parus_url::display_host(host),
+            size,
+            mime_type: summary.mime_type,
+            bytes: summary.bytes,
+            fetched_at: summary.record.map(|record| record.fetched_at),
+            age: summary
+                .record
+                .map(|record| now.saturating_sub(record.fetched_at)),
+            failed: summary.record.is_some_and(|record| record.failed),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct CacheStats {
+    /// Entries of all hosts and sizes
+    entries: usize,
+    hosts: usize,
+    /// Entries without a favicon, which are cached failures
+    failures: usize,
+    /// Total bytes of the favicons
+    bytes: usize,
+    /// Seconds since the UNIX epoch of the oldest fetch
+    oldest_fetched_at: Option<u64>,
+}
+
+#[derive(Deserialize)]
+pub struct PruneRequest {
+    /// Remove entries fetched more than the days ago, including the ones never recorded
+    older_than_days: Option<u64>,
+    /// Remove entries of hosts not in the list, e.g. the hosts of the bookmarks
+    keep_hosts: Option<Vec<String>>,
+}
+
+#[derive(Serialize)]
+pub struct PruneResponse {
+    removed: usize,
+}
+
+#[derive(Serialize)]
+pub struct CompactResponse {
+    /// false if there was nothing to compact
+    compacted: bool,
+}
+
+#[derive(Deserialize)]
+pub struct PrefetchRequest {
+    /// URLs of the sites, e.g. one bookmark of each host
+    urls: Vec<String>,
+    /// Size in pixels, 16 if omitted
+    size: Option<u32>,
+}
+
+fn internal_error(e: impl ToString) -> Response {
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+}
+
+/// `GET /favicon/hosts`
+pub async fn list_favicons(State(state): State<Arc<AppState>>) -> Response {
+    let db = state.db.read().await;
+    let summaries = match db::summaries(&db).await {
+        Ok(summaries) => summaries,
+        Err(e) => return internal_error(e),
+    };
+    let now = cache::now();
+    let favicons = summaries
+        .into_iter()
+        .map(|summary| CachedFavicon::new(summary, now))
+        .collect::<Vec<_>>();
+    Json(favicons).into_response()
+}
+
+/// `DELETE /favicon/hosts/{host}`, removes all sizes of the host
+pub async fn remove_host(State(state): State<Arc<AppState>>, Path(host): Path<String>) -> Response {
+    let host = parus_url::host_key(&host);
+    let keys = match db::keys(&*state.db.read().await).await {
+        Ok(keys) => keys,
+        Err(e) => return internal_error(e),
+    };
+    let keys = keys
+        .into_iter()
+        .filter(|key| parse_cache_key(key).0 == host)
+        .collect::<Vec<_>>();
+    match state.write_db(move |db| db::remove_keys(db, &keys)).await {
+        Ok(0) => (StatusCode::NOT_FOUND, "Host not found").into_response(),
+        Ok(removed) => Json(PruneResponse { removed }).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// `GET /favicon/stats`
+pub async fn cache_stats(State(state): State<Arc<AppState>>) -> Response {
+    let db = state.db.read().await;
+    let summaries = match db::summaries(&db).await {
+        Ok(summaries) => summaries,
+        Err(e) => return internal_error(e),
+    };
+    let hosts = summaries
+        .iter()
+        .map(|summary| parse_cache_key(&summary.key).0)
+        .collect::<HashSet<_>>()
+        .len();
+    let stats = CacheStats {
+        entries: summaries.len(),
+        hosts,
+        failures: summaries
+            .iter()
+            .filter(|summary| summary.mime_type.is_none())
+            .count(),
+        bytes: summaries.iter().map(|summary| summary.bytes).sum(),
+        oldest_fetched_at: summaries
+            .iter()
+            .filter_map(|summary| summary.record)
+            .map(|record| record.fetched_at)
+            .min(),
+    };
+    Json(stats).into_response()
+}
+
+/// `POST /favicon/prune`, an entry matching either condition is removed
+pub async fn prune_favicons(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<PruneRequest>,
+) -> Response {
+    let summaries = match db::summaries(&*state.db.read().await).await {
+        Ok(summaries) => summaries,
+        Err(e) => return internal_error(e),
+    };
+
+    let cutoff = request
+        .older_than_days
+        .map(|days| cache::now().saturating_sub(days * 24 * 60 * 60));
+    let keep_hosts = request.keep_hosts.map(|hosts| {
+        hosts
+            .iter()
+            .map(|host| parus_url::host_key(host))
+            .collect::<HashSet<_>>()
+    });
+    let keys = summaries
+        .into_iter()
+        .filter(|summary| {
+            let expired = cutoff.is_some_and(|cutoff| {
+                summary
+                    .record
+                    .is_none_or(|record| record.fetched_at < cutoff)
+            });
+            let unused = keep_hosts
+                .as_ref()
+                .is_some_and(|hosts| !hosts.contains(parse_cache_key(&summary.key).0));
+            expired || unused
+        })
+        .map(|summary| summary.key)
+        .collect::<Vec<_>>();
+
+    match state.write_db(move |db| db::remove_keys(db, &keys)).await {
+        Ok(removed) => Json(PruneResponse { removed }).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// `POST /favicon/compact`, waits for other requests to release the database
+pub async fn compact_database(State(state): State<Arc<AppState>>) -> Response {
+    let db = state.db.clone();
+    // compacting rewrites the file, so it runs on a blocking thread
+    let compacted = tokio::task::spawn_blocking(move || db.blocking_write().compact()).await;
+    match compacted {
+        Ok(Ok(compacted)) => Json(CompactResponse { compacted }).into_response(),
+        Ok(Err(e)) => internal_error(e),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// `PUT /favicon/custom/{host}`, the body is the image
+pub async fn put_custom_favicon(
+    State(state): State<Arc<AppState>>,
+    Path(host): Path<String>,
+    body: Bytes,
+) -> Response {
+    match custom::set_custom(&state, &host, body.to_vec()).await {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e @ Error::InvalidImage(_)) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// `DELETE /favicon/custom/{host}`, resets the favicon of the host to the fetched one
+pub async fn delete_custom_favicon(
+    State(state): State<Arc<AppState>>,
+    Path(host): Path<String>,
+) -> Response {
+    match custom::reset_custom(&state, &host).await {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => (StatusCode::NOT_FOUND, "Custom favicon not found").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// `POST /favicon/prefetch`, starts a job to fetch the favicons not cached yet.
+/// Only one job runs at a time, the request conflicts while a job is running.
+pub async fn start_prefetch(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<PrefetchRequest>,
+) -> Response {
+    let sizes = request.size.into_iter().collect::<Vec<_>>();
+    match prefetch::start(&state, &request.urls, &sizes) {
+        Ok(progress) => (StatusCode::ACCEPTED, Json(progress)).into_response(),
+        Err(running) => (StatusCode::CONFLICT, Json(running)).into_response(),
+    }
+}
+
+/// `GET /favicon/prefetch`
+pub async fn prefetch_progress(State(state): State<Arc<AppState>>) -> Response {
+    Json(prefetch::progress(&state)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::to_bytes;
+    use redb::{backends::InMemoryBackend, Database};
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::{
+        cache::{cache_key, FetchRecord},
+        favicon::{tests::ico_data, Favicon, PNG, SIZES},
+        FaviconConfig,
+    };
+
+    async fn json_body(response: Response) -> Value {
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    async fn app_state() -> Arc<AppState> {
+        let db = Database::builder()
+            .create_with_backend(InMemoryBackend::new())
+            .unwrap();
+        db::init(&db).unwrap();
+        let favicon = Favicon {
+            mime_type: PNG.to_string(),
+            data: vec![0; 10],
+        };
+        let now = cache::now();
+        let record = |days: u64, failed| FetchRecord {
+            fetched_at: now - days * 24 * 60 * 60,
+            failed,
+        };
+        db::insert(&db, "docs.rs@16", &favicon, record(1, false)).unwrap();
+        db::insert(&db, "docs.rs@32", &favicon, record(40, false)).unwrap();
+        db::insert(&db, "xn--bcher-kva.example@16", &favicon, record(1, false)).unwrap();
+        db::insert_failure(&db, "dead.example@16", record(0, true)).unwrap();
+        Arc::new(AppState::new(db, FaviconConfig::default()))
+    }
+
+    #[tokio::test]
+    async fn test_list_and_stats() {
+        let state = app_state().await;
+
+        let list = json_body(list_favicons(State(state.clone())).await).await;
+        let list = list.as_array().unwrap();
+        assert_eq!(list.len(), 4);
+        assert_eq!(list[0]["host"], "dead.example");
+        assert_eq!(list[0]["failed"], true);
+        assert_eq!(list[0]["mime_type"], Value::Null);
+        assert_eq!(list[2]["size"], 32);
+        let age = list[2]["age"].as_u64().unwrap();
+        assert!((40 * 24 * 60 * 60..40 * 24 * 60 * 60 + 60).contains(&age));
+        assert_eq!(list[3]["display_host"], "bücher.example");
+
+        let stats = json_body(cache_stats(State(state.clone())).await).await;
+        assert_eq!(stats["entries"], 4);
+        assert_eq!(stats["hosts"], 3);
+        assert_eq!(stats["failures"], 1);
+        assert_eq!(stats["bytes"], 30);
+    }
+
+    #[tokio::test]
+    async fn test_remove_and_prune() {
+        let state = app_state().await;
+
+        let response = remove_host(State(state.clone()), Path("www.docs.rs".to_string())).await;
+        assert_eq!(json_body(response).await, json!({ "removed": 2 }));
+        let response = remove_host(State(state.clone()), Path("docs.rs".to_string())).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let state = app_state().await;
+        let request = PruneRequest {
+            older_than_days: Some(30),
+            keep_hosts: None,
+        };
+        let response = prune_favicons(State(state.clone()), Json(request)).await;
+        assert_eq!(json_body(response).await, json!({ "removed": 1 }));
+
+        let request = PruneRequest {
+            older_than_days: None,
+            keep_hosts: Some(vec![
+                "www.docs.rs".to_string(),
+                "bücher.example".to_string(),
+            ]),
+        };
+        let response = prune_favicons(State(state.clone()), Json(request)).await;
+        assert_eq!(json_body(response).await, json!({ "removed": 1 }));
+        let keys = db::keys(&*state.db.read().await).await.unwrap();
+        assert_eq!(keys, ["docs.rs@16", "xn--bcher-kva.example@16"]);
+
+        let response = compact_database(State(state.clone())).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_set_and_reset_custom() {
+        let db = Database::builder()
+            .create_with_backend(InMemoryBackend::new())
+            .unwrap();
+        db::init(&db).unwrap();
+        let state = Arc::new(AppState::new(db, FaviconConfig::default()));
+
+        let response = put_custom_favicon(
+            State(state.clone()),
+            Path("www.docs.rs".to_string()),
+            Bytes::from(ico_data()),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        for size in SIZES {
+            let key = cache_key("docs.rs", size);
+            let favicon = db::find_custom(&*state.db.read().await, &key)
+                .await
+                .unwrap()
+                .unwrap();
+            // converted into PNG as the fetched favicons
+            assert_eq!(favicon.mime_type, PNG);
+        }
+
+        let response = put_custom_favicon(
+            State(state.clone()),
+            Path("docs.rs".to_string()),
+            Bytes::from_static(b"<html></html>"),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response =
+            delete_custom_favicon(State(state.clone()), Path("docs.rs".to_string())).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(
+            db::find_custom(&*state.db.read().await, "docs.rs@16")
+                .await
+                .unwrap(),
+            None
+        );
+        let response =
+            delete_custom_favicon(State(state.clone()), Path("docs.rs".to_string())).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
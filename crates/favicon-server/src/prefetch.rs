@@ -0,0 +1,237 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, PoisonError},
+};
+
+use serde::Serialize;
+use tokio::task::JoinSet;
+use url::Url;
+
+use crate::{
+    cache::{self, Freshness},
+    db,
+    favicon::normalize_size,
+    fetch::refresh,
+    AppState,
+};
+
+/// Progress of the prefetch job, the last job is kept after it finishes
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PrefetchProgress {
+    /// Favicons to prefetch, URLs of the same host are counted once for each size
+    total: usize,
+    /// Favicons processed so far
+    done: usize,
+    fetched: usize,
+    failed: usize,
+    /// Favicons already cached, failed recently or set by the user
+    skipped: usize,
+    running: bool,
+}
+
+enum Outcome {
+    Fetched,
+    Failed,
+    Skipped,
+}
+
+/// Start a job to fetch the favicons of the URLs in the sizes (16 if empty) not cached yet.
+/// Only one job runs at a time, the progress of the running job is returned as the error.
+pub(crate) fn start(
+    state: &Arc<AppState>,
+    urls: &[String],
+    sizes: &[u32],
+) -> Result<PrefetchProgress, PrefetchProgress> {
+    let sizes = match sizes {
+        [] => vec![normalize_size(None)],
+        sizes => sizes
+            .iter()
+            .map(|size| normalize_size(Some(*size)))
+            .collect(),
+    };
+    let mut keys = HashSet::new();
+    let mut targets = Vec::new();
+    for url in urls.iter().filter_map(|url| Url::parse(url).ok()) {
+        let Some(host) = url.host_str() else {
+            continue;
+        };
+        for size in &sizes {
+            let key = cache::cache_key(host, *size);
+            if keys.insert(key.clone()) {
+                targets.push((key, url.clone(), *size));
+            }
+        }
+    }
+
+    let progress = {
+        let mut progress = state
+            .prefetch
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if progress.running {
+            return Err(progress.clone());
+        }
+        *progress = PrefetchProgress {
+            total: targets.len(),
+            running: true,
+            ..Default::default()
+        };
+        progress.clone()
+    };
+
+    tokio::spawn(prefetch(state.clone(), targets));
+    Ok(progress)
+}
+
+/// Progress of the running job or the last one
+pub(crate) fn progress(state: &AppState) -> PrefetchProgress {
+    state
+        .prefetch
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+/// Fetch the favicons with at most `max_concurrent_fetches` hosts at once.
+/// Requests from the sidebar during the job share the fetches of the job.
+async fn prefetch(state: Arc<AppState>, targets: Vec<(String, Url, u32)>) {
+    let concurrency = state.config.max_concurrent_fetches.max(1);
+    let mut tasks = JoinSet::new();
+    for (key, url, size) in targets {
+        while tasks.len() >= concurrency {
+            if let Some(outcome) = tasks.join_next().await {
+                record(&state, outcome.unwrap_or(Outcome::Failed));
+            }
+        }
+        let state = state.clone();
+        tasks.spawn(async move { prefetch_one(&state, &key, &url, size).await });
+    }
+    while let Some(outcome) = tasks.join_next().await {
+        record(&state, outcome.unwrap_or(Outcome::Failed));
+    }
+
+    if let Ok(mut progress) = state.prefetch.lock() {
+        progress.running = false;
+        log::info!("Prefetched favicons: {:?}", progress);
+    }
+}
+
+async fn prefetch_one(state: &AppState, key: &str, url: &Url, size: u32) -> Outcome {
+    let found = {
+        let db = state.db.read().await;
+        if let Ok(Some(_)) = db::find_custom(&db, key).await {
+            return Outcome::Skipped;
+        }
+        db::find(&db, key).await
+    };
+    if let Ok(Some(entry)) = found {
+        if entry.freshness(&state.config.cache, cache::now()) != Freshness::Expired {
+            return Outcome::Skipped;
+        }
+    }
+    match refresh(state, key, url, size).await {
+        Ok(_) => Outcome::Fetched,
+        Err(_) => Outcome::Failed,
+    }
+}
+
+fn record(state: &AppState, outcome: Outcome) {
+    let Ok(mut progress) = state.prefetch.lock() else {
+        return;
+    };
+    progress.done += 1;
+    match outcome {
+        Outcome::Fetched => progress.fetched += 1,
+        Outcome::Failed => progress.failed += 1,
+        Outcome::Skipped => progress.skipped += 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use axum::{routing::get, Router};
+    use redb::{backends::InMemoryBackend, Database};
+
+    use super::*;
+    use crate::{
+        cache::FetchRecord,
+        favicon::{Favicon, PNG},
+        FaviconConfig,
+    };
+
+    #[tokio::test]
+    async fn test_prefetch() {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        let router = Router::new().route(
+            "/favicon.ico",
+            get(move || async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                b"\x89PNG\r\n\x1a\n".to_vec()
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let db = Database::builder()
+            .create_with_backend(InMemoryBackend::new())
+            .unwrap();
+        db::init(&db).unwrap();
+        let cached = Favicon {
+            mime_type: PNG.to_string(),
+            data: vec![0],
+        };
+        db::insert(&db, "docs.rs@16", &cached, FetchRecord::succeeded_now()).unwrap();
+        let state = Arc::new(AppState::new(db, FaviconConfig::default()));
+
+        let urls = [
+            format!("http://{addr}/a"),
+            format!("http://{addr}/b"),
+            "https://www.docs.rs/tauri".to_string(),
+            "not a url".to_string(),
+        ];
+        let started = start(&state, &urls, &[]).unwrap();
+        assert_eq!(started.total, 2);
+        // conflicts with the running job
+        assert!(start(&state, &[], &[]).unwrap_err().running);
+
+        let progress = wait(&state).await;
+        assert_eq!(
+            progress,
+            PrefetchProgress {
+                total: 2,
+                done: 2,
+                fetched: 1,
+                failed: 0,
+                skipped: 1,
+                running: false,
+            }
+        );
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+        // each size is fetched, 20px is rounded up to 32px
+        let started = start(&state, &urls[..1], &[16, 20]).unwrap();
+        assert_eq!(started.total, 2);
+        let progress = wait(&state).await;
+        assert_eq!((progress.fetched, progress.skipped), (1, 1));
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+
+    async fn wait(state: &AppState) -> PrefetchProgress {
+        let mut progress = PrefetchProgress::default();
+        for _ in 0..100 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            progress = super::progress(state);
+            if !progress.running {
+                break;
+            }
+        }
+        progress
+    }
+}
@@ -0,0 +1,370 @@
+use meta_parser::{manifest_icons, Icon, MetaScraper};
+use reqwest::{Client, Response};
+use url::Url;
+
+use crate::{cache::CachePolicy, error::Error, favicon::Favicon};
+
+/// Google's favicon service, sends the host to Google
+pub const GSTATIC_PROVIDER: &str = "https://t0.gstatic.com/faviconV2?client=SOCIAL&type=FAVICON&fallback_opts=TYPE,SIZE,URL&size={size}&url=https://{host}";
+
+/// DuckDuckGo's favicon service, sends the host to DuckDuckGo
+pub const DUCKDUCKGO_PROVIDER: &str = "https://icons.duckduckgo.com/ip3/{host}.ico";
+
+/// Max number of icons tried from a site, `/favicon.ico` is always tried
+const MAX_SITE_CANDIDATES: usize = 4;
+
+/// Responses larger than this are rejected without reading them to the end
+const MAX_RESPONSE_SIZE: usize = 4 * 1024 * 1024;
+
+/// Where favicons are fetched from and how long they are cached.
+/// The site itself is tried first, then third-party providers in order.
+#[derive(Debug, Clone)]
+pub struct FaviconConfig {
+    /// Fetch icons declared by the page and `/favicon.ico` from the site
+    pub fetch_from_site: bool,
+    /// URL templates of third-party providers, `{host}` and `{size}` are replaced
+    pub providers: Vec<String>,
+    /// Convert ICO into PNG of the requested size, which WebViews render more reliably
+    pub ico_to_png: bool,
+    pub cache: CachePolicy,
+    /// Max number of hosts fetched at once
+    pub max_concurrent_fetches: usize,
+}
+
+impl Default for FaviconConfig {
+    /// Only the site itself, no host is sent to third parties
+    fn default() -> Self {
+        Self {
+            fetch_from_site: true,
+            providers: Vec::new(),
+            ico_to_png: true,
+            cache: CachePolicy::default(),
+            max_concurrent_fetches: 8,
+        }
+    }
+}
+
+/// Fetch the favicon of the site of the URL through the provider chain
+pub(crate) async fn fetch_favicon(
+    client: &Client,
+    config: &FaviconConfig,
+    url: &Url,
+    size: u32,
+) -> Result<Favicon, Error> {
+    let favicon = fetch_from_chain(client, config, url, size).await?;
+    let favicon = if config.ico_to_png {
+        favicon.ico_to_png(size)
+    } else {
+        favicon
+    };
+    Ok(favicon.downscale(size))
+}
+
+async fn fetch_from_chain(
+    client: &Client,
+    config: &FaviconConfig,
+    url: &Url,
+    size: u32,
+) -> Result<Favicon, Error> {
+    let host = url
+        .host_str()
+        .ok_or_else(|| Error::Other(format!("Missing host: {url}")))?;
+
+    if config.fetch_from_site {
+        match fetch_from_site(client, url, size).await {
+            Ok(data) => return Ok(data),
+            Err(e) => log::debug!("No favicon from the site {host}: {e}"),
+        }
+    }
+
+    for template in &config.providers {
+        let provider_url = template
+            .replace("{host}", host)
+            .replace("{size}", &size.to_string());
+        log::debug!("Fetching favicon from: {}", provider_url);
+        match fetch_image(client, &provider_url).await {
+            Ok(data) => return Ok(data),
+            Err(e) => log::debug!("No favicon from {provider_url}: {e}"),
+        }
+    }
+
+    Err(Error::FaviconNotFound(host.to_string()))
+}
+
+/// Icons declared by the top page of the site, then `/favicon.ico`
+async fn fetch_from_site(client: &Client, url: &Url, size: u32) -> Result<Favicon, Error> {
+    let origin = site_origin(url)?;
+    let mut candidates = match find_icons(client, &origin, size).await {
+        Ok(icons) => icons,
+        Err(e) => {
+            log::debug!("Failed to find icons in {origin}: {e}");
+            Vec::new()
+        }
+    };
+    candidates.truncate(MAX_SITE_CANDIDATES);
+    let favicon_ico = origin.join("/favicon.ico")?;
+    if !candidates.contains(&favicon_ico) {
+        candidates.push(favicon_ico);
+    }
+
+    for candidate in candidates {
+        log::debug!("Fetching favicon from: {}", candidate);
+        match fetch_image(client, candidate.as_str()).await {
+            Ok(data) => return Ok(data),
+            Err(e) => log::debug!("No favicon from {candidate}: {e}"),
+        }
+    }
+    Err(Error::FaviconNotFound(url.to_string()))
+}
+
+/// URLs of the icons declared by the page and its manifest, the best one for the size first
+async fn find_icons(client: &Client, origin: &Url, size: u32) -> Result<Vec<Url>, Error> {
+    let res = client
+        .get(origin.as_str())
+        .send()
+        .await?
+        .error_for_status()?;
+    // relative URLs are resolved against the URL after redirects
+    let page_url = res.url().clone();
+    let html = String::from_utf8_lossy(&read_body(res).await?).into_owned();
+
+    let (mut icons, manifest_url) = {
+        let scraper = MetaScraper::parse(&html)
+            .map_err(|e| Error::Other(format!("HTML parse error: {e}")))?;
+        let base = scraper
+            .base_href()
+            .and_then(|href| page_url.join(&href).ok())
+            .unwrap_or(page_url);
+        let icons = resolve_icons(&base, scraper.icons());
+        let manifest_url = scraper.manifest().and_then(|href| base.join(&href).ok());
+        (icons, manifest_url)
+    };
+
+    if let Some(manifest_url) = manifest_url {
+        match fetch_manifest_icons(client, &manifest_url).await {
+            Ok(manifest) => icons.extend(manifest),
+            Err(e) => log::debug!("Failed to fetch manifest {manifest_url}: {e}"),
+        }
+    }
+
+    icons.sort_by_key(|(icon, _)| rank(icon, size));
+    Ok(icons.into_iter().map(|(_, url)| url).collect())
+}
+
+async fn fetch_manifest_icons(client: &Client, url: &Url) -> Result<Vec<(Icon, Url)>, Error> {
+    let res = client.get(url.as_str()).send().await?.error_for_status()?;
+    let json = String::from_utf8_lossy(&read_body(res).await?).into_owned();
+    Ok(resolve_icons(url, manifest_icons(&json)))
+}
+
+/// Absolute URLs of the icons, icons which can't be fetched over HTTP (e.g. `data:`) are dropped
+fn resolve_icons(base: &Url, icons: Vec<Icon>) -> Vec<(Icon, Url)> {
+    icons
+        .into_iter()
+        .filter_map(|icon| {
+            let url = base.join(&icon.href).ok()?;
+            matches!(url.scheme(), "http" | "https").then_some((icon, url))
+        })
+        .collect()
+}
+
+/// Smaller is better: raster icons first, then the closest size not smaller than requested.
+/// Icons without sizes come after the ones large enough, smaller icons come last.
+fn rank(icon: &Icon, size: u32) -> (bool, u32, meta_parser::IconKind) {
+    let distance = match icon.largest_size() {
+        Some(width) if width >= size => width - size,
+        None => u16::MAX as u32,
+        Some(width) => u16::MAX as u32 + size - width,
+    };
+    (icon.is_svg(), distance, icon.kind)
+}
+
+/// `scheme://host:port/` of the URL, sites which are not web pages are fetched over https
+fn site_origin(url: &Url) -> Result<Url, Error> {
+    let host = url
+        .host_str()
+        .ok_or_else(|| Error::Other(format!("Missing host: {url}")))?;
+    let origin = match (url.scheme(), url.port()) {
+        (scheme @ ("http" | "https"), Some(port)) => format!("{scheme}://{host}:{port}/"),
+        (scheme @ ("http" | "https"), None) => format!("{scheme}://{host}/"),
+        _ => format!("https://{host}/"),
+    };
+    Ok(Url::parse(&origin)?)
+}
+
+/// Body of a successful response which is an image, e.g. not an error page returned with 200
+async fn fetch_image(client: &Client, url: &str) -> Result<Favicon, Error> {
+    let res = client.get(url).send().await?.error_for_status()?;
+    let data = read_body(res).await?;
+    Favicon::sniff(data).ok_or_else(|| Error::Other(format!("Not an image: {url}")))
+}
+
+/// Body of the response up to `MAX_RESPONSE_SIZE`, checked by `Content-Length` and while reading
+async fn read_body(mut res: Response) -> Result<Vec<u8>, Error> {
+    let url = res.url().clone();
+    let too_large = || {
+        Error::Other(format!(
+            "Response from {url} is larger than {} MB",
+            MAX_RESPONSE_SIZE / 1024 / 1024
+        ))
+    };
+    if res
+        .content_length()
+        .is_some_and(|length| length > MAX_RESPONSE_SIZE as u64)
+    {
+        return Err(too_large());
+    }
+    let mut data = Vec::new();
+    while let Some(chunk) = res.chunk().await? {
+        if data.len() + chunk.len() > MAX_RESPONSE_SIZE {
+            return Err(too_large());
+        }
+        data.extend_from_slice(&chunk);
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use axum::{http::StatusCode, response::Html, routing::get, Router};
+
+    use super::*;
+    use crate::favicon::{tests::ico_data, PNG};
+
+    /// Serve the router on an ephemeral port of localhost
+    async fn serve(router: Router) -> SocketAddr {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        addr
+    }
+
+    /// PNG signature followed by the marker
+    fn png(marker: &str) -> Vec<u8> {
+        [b"\x89PNG\r\n\x1a\n".as_slice(), marker.as_bytes()].concat()
+    }
+
+    fn site_url(addr: SocketAddr) -> Url {
+        Url::parse(&format!("http://{addr}/docs/page.html")).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_fetch_declared_icon() {
+        let addr = serve(
+            Router::new()
+                .route(
+                    "/",
+                    get(|| async {
+                        Html(
+                            r#"<html><head>
+                            <link rel="icon" href="/icon.svg" type="image/svg+xml">
+                            <link rel="icon" href="static/icon-16.png" sizes="16x16">
+                            <link rel="apple-touch-icon" href="/apple.png" sizes="180x180">
+                            <link rel="manifest" href="/site.webmanifest">
+                            </head></html>"#,
+                        )
+                    }),
+                )
+                .route("/static/icon-16.png", get(|| async { png("16") }))
+                .route("/android-32.png", get(|| async { png("32") }))
+                .route(
+                    "/site.webmanifest",
+                    get(|| async { r#"{"icons":[{"src":"android-32.png","sizes":"32x32"}]}"# }),
+                ),
+        )
+        .await;
+        let client = Client::new();
+        let config = FaviconConfig::default();
+
+        let data = fetch_favicon(&client, &config, &site_url(addr), 16)
+            .await
+            .unwrap();
+        assert_eq!(data.data, png("16"));
+        // the manifest icon is closer to the size than the apple touch icon
+        let data = fetch_favicon(&client, &config, &site_url(addr), 32)
+            .await
+            .unwrap();
+        assert_eq!(data.data, png("32"));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_favicon_ico() {
+        let addr = serve(
+            Router::new()
+                .route("/", get(|| async { Html("<html><head></head></html>") }))
+                .route("/favicon.ico", get(|| async { ico_data() })),
+        )
+        .await;
+        let data = fetch_favicon(
+            &Client::new(),
+            &FaviconConfig::default(),
+            &site_url(addr),
+            16,
+        )
+        .await
+        .unwrap();
+        assert_eq!(data.mime_type, PNG);
+
+        let config = FaviconConfig {
+            ico_to_png: false,
+            ..Default::default()
+        };
+        let data = fetch_favicon(&Client::new(), &config, &site_url(addr), 16)
+            .await
+            .unwrap();
+        assert_eq!(data, Favicon::sniff(ico_data()).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_from_provider() {
+        let addr = serve(
+            Router::new()
+                // an error page with 200 is not an icon
+                .route("/favicon.ico", get(|| async { Html("Not Found") }))
+                .route(
+                    "/provider/{size}/{host}",
+                    get(|| async { (StatusCode::OK, png("provider")) }),
+                ),
+        )
+        .await;
+        let client = Client::new();
+
+        let result = fetch_favicon(&client, &FaviconConfig::default(), &site_url(addr), 16).await;
+        assert!(matches!(result, Err(Error::FaviconNotFound(_))));
+
+        let config = FaviconConfig {
+            providers: vec![
+                format!("http://{addr}/missing/{{host}}"),
+                format!("http://{addr}/provider/{{size}}/{{host}}"),
+            ],
+            ..Default::default()
+        };
+        let data = fetch_favicon(&client, &config, &site_url(addr), 16)
+            .await
+            .unwrap();
+        assert_eq!(data.data, png("provider"));
+    }
+
+    #[tokio::test]
+    async fn test_large_response() {
+        let addr = serve(
+            Router::new()
+                .route(
+                    "/large.png",
+                    get(|| async { png(&"0".repeat(MAX_RESPONSE_SIZE)) }),
+                )
+                .route("/small.png", get(|| async { png("small") })),
+        )
+        .await;
+        let client = Client::new();
+
+        let result = fetch_image(&client, &format!("http://{addr}/large.png")).await;
+        assert!(matches!(result, Err(Error::Other(_))));
+        let data = fetch_image(&client, &format!("http://{addr}/small.png"))
+            .await
+            .unwrap();
+        assert_eq!(data.data, png("small"));
+    }
+}
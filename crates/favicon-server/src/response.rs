@@ -1,22 +1,104 @@
+use std::fmt::Display;
+
 use axum::http::{header, StatusCode};
 use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Serialize;
+
+use crate::{error::Error, favicon::Favicon};
 
-pub fn create_image_response(data: Vec<u8>) -> Response {
+pub fn create_image_response(favicon: Favicon) -> Response {
     (
         StatusCode::OK,
         [
-            (header::CONTENT_TYPE, "image/png"),
-            (header::CACHE_CONTROL, "public, max-age=86400"),
+            (header::CONTENT_TYPE, favicon.mime_type),
+            (header::CACHE_CONTROL, "public, max-age=86400".to_string()),
         ],
-        data,
+        favicon.data,
     )
         .into_response()
 }
 
 pub fn create_default_image_response() -> Response {
-    // https://icons8.com/icon/3685/globe
-    // #339AF0
-    // https://icons8.com/icon/NyuxPErq0tu2/globe-africa
-    // #5C7CFA
-    create_image_response(include_bytes!("assets/default.png").to_vec())
+    create_image_response(Favicon::fallback())
+}
+
+#[derive(Serialize, Clone, Debug)]
+struct ErrorDetail {
+    #[serde(with = "http_serde::status_code")]
+    pub code: StatusCode,
+    pub message: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ErrorResponse {
+    error: ErrorDetail,
+}
+
+impl ErrorResponse {
+    pub fn new(code: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            error: ErrorDetail {
+                code,
+                message: message.into(),
+            },
+        }
+    }
+
+    pub fn to_json(&self) -> Json<Self> {
+        Json(self.clone())
+    }
+}
+
+impl Default for ErrorResponse {
+    fn default() -> Self {
+        Self {
+            error: ErrorDetail {
+                code: StatusCode::INTERNAL_SERVER_ERROR,
+                message: "Unknown error".to_string(),
+            },
+        }
+    }
+}
+
+impl Display for ErrorResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.error.code, self.error.message)
+    }
+}
+
+impl IntoResponse for ErrorResponse {
+    fn into_response(self) -> axum::response::Response {
+        log::warn!("Error: {}", self);
+        // (self.error.code, self.to_json()).into_response()
+        // this service needs to return image even if there is an error, so we return default image
+        create_default_image_response()
+    }
+}
+
+impl From<Error> for ErrorResponse {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::Io(err) => Self::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+            Error::AddrParse(err) => Self::new(StatusCode::BAD_REQUEST, err.to_string()),
+            Error::Reqwest(err) => Self::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+            Error::UrlParse(err) => Self::new(StatusCode::BAD_REQUEST, err.to_string()),
+            Error::Redb(err) => Self::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+            Error::PngDecoding(err) => {
+                Self::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+            }
+            Error::PngEncoding(err) => {
+                Self::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+            }
+            Error::FaviconNotFound(err) => Self::new(StatusCode::NOT_FOUND, err),
+            Error::InvalidImage(err) => Self::new(StatusCode::BAD_REQUEST, err),
+            Error::Other(err) => Self::new(StatusCode::INTERNAL_SERVER_ERROR, err),
+        }
+    }
+}
+
+impl From<url::ParseError> for ErrorResponse {
+    fn from(err: url::ParseError) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, err.to_string())
+    }
 }
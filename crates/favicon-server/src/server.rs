@@ -0,0 +1,143 @@
+use axum::{
+    http::{header, HeaderName, HeaderValue, Method},
+    middleware,
+    routing::delete,
+    routing::get,
+    routing::post,
+    routing::put,
+    Router,
+};
+use std::{net::SocketAddr, sync::Arc};
+use tower_http::cors::CorsLayer;
+
+use crate::{
+    auth::{self, TOKEN_HEADER},
+    error::Error,
+    handler::{get_favicon, handler_404, health_check, remove_all_favicons},
+    manage::{
+        cache_stats, compact_database, delete_custom_favicon, list_favicons, prefetch_progress,
+        prune_favicons, put_custom_favicon, remove_host, start_prefetch,
+    },
+    FaviconConfig, FaviconServer, Favicons,
+};
+
+/// Open the database and start the server in the background.
+/// Port 0 binds an ephemeral port, see [`FaviconServer::addr`] for the bound one.
+pub async fn run<P>(
+    path: P,
+    port: u16,
+    allow_origins: Vec<&str>,
+    config: FaviconConfig,
+) -> Result<FaviconServer, Error>
+where
+    P: AsRef<std::path::Path>,
+{
+    let favicons = Favicons::open(path, config)?;
+    serve(favicons, port, allow_origins).await
+}
+
+/// Start the server in the background, returns once the port is bound
+pub async fn serve(
+    favicons: Favicons,
+    port: u16,
+    allow_origins: Vec<&str>,
+) -> Result<FaviconServer, Error> {
+    let origins = allow_origins
+        .iter()
+        .map(|addr| addr.parse::<HeaderValue>().unwrap())
+        .collect::<Vec<_>>();
+
+    let cors = CorsLayer::new()
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
+        .allow_headers([
+            header::AUTHORIZATION,
+            header::ACCEPT,
+            header::CONTENT_TYPE,
+            header::ACCESS_CONTROL_REQUEST_HEADERS,
+            HeaderName::from_static(TOKEN_HEADER),
+        ])
+        .allow_origin(origins);
+
+    let token = auth::generate_token();
+    let app = Router::new()
+        .route("/", get(health_check))
+        .route("/favicon", get(get_favicon))
+        .route("/favicon", delete(remove_all_favicons))
+        .route("/favicon/hosts", get(list_favicons))
+        .route("/favicon/hosts/{host}", delete(remove_host))
+        .route("/favicon/custom/{host}", put(put_custom_favicon))
+        .route("/favicon/custom/{host}", delete(delete_custom_favicon))
+        .route("/favicon/stats", get(cache_stats))
+        .route("/favicon/prune", post(prune_favicons))
+        .route("/favicon/compact", post(compact_database))
+        .route("/favicon/prefetch", get(prefetch_progress))
+        .route("/favicon/prefetch", post(start_prefetch))
+        .route_layer(middleware::from_fn_with_state(
+            Arc::<str>::from(token.as_str()),
+            auth::require_token,
+        ))
+        .layer(cors)
+        .with_state(favicons.state)
+        .fallback(handler_404);
+
+    let listener = tokio::net::TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], port))).await?;
+    let addr = listener.local_addr()?;
+
+    log::info!("Favicon server starts on http://{}", addr);
+
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, app).await {
+            log::error!("Favicon server stopped: {e}");
+        }
+    });
+    Ok(FaviconServer { addr, token })
+}
+
+#[cfg(test)]
+mod tests {
+    use redb::{backends::InMemoryBackend, Database};
+    use reqwest::Client;
+
+    use super::*;
+    use crate::{auth::TOKEN_QUERY, db, AppState};
+
+    #[tokio::test]
+    async fn test_serve_with_token() {
+        let db = Database::builder()
+            .create_with_backend(InMemoryBackend::new())
+            .unwrap();
+        db::init(&db).unwrap();
+        let favicons = Favicons {
+            state: Arc::new(AppState::new(db, FaviconConfig::default())),
+        };
+        let server = serve(favicons, 0, Vec::new()).await.unwrap();
+        assert_ne!(server.addr.port(), 0);
+
+        let client = Client::new();
+        let url = format!("http://{}/favicon/stats", server.addr);
+        let response = client.get(&url).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+        let response = client
+            .get(&url)
+            .query(&[(TOKEN_QUERY, "guess")])
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        let response = client
+            .get(&url)
+            .header(TOKEN_HEADER, &server.token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let response = client
+            .get(&url)
+            .query(&[(TOKEN_QUERY, &server.token)])
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+    }
+}
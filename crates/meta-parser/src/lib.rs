@@ -3,6 +3,7 @@
 //! [crate]: https://crates.io/crates/metascraper
 //! [github]: https://github.com/mehmetcansahin/metascraper
 
+use serde::Deserialize;
 use tl::{ParseError, ParserOptions, VDom};
 
 #[derive(Debug)]
@@ -16,6 +17,75 @@ pub struct MetaData {
     pub title: Option<String>,
 }
 
+/// Kind of an icon declared by a page
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum IconKind {
+    /// `<link rel="icon">` or `<link rel="shortcut icon">`
+    Icon,
+    /// `<link rel="apple-touch-icon">`
+    AppleTouchIcon,
+    /// `icons` in the web app manifest
+    Manifest,
+}
+
+/// Icon declared by a page, `href` is as written and may be relative
+#[derive(Debug, Clone, PartialEq)]
+pub struct Icon {
+    pub kind: IconKind,
+    pub href: String,
+    /// `sizes` attribute, e.g. `16x16 32x32` or `any`
+    pub sizes: Option<String>,
+    pub mime_type: Option<String>,
+}
+
+impl Icon {
+    /// Largest width of `sizes`, None if unknown or `any`
+    pub fn largest_size(&self) -> Option<u32> {
+        self.sizes
+            .as_deref()?
+            .split_whitespace()
+            .filter_map(|size| size.to_ascii_lowercase().split_once('x')?.0.parse().ok())
+            .max()
+    }
+
+    pub fn is_svg(&self) -> bool {
+        self.mime_type.as_deref() == Some("image/svg+xml")
+            || self.href.to_ascii_lowercase().ends_with(".svg")
+    }
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+    #[serde(default)]
+    icons: Vec<ManifestIcon>,
+}
+
+#[derive(Deserialize)]
+struct ManifestIcon {
+    src: String,
+    sizes: Option<String>,
+    #[serde(rename = "type")]
+    mime_type: Option<String>,
+}
+
+/// Icons of a web app manifest, an invalid manifest has no icons.
+/// `href` of the icons is relative to the manifest URL.
+pub fn manifest_icons(json: &str) -> Vec<Icon> {
+    let Ok(manifest) = serde_json::from_str::<Manifest>(json) else {
+        return Vec::new();
+    };
+    manifest
+        .icons
+        .into_iter()
+        .map(|icon| Icon {
+            kind: IconKind::Manifest,
+            href: icon.src,
+            sizes: icon.sizes,
+            mime_type: icon.mime_type,
+        })
+        .collect()
+}
+
 pub struct MetaScraper<'a> {
     dom: VDom<'a>,
 }
@@ -84,6 +154,51 @@ impl MetaScraper<'_> {
         Some(metatags)
     }
 
+    /// Icons declared by `<link>` in the document order.
+    /// `mask-icon` is not included since it's a monochrome mask.
+    pub fn icons(&self) -> Vec<Icon> {
+        let Some(links) = self.dom.query_selector("link") else {
+            return Vec::new();
+        };
+        let parser = self.dom.parser();
+        links
+            .filter_map(|node_handle| node_handle.get(parser)?.as_tag())
+            .filter_map(|tag| {
+                let attribute = |name: &str| {
+                    tag.attributes()
+                        .get(name)
+                        .flatten()
+                        .map(|bytes| bytes.as_utf8_str().trim().to_string())
+                };
+                let rel = attribute("rel")?.to_ascii_lowercase();
+                let kind = rel.split_whitespace().find_map(|rel| match rel {
+                    "icon" => Some(IconKind::Icon),
+                    "apple-touch-icon" | "apple-touch-icon-precomposed" => {
+                        Some(IconKind::AppleTouchIcon)
+                    }
+                    _ => None,
+                })?;
+                let href = attribute("href").filter(|href| !href.is_empty())?;
+                Some(Icon {
+                    kind,
+                    href,
+                    sizes: attribute("sizes"),
+                    mime_type: attribute("type"),
+                })
+            })
+            .collect()
+    }
+
+    /// `href` of `<link rel="manifest">`
+    pub fn manifest(&self) -> Option<String> {
+        self.attribute("link[rel=manifest]", "href")
+    }
+
+    /// `href` of `<base>`, relative URLs of the document are resolved against it
+    pub fn base_href(&self) -> Option<String> {
+        self.attribute("base", "href")
+    }
+
     /// Returns the title
     pub fn title(&self) -> Option<String> {
         self.inner_text("title")
@@ -106,7 +221,7 @@ impl MetaScraper<'_> {
 mod tests {
     use std::time::Duration;
 
-    use crate::MetaScraper;
+    use crate::{manifest_icons, IconKind, MetaScraper};
 
     #[test]
     fn test_page() {
@@ -116,6 +231,33 @@ mod tests {
         assert_eq!(metadata.title, Some("Title".to_string()));
     }
 
+    #[test]
+    fn test_icons() {
+        let input = include_str!("test.html");
+        let metascraper = MetaScraper::parse(input).unwrap();
+        let icons = metascraper.icons();
+        assert_eq!(icons.len(), 3);
+        assert_eq!(icons[0].kind, IconKind::Icon);
+        assert_eq!(icons[0].href, "/favicon-32x32.png");
+        assert_eq!(icons[0].largest_size(), Some(32));
+        assert_eq!(icons[1].kind, IconKind::Icon);
+        assert!(icons[1].is_svg());
+        assert_eq!(icons[2].kind, IconKind::AppleTouchIcon);
+        assert_eq!(icons[2].largest_size(), Some(180));
+        assert_eq!(
+            metascraper.manifest(),
+            Some("/site.webmanifest".to_string())
+        );
+
+        let icons = manifest_icons(
+            r#"{"name":"Site","icons":[{"src":"android-192.png","sizes":"192x192 96x96","type":"image/png"}]}"#,
+        );
+        assert_eq!(icons.len(), 1);
+        assert_eq!(icons[0].kind, IconKind::Manifest);
+        assert_eq!(icons[0].largest_size(), Some(192));
+        assert!(manifest_icons("not json").is_empty());
+    }
+
     #[test]
     fn test_reqwest() {
         const BROWSER_USER_AGENT: &str =
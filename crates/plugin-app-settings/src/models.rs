@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use parus_common::constants::DEFAULT_BOOKMARK_COLLECTION_ID;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppSettings {
     #[serde(default = "default_gpu_acceleration_enabled")]
@@ -10,6 +12,10 @@ pub struct AppSettings {
 
     #[serde(default = "default_start_page_url")]
     pub start_page_url: String,
+
+    /// Id of the bookmark collection opened at startup
+    #[serde(default = "default_active_bookmark_collection")]
+    pub active_bookmark_collection: String,
 }
 
 impl Default for AppSettings {
@@ -18,6 +24,7 @@ impl Default for AppSettings {
             gpu_acceleration_enabled: default_gpu_acceleration_enabled(),
             incognito: default_incognito(),
             start_page_url: default_start_page_url(),
+            active_bookmark_collection: default_active_bookmark_collection(),
         }
     }
 }
@@ -33,3 +40,7 @@ fn default_incognito() -> bool {
 pub fn default_start_page_url() -> String {
     "https://search.brave.com/".to_string()
 }
+
+fn default_active_bookmark_collection() -> String {
+    DEFAULT_BOOKMARK_COLLECTION_ID.to_string()
+}
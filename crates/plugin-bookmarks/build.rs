@@ -13,6 +13,32 @@ const COMMANDS: &[&str] = &[
     "prepend_to_child",
     "set_is_open",
     "toggle_is_open",
+    "search_bookmarks",
+    "sort_by_frecency",
+    "add_smart_folder",
+    "update_smart_folder_query",
+    "set_bookmark_tags",
+    "set_bookmark_keyword",
+    "add_to_reading_list",
+    "set_is_read",
+    "get_reading_list",
+    "get_sync_config",
+    "set_sync_config",
+    "get_sync_status",
+    "sync_bookmarks",
+    "get_encryption_status",
+    "unlock_bookmarks",
+    "enable_encryption",
+    "disable_encryption",
+    "list_collections",
+    "create_collection",
+    "rename_collection",
+    "delete_collection",
+    "switch_collection",
+    "group_bookmarks_by_domain",
+    "organize_by_domain",
+    "get_bookmark_stats",
+    "get_favicon_urls",
 ];
 
 fn main() {
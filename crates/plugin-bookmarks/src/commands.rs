@@ -1,12 +1,26 @@
 use std::sync::Mutex;
 
 use serde::Serialize;
+use tauri::Manager;
 
 use parus_bookmark::{
-    bookmarks::{Bookmarks, NestedBookmark},
-    data::{FolderData, ToolbarBookmarkData},
+    bookmarks::{domain::DomainGrouping, stats::BookmarkStats, Bookmarks, NestedBookmark},
+    crypto::EncryptionKey,
+    data::{
+        DomainGroupData, FolderData, ReadingListItemData, SearchResultData, ToolbarBookmarkData,
+    },
+    query::SmartQuery,
+};
+use parus_common::{constants::EXTERNAL_WEBVIEW_LABEL, Error};
+use parus_sync::{pending_changes, SyncConfig};
+
+use crate::{
+    models::{
+        CollectionInfo, CollectionList, EncryptionStatus, SyncReport, SyncSettings, SyncStatus,
+    },
+    registry::BookmarksRegistry,
+    repair_bookmarks, AppHandleExt, BookmarksExt,
 };
-use parus_common::Error;
 
 #[derive(Serialize)]
 pub struct BookmarkResponse {
@@ -16,48 +30,54 @@ pub struct BookmarkResponse {
 
 #[tauri::command]
 pub fn get_nested_json(
-    state: tauri::State<'_, Mutex<Bookmarks>>,
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
     index: usize,
 ) -> Result<NestedBookmark, Error> {
     if index == 0 {
         return Err(Error::Other("index should not be 0".to_string()));
     }
-    let bookmarks = state
+    let registry = state
         .lock()
         .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let bookmarks = registry.active_bookmarks()?;
     Ok(bookmarks.to_nested_bookmark(index)?)
 }
 
 #[tauri::command]
 pub fn get_root_and_children_folders(
-    state: tauri::State<'_, Mutex<Bookmarks>>,
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
 ) -> Result<Vec<FolderData>, Error> {
-    let bookmarks = state
+    let registry = state
         .lock()
         .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let bookmarks = registry.active_bookmarks()?;
     Ok(bookmarks.get_root_and_children_folders()?)
 }
 
 #[tauri::command]
 pub fn get_toolbar_bookmarks(
-    state: tauri::State<'_, Mutex<Bookmarks>>,
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
 ) -> Result<Vec<ToolbarBookmarkData>, Error> {
-    let bookmarks = state
+    let registry = state
         .lock()
         .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let bookmarks = registry.active_bookmarks()?;
     Ok(bookmarks.get_toolbar_bookmarks())
 }
 
 #[tauri::command]
-pub fn add_bookmark(
-    state: tauri::State<'_, Mutex<Bookmarks>>,
+pub fn add_bookmark<R: tauri::Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
     title: String,
     url: String,
     top_level_index: usize,
 ) -> Result<BookmarkResponse, Error> {
-    let mut bookmarks = state
+    let mut registry = state
         .lock()
         .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let bookmarks = registry.active_bookmarks_mut()?;
+    bookmarks.set_scheme_policy(app_handle.scheme_policy());
     let index = bookmarks.add_bookmark(&title, &url, top_level_index)?;
     let nested = bookmarks.to_nested_bookmark(top_level_index)?;
 
@@ -68,15 +88,18 @@ pub fn add_bookmark(
 }
 
 #[tauri::command]
-pub fn append_bookmark_to_toolbar(
-    state: tauri::State<'_, Mutex<Bookmarks>>,
+pub fn append_bookmark_to_toolbar<R: tauri::Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
     title: String,
     url: String,
     top_level_index: usize,
 ) -> Result<NestedBookmark, Error> {
-    let mut bookmarks = state
+    let mut registry = state
         .lock()
         .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let bookmarks = registry.active_bookmarks_mut()?;
+    bookmarks.set_scheme_policy(app_handle.scheme_policy());
     bookmarks.append_bookmark_to_toolbar(&title, &url)?;
 
     Ok(bookmarks.to_nested_bookmark(top_level_index)?)
@@ -84,13 +107,14 @@ pub fn append_bookmark_to_toolbar(
 
 #[tauri::command]
 pub fn remove_bookmark(
-    state: tauri::State<'_, Mutex<Bookmarks>>,
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
     index: usize,
     top_level_index: usize,
 ) -> Result<NestedBookmark, Error> {
-    let mut bookmarks = state
+    let mut registry = state
         .lock()
         .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let bookmarks = registry.active_bookmarks_mut()?;
     bookmarks.remove_subtree(index)?;
 
     Ok(bookmarks.to_nested_bookmark(top_level_index)?)
@@ -98,14 +122,15 @@ pub fn remove_bookmark(
 
 #[tauri::command]
 pub fn update_bookmark_title(
-    state: tauri::State<'_, Mutex<Bookmarks>>,
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
     index: usize,
     title: String,
     top_level_index: usize,
 ) -> Result<NestedBookmark, Error> {
-    let mut bookmarks = state
+    let mut registry = state
         .lock()
         .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let bookmarks = registry.active_bookmarks_mut()?;
     bookmarks.update_title(index, title)?;
 
     Ok(bookmarks.to_nested_bookmark(top_level_index)?)
@@ -113,14 +138,15 @@ pub fn update_bookmark_title(
 
 #[tauri::command]
 pub fn add_folder(
-    state: tauri::State<'_, Mutex<Bookmarks>>,
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
     parent_index: usize,
     title: String,
     top_level_index: usize,
 ) -> Result<BookmarkResponse, Error> {
-    let mut bookmarks = state
+    let mut registry = state
         .lock()
         .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let bookmarks = registry.active_bookmarks_mut()?;
 
     let index = bookmarks.add_folder(parent_index, &title)?;
     let nested = bookmarks.to_nested_bookmark(top_level_index)?;
@@ -133,14 +159,15 @@ pub fn add_folder(
 
 #[tauri::command]
 pub fn insert_after(
-    state: tauri::State<'_, Mutex<Bookmarks>>,
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
     source_index: usize,
     destination_index: usize,
     top_level_index: usize,
 ) -> Result<NestedBookmark, Error> {
-    let mut bookmarks = state
+    let mut registry = state
         .lock()
         .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let bookmarks = registry.active_bookmarks_mut()?;
     bookmarks.insert_after(source_index, destination_index)?;
 
     Ok(bookmarks.to_nested_bookmark(top_level_index)?)
@@ -148,14 +175,15 @@ pub fn insert_after(
 
 #[tauri::command]
 pub fn insert_before(
-    state: tauri::State<'_, Mutex<Bookmarks>>,
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
     source_index: usize,
     destination_index: usize,
     top_level_index: usize,
 ) -> Result<NestedBookmark, Error> {
-    let mut bookmarks = state
+    let mut registry = state
         .lock()
         .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let bookmarks = registry.active_bookmarks_mut()?;
     bookmarks.insert_before(source_index, destination_index)?;
 
     Ok(bookmarks.to_nested_bookmark(top_level_index)?)
@@ -163,14 +191,15 @@ pub fn insert_before(
 
 #[tauri::command]
 pub fn append_to_child(
-    state: tauri::State<'_, Mutex<Bookmarks>>,
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
     source_index: usize,
     destination_index: usize,
     top_level_index: usize,
 ) -> Result<NestedBookmark, Error> {
-    let mut bookmarks = state
+    let mut registry = state
         .lock()
         .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let bookmarks = registry.active_bookmarks_mut()?;
     bookmarks.append_to_child(source_index, destination_index)?;
 
     Ok(bookmarks.to_nested_bookmark(top_level_index)?)
@@ -178,14 +207,15 @@ pub fn append_to_child(
 
 #[tauri::command]
 pub fn prepend_to_child(
-    state: tauri::State<'_, Mutex<Bookmarks>>,
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
     source_index: usize,
     destination_index: usize,
     top_level_index: usize,
 ) -> Result<NestedBookmark, Error> {
-    let mut bookmarks = state
+    let mut registry = state
         .lock()
         .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let bookmarks = registry.active_bookmarks_mut()?;
     bookmarks.prepend_to_child(source_index, destination_index)?;
 
     Ok(bookmarks.to_nested_bookmark(top_level_index)?)
@@ -193,14 +223,15 @@ pub fn prepend_to_child(
 
 #[tauri::command]
 pub fn set_is_open(
-    state: tauri::State<'_, Mutex<Bookmarks>>,
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
     index: usize,
     is_open: bool,
     top_level_index: usize,
 ) -> Result<NestedBookmark, Error> {
-    let mut bookmarks = state
+    let mut registry = state
         .lock()
         .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let bookmarks = registry.active_bookmarks_mut()?;
     bookmarks.set_is_open(index, is_open)?;
 
     Ok(bookmarks.to_nested_bookmark(top_level_index)?)
@@ -208,14 +239,589 @@ pub fn set_is_open(
 
 #[tauri::command]
 pub fn toggle_is_open(
-    state: tauri::State<'_, Mutex<Bookmarks>>,
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
     index: usize,
     top_level_index: usize,
 ) -> Result<NestedBookmark, Error> {
-    let mut bookmarks = state
+    let mut registry = state
         .lock()
         .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let bookmarks = registry.active_bookmarks_mut()?;
     bookmarks.toggle_is_open(index)?;
 
     Ok(bookmarks.to_nested_bookmark(top_level_index)?)
 }
+
+#[tauri::command]
+pub fn search_bookmarks(
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
+    query: String,
+) -> Result<Vec<SearchResultData>, Error> {
+    let registry = state
+        .lock()
+        .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let bookmarks = registry.active_bookmarks()?;
+    Ok(bookmarks.search(&query)?)
+}
+
+#[tauri::command]
+pub fn sort_by_frecency(
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
+    index: usize,
+    top_level_index: usize,
+) -> Result<NestedBookmark, Error> {
+    let mut registry = state
+        .lock()
+        .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let bookmarks = registry.active_bookmarks_mut()?;
+    bookmarks.sort_by_frecency(index)?;
+
+    Ok(bookmarks.to_nested_bookmark(top_level_index)?)
+}
+
+/// Statistics of the active collection for the library stats page
+#[tauri::command]
+pub fn get_bookmark_stats(
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
+) -> Result<BookmarkStats, Error> {
+    let registry = state
+        .lock()
+        .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let bookmarks = registry.active_bookmarks()?;
+    Ok(bookmarks.stats()?)
+}
+
+/// Bookmarks under the node grouped by host or registrable domain
+#[tauri::command]
+pub fn group_bookmarks_by_domain(
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
+    index: usize,
+    grouping: DomainGrouping,
+) -> Result<Vec<DomainGroupData>, Error> {
+    let registry = state
+        .lock()
+        .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let bookmarks = registry.active_bookmarks()?;
+    Ok(bookmarks.group_by_domain(index, grouping)?)
+}
+
+/// One URL of each bookmarked host to prefetch their favicons
+#[tauri::command]
+pub fn get_favicon_urls(
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
+) -> Result<Vec<String>, Error> {
+    let registry = state
+        .lock()
+        .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let bookmarks = registry.active_bookmarks()?;
+    Ok(bookmarks.favicon_urls()?)
+}
+
+/// Move the bookmarks in the folder into a subfolder per domain
+#[tauri::command]
+pub fn organize_by_domain(
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
+    index: usize,
+    grouping: DomainGrouping,
+    top_level_index: usize,
+) -> Result<NestedBookmark, Error> {
+    let mut registry = state
+        .lock()
+        .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let bookmarks = registry.active_bookmarks_mut()?;
+    bookmarks.organize_by_domain(index, grouping)?;
+
+    Ok(bookmarks.to_nested_bookmark(top_level_index)?)
+}
+
+#[tauri::command]
+pub fn add_smart_folder(
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
+    parent_index: usize,
+    title: String,
+    query: SmartQuery,
+    top_level_index: usize,
+) -> Result<BookmarkResponse, Error> {
+    let mut registry = state
+        .lock()
+        .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let bookmarks = registry.active_bookmarks_mut()?;
+
+    let index = bookmarks.add_smart_folder(parent_index, &title, query)?;
+    let nested = bookmarks.to_nested_bookmark(top_level_index)?;
+
+    Ok(BookmarkResponse {
+        index,
+        bookmarks: nested,
+    })
+}
+
+#[tauri::command]
+pub fn update_smart_folder_query(
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
+    index: usize,
+    query: SmartQuery,
+    top_level_index: usize,
+) -> Result<NestedBookmark, Error> {
+    let mut registry = state
+        .lock()
+        .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let bookmarks = registry.active_bookmarks_mut()?;
+    bookmarks.update_smart_query(index, query)?;
+
+    Ok(bookmarks.to_nested_bookmark(top_level_index)?)
+}
+
+#[tauri::command]
+pub fn set_bookmark_tags(
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
+    index: usize,
+    tags: Vec<String>,
+    top_level_index: usize,
+) -> Result<NestedBookmark, Error> {
+    let mut registry = state
+        .lock()
+        .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let bookmarks = registry.active_bookmarks_mut()?;
+    bookmarks.set_tags(index, tags)?;
+
+    Ok(bookmarks.to_nested_bookmark(top_level_index)?)
+}
+
+#[tauri::command]
+pub fn set_bookmark_keyword(
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
+    index: usize,
+    keyword: Option<String>,
+    top_level_index: usize,
+) -> Result<NestedBookmark, Error> {
+    let mut registry = state
+        .lock()
+        .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let bookmarks = registry.active_bookmarks_mut()?;
+    bookmarks.set_keyword(index, keyword)?;
+
+    Ok(bookmarks.to_nested_bookmark(top_level_index)?)
+}
+
+/// Add the current page of the external webview to the reading list
+#[tauri::command]
+pub fn add_to_reading_list<R: tauri::Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
+    title: String,
+    top_level_index: usize,
+) -> Result<BookmarkResponse, Error> {
+    let url = app_handle
+        .get_webview(EXTERNAL_WEBVIEW_LABEL)
+        .ok_or(Error::WebviewNotFound)?
+        .url()?;
+
+    let mut registry = state
+        .lock()
+        .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let bookmarks = registry.active_bookmarks_mut()?;
+    bookmarks.set_scheme_policy(app_handle.scheme_policy());
+    let index = bookmarks.add_to_reading_list(&title, url.as_str())?;
+    let nested = bookmarks.to_nested_bookmark(top_level_index)?;
+
+    Ok(BookmarkResponse {
+        index,
+        bookmarks: nested,
+    })
+}
+
+#[tauri::command]
+pub fn set_is_read(
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
+    index: usize,
+    is_read: bool,
+    top_level_index: usize,
+) -> Result<NestedBookmark, Error> {
+    let mut registry = state
+        .lock()
+        .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let bookmarks = registry.active_bookmarks_mut()?;
+    bookmarks.set_is_read(index, is_read)?;
+
+    Ok(bookmarks.to_nested_bookmark(top_level_index)?)
+}
+
+#[tauri::command]
+pub fn get_reading_list(
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
+    unread_only: bool,
+) -> Result<Vec<ReadingListItemData>, Error> {
+    let registry = state
+        .lock()
+        .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let bookmarks = registry.active_bookmarks()?;
+    Ok(bookmarks.get_reading_list(unread_only))
+}
+
+#[tauri::command]
+pub fn get_sync_config(
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
+) -> Result<Option<SyncConfig>, Error> {
+    let registry = state
+        .lock()
+        .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    Ok(registry.active()?.sync.config.clone())
+}
+
+/// Set or clear the sync configuration of the active collection.
+/// The sync history is reset because the new backend does not share it.
+/// The WebDAV password is saved in the OS credential store.
+#[tauri::command]
+pub fn set_sync_config<R: tauri::Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
+    config: Option<SyncConfig>,
+) -> Result<(), Error> {
+    let mut registry = state
+        .lock()
+        .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let id = registry.active_id().to_string();
+    let collection = registry.active_mut()?;
+    collection.sync = SyncSettings {
+        config,
+        ..Default::default()
+    };
+    app_handle.save_sync_password(&id, collection.sync.password())?;
+    app_handle.remove_sync_base(&id)?;
+    app_handle.save_sync_settings(&id, &collection.sync)
+}
+
+#[tauri::command]
+pub fn get_sync_status<R: tauri::Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
+) -> Result<SyncStatus, Error> {
+    let registry = state
+        .lock()
+        .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let collection = registry.active()?;
+    // the placeholder of locked bookmarks has no changes
+    let pending_changes = if collection.encryption.locked {
+        Vec::new()
+    } else {
+        let base =
+            app_handle.load_sync_base(registry.active_id(), collection.encryption.key.as_ref());
+        pending_changes(base.as_ref(), &collection.bookmarks)
+    };
+
+    Ok(SyncStatus {
+        configured: collection.sync.config.is_some(),
+        last_synced_at: collection.sync.last_synced_at,
+        last_error: collection.sync.last_error.clone(),
+        pending_changes,
+    })
+}
+
+/// Sync the active collection with its configured backend.
+/// The remote file of an encrypted collection is encrypted with the same key.
+/// Node indexes change after sync, so the frontend should reload the whole tree.
+#[tauri::command]
+pub async fn sync_bookmarks<R: tauri::Runtime>(
+    app_handle: tauri::AppHandle<R>,
+) -> Result<SyncReport, Error> {
+    // the collection may be switched while syncing, so the result is applied by id
+    let (id, config, local, base, key) = {
+        let state = app_handle.state::<Mutex<BookmarksRegistry>>();
+        let registry = state
+            .lock()
+            .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+        let collection = registry.active()?;
+        if collection.encryption.locked {
+            return Err(Error::BookmarksLocked);
+        }
+        let config = collection
+            .sync
+            .config
+            .clone()
+            .ok_or(parus_sync::Error::NotConfigured())?;
+        let id = registry.active_id().to_string();
+        let base = app_handle.load_sync_base(&id, collection.encryption.key.as_ref());
+        (
+            id,
+            config,
+            Bookmarks::new(collection.bookmarks.arena().clone()),
+            base,
+            collection.encryption.key.clone(),
+        )
+    };
+
+    let backend = config.backend.build()?;
+    let result = parus_sync::sync(
+        &backend,
+        &local,
+        base.as_ref(),
+        config.strategy,
+        key.as_ref(),
+    )
+    .await;
+    let synced_at = parus_bookmark::get_unix_timestamp().unwrap_or_default();
+    let outcome = match result {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            app_handle
+                .update_sync_settings(&id, |settings| settings.last_error = Some(e.to_string()))?;
+            return Err(e.into());
+        }
+    };
+
+    {
+        let state = app_handle.state::<Mutex<BookmarksRegistry>>();
+        let mut registry = state
+            .lock()
+            .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+        let collection = registry.get_mut(&id)?;
+        app_handle.save_sync_base(&id, &outcome.bookmarks, collection.encryption.key.as_ref())?;
+        // keep changes made while syncing
+        collection.bookmarks = if collection.bookmarks.is_equivalent(&local) {
+            outcome.bookmarks
+        } else {
+            Bookmarks::three_way_merge(&local, &collection.bookmarks, &outcome.bookmarks).bookmarks
+        };
+        app_handle.save_collection(&id, collection)?;
+    }
+    app_handle.update_sync_settings(&id, |settings| {
+        settings.last_synced_at = Some(synced_at);
+        settings.last_error = None;
+    })?;
+
+    Ok(SyncReport {
+        synced_at,
+        uploaded: outcome.uploaded,
+        conflicts: outcome.conflicts,
+    })
+}
+
+#[tauri::command]
+pub fn get_encryption_status(
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
+) -> Result<EncryptionStatus, Error> {
+    let registry = state
+        .lock()
+        .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let encryption = &registry.active()?.encryption;
+    Ok(EncryptionStatus {
+        encrypted: encryption.locked || encryption.key.is_some(),
+        locked: encryption.locked,
+    })
+}
+
+/// Decrypt the file of the active collection with the passphrase and replace the placeholder bookmarks
+#[tauri::command]
+pub async fn unlock_bookmarks<R: tauri::Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    passphrase: String,
+) -> Result<NestedBookmark, Error> {
+    let id = {
+        let state = app_handle.state::<Mutex<BookmarksRegistry>>();
+        let registry = state
+            .lock()
+            .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+        if !registry.active()?.encryption.locked {
+            return Err(Error::Other("bookmarks are not locked".to_string()));
+        }
+        registry.active_id().to_string()
+    };
+
+    let path = app_handle.collection_paths(&id).bookmarks;
+    let (mut unlocked, key) =
+        derive_key_blocking(move || Bookmarks::load_from_encrypted_file(path, &passphrase)).await?;
+    if !repair_bookmarks(&mut unlocked) {
+        return Err(Error::Other("bookmarks can't be repaired".to_string()));
+    }
+
+    let state = app_handle.state::<Mutex<BookmarksRegistry>>();
+    let mut registry = state
+        .lock()
+        .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let collection = registry.get_mut(&id)?;
+    if collection.encryption.locked {
+        collection.bookmarks = unlocked;
+        collection.encryption.key = Some(key);
+        collection.encryption.locked = false;
+    }
+
+    Ok(collection.bookmarks.to_nested_bookmark(1)?)
+}
+
+/// Encrypt the file of the active collection with the passphrase, or change the passphrase if already encrypted
+#[tauri::command]
+pub async fn enable_encryption<R: tauri::Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    passphrase: String,
+) -> Result<(), Error> {
+    if passphrase.is_empty() {
+        return Err(Error::Other("passphrase should not be empty".to_string()));
+    }
+    let id = active_unlocked_id(&app_handle)?;
+    let key = derive_key_blocking(move || EncryptionKey::derive(&passphrase)).await?;
+
+    let state = app_handle.state::<Mutex<BookmarksRegistry>>();
+    let mut registry = state
+        .lock()
+        .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let collection = registry.get_mut(&id)?;
+    if collection.encryption.locked {
+        return Err(Error::BookmarksLocked);
+    }
+    let old_key = collection.encryption.key.replace(key.clone());
+    app_handle.save_collection(&id, collection)?;
+
+    // the sync base and the backup made on load must not be left as plain JSON
+    app_handle.rekey_sync_base(&id, old_key.as_ref(), Some(&key))?;
+    let backup_path = app_handle
+        .collection_paths(&id)
+        .bookmarks
+        .with_extension("bak");
+    if backup_path.exists() && !Bookmarks::is_encrypted_file(&backup_path)? {
+        let data = std::fs::read(&backup_path)?;
+        std::fs::write(&backup_path, key.encrypt(&data)?)?;
+    }
+    Ok(())
+}
+
+/// Store the file of the active collection as plain JSON again. The current passphrase is required.
+#[tauri::command]
+pub async fn disable_encryption<R: tauri::Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    passphrase: String,
+) -> Result<(), Error> {
+    let id = active_unlocked_id(&app_handle)?;
+    // verify the passphrase with the saved file
+    let path = app_handle.collection_paths(&id).bookmarks;
+    if Bookmarks::is_encrypted_file(&path)? {
+        derive_key_blocking(move || Bookmarks::load_from_encrypted_file(path, &passphrase)).await?;
+    }
+
+    let state = app_handle.state::<Mutex<BookmarksRegistry>>();
+    let mut registry = state
+        .lock()
+        .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let collection = registry.get_mut(&id)?;
+    if collection.encryption.locked {
+        return Err(Error::BookmarksLocked);
+    }
+    let Some(old_key) = collection.encryption.key.take() else {
+        return Ok(());
+    };
+    app_handle.save_collection(&id, collection)?;
+    app_handle.rekey_sync_base(&id, Some(&old_key), None)
+}
+
+/// Id of the active collection, which should not be locked
+fn active_unlocked_id<R: tauri::Runtime>(
+    app_handle: &tauri::AppHandle<R>,
+) -> Result<String, Error> {
+    let state = app_handle.state::<Mutex<BookmarksRegistry>>();
+    let registry = state
+        .lock()
+        .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    if registry.active()?.encryption.locked {
+        return Err(Error::BookmarksLocked);
+    }
+    Ok(registry.active_id().to_string())
+}
+
+/// Run the key derivation on a blocking thread, it takes about a second.
+/// The registry should not be locked meanwhile.
+async fn derive_key_blocking<T, F>(f: F) -> Result<T, Error>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, parus_bookmark::Error> + Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(f)
+        .await
+        .map_err(|e| Error::Other(e.to_string()))?
+        .map_err(Error::from)
+}
+
+#[tauri::command]
+pub fn list_collections(
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
+) -> Result<CollectionList, Error> {
+    let registry = state
+        .lock()
+        .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    Ok(CollectionList {
+        active: registry.active_id().to_string(),
+        collections: registry.infos().to_vec(),
+    })
+}
+
+/// Create an empty collection. It is not switched to.
+#[tauri::command]
+pub fn create_collection<R: tauri::Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
+    name: String,
+) -> Result<CollectionInfo, Error> {
+    let mut registry = state
+        .lock()
+        .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let info = registry.create(&name)?;
+    app_handle.save_collection(&info.id, registry.get(&info.id)?)?;
+    app_handle.save_collection_infos(registry.infos())?;
+
+    Ok(info)
+}
+
+#[tauri::command]
+pub fn rename_collection<R: tauri::Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
+    id: String,
+    name: String,
+) -> Result<CollectionInfo, Error> {
+    let mut registry = state
+        .lock()
+        .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    let info = registry.rename(&id, &name)?;
+    app_handle.save_collection_infos(registry.infos())?;
+
+    Ok(info)
+}
+
+/// Delete a collection and its files. Switch to another collection before deleting the active one.
+#[tauri::command]
+pub fn delete_collection<R: tauri::Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
+    id: String,
+) -> Result<(), Error> {
+    let mut registry = state
+        .lock()
+        .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    registry.remove(&id)?;
+    app_handle.save_collection_infos(registry.infos())?;
+    app_handle.remove_collection_files(&id)
+}
+
+/// Switch the active collection, loading it from its file for the first time.
+/// The previous collection is saved. Returns the root of the new collection.
+#[tauri::command]
+pub fn switch_collection<R: tauri::Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    state: tauri::State<'_, Mutex<BookmarksRegistry>>,
+    id: String,
+) -> Result<NestedBookmark, Error> {
+    let mut registry = state
+        .lock()
+        .map_err(|_| Error::Mutex("can't get bookmarks".to_string()))?;
+    if !registry.contains(&id) {
+        return Err(Error::CollectionNotFound(id));
+    }
+    app_handle.save_collection(registry.active_id(), registry.active()?)?;
+    if !registry.is_loaded(&id) {
+        let collection = app_handle.load_collection(&id);
+        registry.insert_loaded(&id, collection)?;
+    }
+    registry.set_active(&id)?;
+    app_handle.save_active_collection_id(&id)?;
+
+    Ok(registry.active_bookmarks()?.to_nested_bookmark(1)?)
+}
@@ -1,23 +1,100 @@
 mod commands;
 mod models;
+mod registry;
 
-use std::{fs, sync::Mutex};
+use std::{fs, path::PathBuf, sync::Mutex};
 
-use tauri::Manager;
+use tauri::{Listener, Manager};
 
-use parus_bookmark::bookmarks::Bookmarks;
-use parus_common::{AppHandlePathExt, Error};
+use parus_bookmark::{bookmarks::Bookmarks, crypto::EncryptionKey, scheme::SchemePolicy};
+use parus_common::{
+    constants::DEFAULT_BOOKMARK_COLLECTION_ID, utils::deserialize_from_file_or_default, AppEvent,
+    AppHandlePathExt, Error,
+};
+use tauri_plugin_app_settings::AppSettings;
+use tauri_plugin_user_settings::UserSettings;
+
+use models::{CollectionInfo, EncryptionState, SyncSettings};
+use registry::{BookmarksRegistry, Collection};
 
 const PLUGIN_NAME: &str = "bookmarks";
 
+/// Files of a bookmark collection
+struct CollectionPaths {
+    bookmarks: PathBuf,
+    sync: PathBuf,
+    sync_base: PathBuf,
+}
+
 trait AppHandleExt {
-    fn load_bookmarks(&self) -> Bookmarks;
+    fn collection_paths(&self, id: &str) -> CollectionPaths;
+    fn load_collection(&self, id: &str) -> Collection;
+    fn load_bookmarks(&self, id: &str) -> (Bookmarks, EncryptionState);
+    fn save_collection(&self, id: &str, collection: &Collection) -> Result<(), Error>;
     fn save_bookmarks(&self) -> Result<(), Error>;
+    fn remove_collection_files(&self, id: &str) -> Result<(), Error>;
+    fn load_collection_infos(&self) -> Vec<CollectionInfo>;
+    fn save_collection_infos(&self, infos: &[CollectionInfo]) -> Result<(), Error>;
+    fn load_active_collection_id(&self) -> String;
+    fn save_active_collection_id(&self, id: &str) -> Result<(), Error>;
+    fn record_visit(&self, url: &str) -> Result<(), Error>;
+    fn mark_read(&self, url: &str) -> Result<(), Error>;
+    fn load_sync_settings(&self, id: &str) -> SyncSettings;
+    fn save_sync_settings(&self, id: &str, settings: &SyncSettings) -> Result<(), Error>;
+    fn sync_password_entry(&self, id: &str) -> Result<keyring::Entry, Error>;
+    fn load_sync_password(&self, id: &str) -> Option<String>;
+    fn save_sync_password(&self, id: &str, password: Option<&str>) -> Result<(), Error>;
+    fn update_sync_settings<F: FnOnce(&mut SyncSettings)>(
+        &self,
+        id: &str,
+        f: F,
+    ) -> Result<(), Error>;
+    fn load_sync_base(&self, id: &str, key: Option<&EncryptionKey>) -> Option<Bookmarks>;
+    fn save_sync_base(
+        &self,
+        id: &str,
+        bookmarks: &Bookmarks,
+        key: Option<&EncryptionKey>,
+    ) -> Result<(), Error>;
+    fn rekey_sync_base(
+        &self,
+        id: &str,
+        old_key: Option<&EncryptionKey>,
+        new_key: Option<&EncryptionKey>,
+    ) -> Result<(), Error>;
+    fn remove_sync_base(&self, id: &str) -> Result<(), Error>;
 }
 
 impl<R: tauri::Runtime> AppHandleExt for tauri::AppHandle<R> {
-    fn load_bookmarks(&self) -> Bookmarks {
-        let path = self.bookmarks_path();
+    /// The default collection keeps the original file names,
+    /// other collections are stored in the collections dir by id.
+    fn collection_paths(&self, id: &str) -> CollectionPaths {
+        if id == DEFAULT_BOOKMARK_COLLECTION_ID {
+            return CollectionPaths {
+                bookmarks: self.bookmarks_path(),
+                sync: self.bookmarks_sync_path(),
+                sync_base: self.bookmarks_sync_base_path(),
+            };
+        }
+        let dir = self.get_bookmark_collections_dir();
+        CollectionPaths {
+            bookmarks: dir.join(format!("{id}.json")),
+            sync: dir.join(format!("{id}.sync.json")),
+            sync_base: dir.join(format!("{id}.sync_base.json")),
+        }
+    }
+
+    fn load_collection(&self, id: &str) -> Collection {
+        let (bookmarks, encryption) = self.load_bookmarks(id);
+        Collection {
+            bookmarks,
+            encryption,
+            sync: self.load_sync_settings(id),
+        }
+    }
+
+    fn load_bookmarks(&self, id: &str) -> (Bookmarks, EncryptionState) {
+        let path = self.collection_paths(id).bookmarks;
         if path.exists() {
             log::info!("Bookmarks file found: {:?}", path);
             // create backup before loading
@@ -30,11 +107,23 @@ impl<R: tauri::Runtime> AppHandleExt for tauri::AppHandle<R> {
                     log::warn!("Failed to create backup: {:?}", e);
                 }
             }
+            // encrypted bookmarks are loaded by unlock_bookmarks command
+            if Bookmarks::is_encrypted_file(&path).unwrap_or(false) {
+                log::info!("Bookmarks file is encrypted, waiting for unlock");
+                return (Bookmarks::default(), EncryptionState::locked());
+            }
             match Bookmarks::load_from_file(path) {
-                Ok(bookmarks) => bookmarks,
+                Ok(mut bookmarks) => {
+                    if repair_bookmarks(&mut bookmarks) {
+                        (bookmarks, EncryptionState::default())
+                    } else {
+                        log::warn!("Load default bookmarks: bookmarks can't be repaired");
+                        (Bookmarks::default(), EncryptionState::default())
+                    }
+                }
                 Err(e) => {
                     log::warn!("Load default bookmarks: {:?}", e);
-                    Bookmarks::default()
+                    (Bookmarks::default(), EncryptionState::default())
                 }
             }
         } else {
@@ -42,21 +131,319 @@ impl<R: tauri::Runtime> AppHandleExt for tauri::AppHandle<R> {
                 "Bookmarks file not found, load default bookmarks: {:?}",
                 path
             );
-            Bookmarks::default()
+            (Bookmarks::default(), EncryptionState::default())
+        }
+    }
+
+    fn save_collection(&self, id: &str, collection: &Collection) -> Result<(), Error> {
+        // never overwrite the encrypted file with the placeholder bookmarks
+        if collection.encryption.locked {
+            log::warn!("Bookmarks are locked, skip saving: {}", id);
+            return Ok(());
         }
+
+        let path = self.collection_paths(id).bookmarks;
+        match &collection.encryption.key {
+            Some(key) => collection.bookmarks.save_to_encrypted_file(path, key)?,
+            None => collection.bookmarks.save_to_file(path)?,
+        }
+
+        Ok(())
     }
 
+    /// Save all loaded collections and the list of collections
     fn save_bookmarks(&self) -> Result<(), Error> {
-        let path = self.bookmarks_path();
-        let state = self.state::<Mutex<Bookmarks>>();
-        let bookmarks = state
+        let state = self.state::<Mutex<BookmarksRegistry>>();
+        let registry = state
+            .lock()
+            .map_err(|_| Error::Mutex("can't lock bookmarks".to_string()))?;
+
+        for (id, collection) in registry.loaded() {
+            if let Err(e) = self.save_collection(id, collection) {
+                log::warn!("Failed to save collection {}: {:?}", id, e);
+            }
+        }
+        self.save_collection_infos(registry.infos())
+    }
+
+    fn remove_collection_files(&self, id: &str) -> Result<(), Error> {
+        let paths = self.collection_paths(id);
+        let backup_path = paths.bookmarks.with_extension("bak");
+        for path in [paths.bookmarks, backup_path, paths.sync, paths.sync_base] {
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+        }
+        if let Err(e) = self.save_sync_password(id, None) {
+            log::warn!("Failed to remove sync password of {}: {:?}", id, e);
+        }
+        Ok(())
+    }
+
+    fn load_collection_infos(&self) -> Vec<CollectionInfo> {
+        let path = self.bookmark_collections_path();
+        if !path.exists() {
+            return Vec::new();
+        }
+        deserialize_from_file_or_default(path)
+    }
+
+    fn save_collection_infos(&self, infos: &[CollectionInfo]) -> Result<(), Error> {
+        let path = self.bookmark_collections_path();
+        let file = fs::File::create(path)?;
+        serde_json::to_writer_pretty(file, infos)?;
+        Ok(())
+    }
+
+    /// Active collection remembered in app settings
+    fn load_active_collection_id(&self) -> String {
+        self.try_state::<Mutex<AppSettings>>()
+            .and_then(|state| {
+                state
+                    .lock()
+                    .ok()
+                    .map(|settings| settings.active_bookmark_collection.clone())
+            })
+            .unwrap_or_else(|| DEFAULT_BOOKMARK_COLLECTION_ID.to_string())
+    }
+
+    /// App settings are saved by the app-settings plugin on exit
+    fn save_active_collection_id(&self, id: &str) -> Result<(), Error> {
+        if let Some(state) = self.try_state::<Mutex<AppSettings>>() {
+            let mut settings = state
+                .lock()
+                .map_err(|_| Error::Mutex("can't get settings".to_string()))?;
+            settings.active_bookmark_collection = id.to_string();
+        }
+        Ok(())
+    }
+
+    fn record_visit(&self, url: &str) -> Result<(), Error> {
+        let state = self.state::<Mutex<BookmarksRegistry>>();
+        let mut registry = state
+            .lock()
+            .map_err(|_| Error::Mutex("can't lock bookmarks".to_string()))?;
+
+        // visits are not recorded until the bookmarks are unlocked
+        let bookmarks = match registry.active_bookmarks_mut() {
+            Err(Error::BookmarksLocked) => return Ok(()),
+            bookmarks => bookmarks?,
+        };
+        let updated = bookmarks.record_visit(url)?;
+        if !updated.is_empty() {
+            log::debug!("Visit recorded: {} {:?}", url, updated);
+        }
+
+        Ok(())
+    }
+
+    fn mark_read(&self, url: &str) -> Result<(), Error> {
+        let state = self.state::<Mutex<BookmarksRegistry>>();
+        let mut registry = state
             .lock()
             .map_err(|_| Error::Mutex("can't lock bookmarks".to_string()))?;
 
-        bookmarks.save_to_file(path)?;
+        let bookmarks = match registry.active_bookmarks_mut() {
+            Err(Error::BookmarksLocked) => return Ok(()),
+            bookmarks => bookmarks?,
+        };
+        let updated = bookmarks.mark_read_by_url(url)?;
+        if !updated.is_empty() {
+            log::debug!("Marked as read: {} {:?}", url, updated);
+        }
 
         Ok(())
     }
+
+    fn load_sync_settings(&self, id: &str) -> SyncSettings {
+        let path = self.collection_paths(id).sync;
+        if !path.exists() {
+            return SyncSettings::default();
+        }
+        let mut settings: SyncSettings = deserialize_from_file_or_default(path);
+        match settings.password().map(str::to_string) {
+            // older versions saved the password in the file
+            Some(password) => match self.save_sync_password(id, Some(&password)) {
+                Ok(()) => {
+                    if let Err(e) = self.save_sync_settings(id, &settings) {
+                        log::warn!("Failed to remove sync password from the file: {:?}", e);
+                    }
+                }
+                Err(e) => log::warn!("Failed to move sync password: {:?}", e),
+            },
+            None => {
+                if let Some(password) = settings.password_mut() {
+                    *password = self.load_sync_password(id);
+                }
+            }
+        }
+        settings
+    }
+
+    /// The password is saved by [`AppHandleExt::save_sync_password`], never in the file
+    fn save_sync_settings(&self, id: &str, settings: &SyncSettings) -> Result<(), Error> {
+        let mut settings = settings.clone();
+        if let Some(password) = settings.password_mut() {
+            *password = None;
+        }
+        let path = self.collection_paths(id).sync;
+        let file = fs::File::create(path)?;
+        serde_json::to_writer_pretty(file, &settings)?;
+
+        Ok(())
+    }
+
+    /// Entry of the WebDAV password of the collection in the OS credential store
+    fn sync_password_entry(&self, id: &str) -> Result<keyring::Entry, Error> {
+        keyring::Entry::new(&self.config().identifier, &format!("sync-{id}"))
+            .map_err(credential_error)
+    }
+
+    fn load_sync_password(&self, id: &str) -> Option<String> {
+        let password = self
+            .sync_password_entry(id)
+            .and_then(|entry| match entry.get_password() {
+                Ok(password) => Ok(Some(password)),
+                Err(keyring::Error::NoEntry) => Ok(None),
+                Err(e) => Err(credential_error(e)),
+            });
+        password.unwrap_or_else(|e| {
+            log::warn!("Failed to load sync password of {}: {:?}", id, e);
+            None
+        })
+    }
+
+    /// Save the password in the OS credential store, or remove it if None
+    fn save_sync_password(&self, id: &str, password: Option<&str>) -> Result<(), Error> {
+        let entry = self.sync_password_entry(id)?;
+        let result = match password {
+            Some(password) => entry.set_password(password),
+            None => entry.delete_credential(),
+        };
+        match result {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(credential_error(e)),
+        }
+    }
+
+    fn update_sync_settings<F: FnOnce(&mut SyncSettings)>(
+        &self,
+        id: &str,
+        f: F,
+    ) -> Result<(), Error> {
+        let state = self.state::<Mutex<BookmarksRegistry>>();
+        let mut registry = state
+            .lock()
+            .map_err(|_| Error::Mutex("can't lock bookmarks".to_string()))?;
+        let settings = &mut registry.get_mut(id)?.sync;
+        f(settings);
+        self.save_sync_settings(id, settings)
+    }
+
+    /// The sync base of an encrypted collection is encrypted with the same key
+    fn load_sync_base(&self, id: &str, key: Option<&EncryptionKey>) -> Option<Bookmarks> {
+        let path = self.collection_paths(id).sync_base;
+        if !path.exists() {
+            return None;
+        }
+        let result = match key {
+            // older versions saved the sync base as plain JSON
+            Some(key) if Bookmarks::is_encrypted_file(&path).unwrap_or(false) => {
+                Bookmarks::load_from_encrypted_file_with_key(path, key)
+            }
+            _ => Bookmarks::load_from_file(path),
+        };
+        match result {
+            Ok(bookmarks) => Some(bookmarks),
+            Err(e) => {
+                log::warn!("Failed to load sync base, sync as first time: {:?}", e);
+                None
+            }
+        }
+    }
+
+    fn save_sync_base(
+        &self,
+        id: &str,
+        bookmarks: &Bookmarks,
+        key: Option<&EncryptionKey>,
+    ) -> Result<(), Error> {
+        let path = self.collection_paths(id).sync_base;
+        match key {
+            Some(key) => bookmarks.save_to_encrypted_file(path, key)?,
+            None => bookmarks.save_to_file(path)?,
+        }
+        Ok(())
+    }
+
+    /// Save the sync base again when the key of the collection is changed
+    fn rekey_sync_base(
+        &self,
+        id: &str,
+        old_key: Option<&EncryptionKey>,
+        new_key: Option<&EncryptionKey>,
+    ) -> Result<(), Error> {
+        match self.load_sync_base(id, old_key) {
+            Some(base) => self.save_sync_base(id, &base, new_key),
+            None => Ok(()),
+        }
+    }
+
+    fn remove_sync_base(&self, id: &str) -> Result<(), Error> {
+        let path = self.collection_paths(id).sync_base;
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+fn credential_error(e: keyring::Error) -> Error {
+    Error::Other(format!("failed to access the credential store: {e}"))
+}
+
+/// Repair problems of loaded bookmarks, which are saved on exit.
+/// Returns false if the bookmarks are still broken, e.g. without root.
+fn repair_bookmarks(bookmarks: &mut Bookmarks) -> bool {
+    match bookmarks.repair() {
+        Ok(problems) => {
+            for problem in problems {
+                log::warn!("Bookmarks repaired: {}", problem);
+            }
+        }
+        Err(e) => log::warn!("Failed to repair bookmarks: {:?}", e),
+    }
+    bookmarks.validate().is_empty()
+}
+
+/// Extensions to [`tauri::AppHandle`] to access bookmarks from other crates.
+pub trait BookmarksExt {
+    /// Resolve an input like `rs serde` into a URL using the bookmark keyword.
+    fn resolve_keyword(&self, input: &str) -> Result<Option<String>, Error>;
+    /// URL schemes allowed by the user settings
+    fn scheme_policy(&self) -> SchemePolicy;
+}
+
+impl<R: tauri::Runtime> BookmarksExt for tauri::AppHandle<R> {
+    fn resolve_keyword(&self, input: &str) -> Result<Option<String>, Error> {
+        let state = self.state::<Mutex<BookmarksRegistry>>();
+        let registry = state
+            .lock()
+            .map_err(|_| Error::Mutex("can't lock bookmarks".to_string()))?;
+
+        Ok(registry.active_bookmarks()?.resolve_keyword(input))
+    }
+
+    fn scheme_policy(&self) -> SchemePolicy {
+        self.try_state::<Mutex<UserSettings>>()
+            .and_then(|state| {
+                state
+                    .lock()
+                    .ok()
+                    .map(|settings| SchemePolicy::new(&settings.allowed_url_schemes))
+            })
+            .unwrap_or_default()
+    }
 }
 
 /// Initializes the plugin.
@@ -77,10 +464,76 @@ pub fn init<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
             commands::prepend_to_child,
             commands::set_is_open,
             commands::toggle_is_open,
+            commands::search_bookmarks,
+            commands::sort_by_frecency,
+            commands::add_smart_folder,
+            commands::update_smart_folder_query,
+            commands::set_bookmark_tags,
+            commands::set_bookmark_keyword,
+            commands::add_to_reading_list,
+            commands::set_is_read,
+            commands::get_reading_list,
+            commands::get_sync_config,
+            commands::set_sync_config,
+            commands::get_sync_status,
+            commands::sync_bookmarks,
+            commands::get_encryption_status,
+            commands::unlock_bookmarks,
+            commands::enable_encryption,
+            commands::disable_encryption,
+            commands::list_collections,
+            commands::create_collection,
+            commands::rename_collection,
+            commands::delete_collection,
+            commands::switch_collection,
+            commands::group_bookmarks_by_domain,
+            commands::organize_by_domain,
+            commands::get_bookmark_stats,
+            commands::get_favicon_urls,
         ])
         .setup(|app, _api| {
-            let bookmarks = app.load_bookmarks();
-            app.manage(Mutex::new(bookmarks));
+            let infos = app.load_collection_infos();
+            let mut active_id = app.load_active_collection_id();
+            if active_id != DEFAULT_BOOKMARK_COLLECTION_ID
+                && !infos.iter().any(|info| info.id == active_id)
+            {
+                log::warn!("Collection not found, open default: {}", active_id);
+                active_id = DEFAULT_BOOKMARK_COLLECTION_ID.to_string();
+            }
+            let active = app.load_collection(&active_id);
+            app.manage(Mutex::new(BookmarksRegistry::new(infos, active_id, active)));
+
+            // record visits when the external webview navigates to a bookmarked URL.
+            // url-observer.js sends the same URL on pushState/replaceState as well,
+            // so consecutive duplicates are skipped.
+            let app_handle = app.clone();
+            let last_url = Mutex::new(String::new());
+            app.listen_any(AppEvent::ExternalUrlChanged.as_ref(), move |event| {
+                let Ok(url) = serde_json::from_str::<String>(event.payload()) else {
+                    return;
+                };
+                if let Ok(mut last_url) = last_url.lock() {
+                    if *last_url == url {
+                        return;
+                    }
+                    last_url.clone_from(&url);
+                }
+                if let Err(e) = app_handle.record_visit(&url) {
+                    log::warn!("Failed to record visit: {:?}", e);
+                }
+            });
+
+            // mark reading list items as read when the page is scrolled to the end
+            let app_handle = app.clone();
+            app.listen_any(AppEvent::ExternalPageRead.as_ref(), move |event| {
+                let Ok(url) = serde_json::from_str::<String>(event.payload()) else {
+                    return;
+                };
+                if let Err(e) = app_handle.mark_read(&url) {
+                    log::warn!("Failed to mark as read: {:?}", e);
+                }
+            });
+
             Ok(())
         })
         .on_event(|app_handle, event| match event {
@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+
+use parus_bookmark::{crypto::EncryptionKey, diff::DiffOperation, merge::MergeConflict};
+use parus_sync::{BackendConfig, SyncConfig};
+
+/// Sync configuration and the result of the last sync
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct SyncSettings {
+    pub config: Option<SyncConfig>,
+    /// Unix timestamp in milliseconds of the last successful sync
+    pub last_synced_at: Option<u64>,
+    /// Error message of the last failed sync
+    pub last_error: Option<String>,
+}
+
+impl SyncSettings {
+    /// WebDAV password, which is kept in the OS credential store instead of the settings file
+    pub fn password(&self) -> Option<&str> {
+        match &self.config {
+            Some(SyncConfig {
+                backend: BackendConfig::WebDav { password, .. },
+                ..
+            }) => password.as_deref(),
+            _ => None,
+        }
+    }
+
+    pub fn password_mut(&mut self) -> Option<&mut Option<String>> {
+        match &mut self.config {
+            Some(SyncConfig {
+                backend: BackendConfig::WebDav { password, .. },
+                ..
+            }) => Some(password),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct SyncStatus {
+    pub configured: bool,
+    pub last_synced_at: Option<u64>,
+    pub last_error: Option<String>,
+    /// Local changes since the last sync
+    pub pending_changes: Vec<DiffOperation>,
+}
+
+#[derive(Serialize)]
+pub struct SyncReport {
+    pub synced_at: u64,
+    pub uploaded: bool,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// Encryption state of the bookmarks file
+#[derive(Default)]
+pub struct EncryptionState {
+    /// Key of the unlocked file, None if the file is not encrypted
+    pub key: Option<EncryptionKey>,
+    /// The file is encrypted and not unlocked yet.
+    /// Placeholder bookmarks are loaded and must not be saved or synced.
+    pub locked: bool,
+}
+
+impl EncryptionState {
+    pub fn locked() -> Self {
+        Self {
+            key: None,
+            locked: true,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct EncryptionStatus {
+    pub encrypted: bool,
+    pub locked: bool,
+}
+
+/// Name of a bookmark collection, e.g. Work or Personal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CollectionInfo {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Serialize)]
+pub struct CollectionList {
+    /// Id of the active collection
+    pub active: String,
+    pub collections: Vec<CollectionInfo>,
+}
@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+
+use parus_bookmark::bookmarks::Bookmarks;
+use parus_common::{constants::DEFAULT_BOOKMARK_COLLECTION_ID, Error};
+
+use crate::models::{CollectionInfo, EncryptionState, SyncSettings};
+
+/// Bookmarks of a collection with its own encryption and sync state
+#[derive(Default)]
+pub struct Collection {
+    pub bookmarks: Bookmarks,
+    pub encryption: EncryptionState,
+    pub sync: SyncSettings,
+}
+
+/// Named bookmark collections. Each collection is stored in its own file
+/// and loaded when it is opened for the first time.
+pub struct BookmarksRegistry {
+    infos: Vec<CollectionInfo>,
+    loaded: HashMap<String, Collection>,
+    active_id: String,
+}
+
+impl BookmarksRegistry {
+    /// Create a registry with the active collection loaded.
+    /// The default collection is always listed first.
+    pub fn new(mut infos: Vec<CollectionInfo>, active_id: String, active: Collection) -> Self {
+        if !infos
+            .iter()
+            .any(|info| info.id == DEFAULT_BOOKMARK_COLLECTION_ID)
+        {
+            infos.insert(
+                0,
+                CollectionInfo {
+                    id: DEFAULT_BOOKMARK_COLLECTION_ID.to_string(),
+                    name: "Default".to_string(),
+                },
+            );
+        }
+        let mut loaded = HashMap::new();
+        loaded.insert(active_id.clone(), active);
+
+        Self {
+            infos,
+            loaded,
+            active_id,
+        }
+    }
+
+    pub fn infos(&self) -> &[CollectionInfo] {
+        &self.infos
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.infos.iter().any(|info| info.id == id)
+    }
+
+    pub fn is_loaded(&self, id: &str) -> bool {
+        self.loaded.contains_key(id)
+    }
+
+    pub fn active_id(&self) -> &str {
+        &self.active_id
+    }
+
+    pub fn active(&self) -> Result<&Collection, Error> {
+        self.loaded
+            .get(&self.active_id)
+            .ok_or_else(|| Error::CollectionNotFound(self.active_id.clone()))
+    }
+
+    pub fn active_mut(&mut self) -> Result<&mut Collection, Error> {
+        self.loaded
+            .get_mut(&self.active_id)
+            .ok_or_else(|| Error::CollectionNotFound(self.active_id.clone()))
+    }
+
+    pub fn active_bookmarks(&self) -> Result<&Bookmarks, Error> {
+        Ok(&self.active()?.bookmarks)
+    }
+
+    /// Locked bookmarks are a placeholder which is never saved, so they can't be edited
+    pub fn active_bookmarks_mut(&mut self) -> Result<&mut Bookmarks, Error> {
+        let collection = self.active_mut()?;
+        if collection.encryption.locked {
+            return Err(Error::BookmarksLocked);
+        }
+        Ok(&mut collection.bookmarks)
+    }
+
+    pub fn get(&self, id: &str) -> Result<&Collection, Error> {
+        self.loaded
+            .get(id)
+            .ok_or_else(|| Error::CollectionNotFound(id.to_string()))
+    }
+
+    pub fn get_mut(&mut self, id: &str) -> Result<&mut Collection, Error> {
+        self.loaded
+            .get_mut(id)
+            .ok_or_else(|| Error::CollectionNotFound(id.to_string()))
+    }
+
+    /// Loaded collections, which need to be saved on exit
+    pub fn loaded(&self) -> impl Iterator<Item = (&String, &Collection)> {
+        self.loaded.iter()
+    }
+
+    /// Keep a collection loaded from its file
+    pub fn insert_loaded(&mut self, id: &str, collection: Collection) -> Result<(), Error> {
+        if !self.contains(id) {
+            return Err(Error::CollectionNotFound(id.to_string()));
+        }
+        self.loaded.insert(id.to_string(), collection);
+        Ok(())
+    }
+
+    /// Switch to a loaded collection
+    pub fn set_active(&mut self, id: &str) -> Result<(), Error> {
+        if !self.is_loaded(id) {
+            return Err(Error::CollectionNotFound(id.to_string()));
+        }
+        self.active_id = id.to_string();
+        Ok(())
+    }
+
+    /// Add an empty collection
+    pub fn create(&mut self, name: &str) -> Result<CollectionInfo, Error> {
+        let name = self.validate_name(name, None)?;
+        let info = CollectionInfo {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+        };
+        self.infos.push(info.clone());
+        self.loaded.insert(info.id.clone(), Collection::default());
+        Ok(info)
+    }
+
+    pub fn rename(&mut self, id: &str, name: &str) -> Result<CollectionInfo, Error> {
+        let name = self.validate_name(name, Some(id))?;
+        let info = self
+            .infos
+            .iter_mut()
+            .find(|info| info.id == id)
+            .ok_or_else(|| Error::CollectionNotFound(id.to_string()))?;
+        info.name = name;
+        Ok(info.clone())
+    }
+
+    /// Remove a collection. The default and the active collection can't be removed.
+    pub fn remove(&mut self, id: &str) -> Result<CollectionInfo, Error> {
+        if id == DEFAULT_BOOKMARK_COLLECTION_ID {
+            return Err(Error::InvalidCollection(
+                "the default collection can't be deleted".to_string(),
+            ));
+        }
+        if id == self.active_id {
+            return Err(Error::InvalidCollection(
+                "the active collection can't be deleted".to_string(),
+            ));
+        }
+        let position = self
+            .infos
+            .iter()
+            .position(|info| info.id == id)
+            .ok_or_else(|| Error::CollectionNotFound(id.to_string()))?;
+        self.loaded.remove(id);
+        Ok(self.infos.remove(position))
+    }
+
+    /// Trimmed name, which should not be empty or used by another collection
+    fn validate_name(&self, name: &str, id: Option<&str>) -> Result<String, Error> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(Error::InvalidCollection(
+                "name should not be empty".to_string(),
+            ));
+        }
+        let duplicated = self.infos.iter().any(|info| {
+            Some(info.id.as_str()) != id && info.name.to_lowercase() == name.to_lowercase()
+        });
+        if duplicated {
+            return Err(Error::InvalidCollection(format!(
+                "name is already used: {name}"
+            )));
+        }
+        Ok(name.to_string())
+    }
+}
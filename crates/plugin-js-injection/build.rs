@@ -4,6 +4,7 @@ const COMMANDS: &[&str] = &[
     "get_external_webview_title",
     "history_back",
     "history_forward",
+    "send_page_read",
 ];
 
 fn main() {
@@ -36,6 +36,12 @@ pub fn send_page_url<R: tauri::Runtime>(app_handle: tauri::AppHandle<R>, url: St
     let _ = emit_to_app_webview(&app_handle, AppEvent::ExternalUrlChanged, url);
 }
 
+/// Sent when the page in the external webview is scrolled to the end
+#[tauri::command]
+pub fn send_page_read<R: tauri::Runtime>(app_handle: tauri::AppHandle<R>, url: String) {
+    let _ = emit_to_app_webview(&app_handle, AppEvent::ExternalPageRead, url);
+}
+
 /// Get the title of the external webview by evaluating a JavaScript
 /// script sending title to send_page_title and it emits to app webview
 #[tauri::command]
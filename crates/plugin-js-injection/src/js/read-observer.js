@@ -0,0 +1,51 @@
+(() => {
+  console.info("Run: read-observer");
+
+  // the page is considered read when scrolled to this ratio of the document height
+  const READ_THRESHOLD = 0.9;
+  const URL_CHANGE_EVENT = "urlchange";
+  const POP_STATE_EVENT = "popstate";
+  // SPA sites render the new page after the URL changes
+  const URL_CHANGE_CHECK_DELAY = 1000;
+
+  let isSent = false;
+
+  const sendReadToTauri = (url) => {
+    if (!window.__TAURI__?.core?.invoke) {
+      return;
+    }
+    window.__TAURI__.core.invoke("plugin:js-injection|send_page_read", { url });
+  };
+
+  // also called without scrolling, pages shorter than the viewport never scroll
+  const handleScroll = () => {
+    if (isSent) {
+      return;
+    }
+    const scrolled = window.scrollY + window.innerHeight;
+    const height = document.documentElement.scrollHeight;
+    if (height > 0 && scrolled / height >= READ_THRESHOLD) {
+      isSent = true;
+      sendReadToTauri(window.location.href);
+    }
+  };
+
+  // reset when the URL changes in SPA sites
+  const handleUrlChange = () => {
+    isSent = false;
+    setTimeout(handleScroll, URL_CHANGE_CHECK_DELAY);
+  };
+
+  try {
+    window.addEventListener("scroll", handleScroll, { passive: true });
+    window.addEventListener(URL_CHANGE_EVENT, handleUrlChange);
+    window.addEventListener(POP_STATE_EVENT, handleUrlChange);
+    if (document.readyState === "complete") {
+      handleScroll();
+    } else {
+      window.addEventListener("load", handleScroll, { once: true });
+    }
+  } catch (error) {
+    console.error("Error initializing read observer:", error);
+  }
+})();
@@ -9,6 +9,7 @@ static JS_SCRIPTS: Lazy<String> = Lazy::new(|| {
     let mut script = String::new();
     script.push_str(include_str!("js/title-observer.js"));
     script.push_str(include_str!("js/url-observer.js"));
+    script.push_str(include_str!("js/read-observer.js"));
     script.push_str(include_str!("js/target-remover.js"));
     script.push_str(include_str!("js/mouse-gesture.js"));
     script
@@ -22,6 +23,7 @@ pub fn init<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
             commands::get_external_webview_title,
             commands::history_back,
             commands::history_forward,
+            commands::send_page_read,
         ])
         .on_page_load(|webview, payload| {
             if webview.label() == EXTERNAL_WEBVIEW_LABEL {
@@ -13,6 +13,10 @@ pub struct UserSettings {
 
     #[serde(default = "default_sidebar_font_size")]
     pub sidebar_font_size: f32,
+
+    /// URL schemes which can be bookmarked, only http and https unless e.g. `javascript` is opted in
+    #[serde(default = "default_allowed_url_schemes")]
+    pub allowed_url_schemes: Vec<String>,
 }
 
 impl Default for UserSettings {
@@ -22,6 +26,7 @@ impl Default for UserSettings {
             theme: default_theme(),
             home_page_url: default_home_page_url(),
             sidebar_font_size: default_sidebar_font_size(),
+            allowed_url_schemes: default_allowed_url_schemes(),
         }
     }
 }
@@ -41,3 +46,7 @@ fn default_theme() -> String {
 fn default_sidebar_font_size() -> f32 {
     13.0
 }
+
+fn default_allowed_url_schemes() -> Vec<String> {
+    ["http", "https"].into_iter().map(String::from).collect()
+}
@@ -0,0 +1,100 @@
+use std::{future::Future, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use crate::{directory::DirectoryBackend, error::Error, webdav::WebDavBackend};
+
+/// Bookmarks file stored in a backend
+#[derive(Debug, Clone)]
+pub struct RemoteFile {
+    pub data: Vec<u8>,
+    /// Opaque revision used to detect changes made by other machines
+    pub revision: String,
+}
+
+/// Storage of the shared bookmarks file
+pub trait SyncBackend {
+    /// Download the bookmarks file, returns None if it does not exist yet
+    fn download(&self) -> impl Future<Output = Result<Option<RemoteFile>, Error>> + Send;
+
+    /// Upload the bookmarks file and return the new revision.
+    /// Fails with [`Error::RemoteChanged`] if the current revision is not `expected`,
+    /// `None` means the file must not exist yet.
+    fn upload(
+        &self,
+        data: Vec<u8>,
+        expected: Option<String>,
+    ) -> impl Future<Output = Result<String, Error>> + Send;
+}
+
+/// Prefix of the revisions computed from the content
+const CONTENT_REVISION_PREFIX: &str = "sha256:";
+
+/// Revision computed from the content for backends without their own revisions.
+/// SHA-256 gives the same revision on every machine and Rust version.
+pub(crate) fn content_revision(data: &[u8]) -> String {
+    format!("{CONTENT_REVISION_PREFIX}{:x}", Sha256::digest(data))
+}
+
+/// Whether the revision was computed by [`content_revision`] rather than given by the server
+pub(crate) fn is_content_revision(revision: &str) -> bool {
+    revision.starts_with(CONTENT_REVISION_PREFIX)
+}
+
+/// Serializable backend configuration
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum BackendConfig {
+    /// A directory shared by a file sync service or a network drive
+    Directory { path: PathBuf },
+    /// A file on a WebDAV server
+    WebDav {
+        url: String,
+        username: Option<String>,
+        password: Option<String>,
+    },
+}
+
+impl BackendConfig {
+    pub fn build(&self) -> Result<Backend, Error> {
+        let backend = match self {
+            Self::Directory { path } => Backend::Directory(DirectoryBackend::new(path)),
+            Self::WebDav {
+                url,
+                username,
+                password,
+            } => {
+                let mut backend = WebDavBackend::new(Url::parse(url)?);
+                if let Some(username) = username {
+                    backend = backend.with_credentials(username, password.as_deref());
+                }
+                Backend::WebDav(backend)
+            }
+        };
+        Ok(backend)
+    }
+}
+
+/// A backend built from [`BackendConfig`]
+pub enum Backend {
+    Directory(DirectoryBackend),
+    WebDav(WebDavBackend),
+}
+
+impl SyncBackend for Backend {
+    async fn download(&self) -> Result<Option<RemoteFile>, Error> {
+        match self {
+            Self::Directory(backend) => backend.download().await,
+            Self::WebDav(backend) => backend.download().await,
+        }
+    }
+
+    async fn upload(&self, data: Vec<u8>, expected: Option<String>) -> Result<String, Error> {
+        match self {
+            Self::Directory(backend) => backend.upload(data, expected).await,
+            Self::WebDav(backend) => backend.upload(data, expected).await,
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    backend::{content_revision, RemoteFile, SyncBackend},
+    error::Error,
+};
+
+/// File name of the shared bookmarks file in the directory
+pub const SYNC_FILE_NAME: &str = "parus-bookmarks.json";
+
+/// Backend storing the bookmarks file in a plain directory,
+/// e.g. a folder synced by Dropbox or a mounted network drive.
+pub struct DirectoryBackend {
+    path: PathBuf,
+}
+
+impl DirectoryBackend {
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        Self {
+            path: dir.as_ref().join(SYNC_FILE_NAME),
+        }
+    }
+
+    /// Path of the shared bookmarks file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl SyncBackend for DirectoryBackend {
+    async fn download(&self) -> Result<Option<RemoteFile>, Error> {
+        match tokio::fs::read(&self.path).await {
+            Ok(data) => {
+                let revision = content_revision(&data);
+                Ok(Some(RemoteFile { data, revision }))
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn upload(&self, data: Vec<u8>, expected: Option<String>) -> Result<String, Error> {
+        let current = self.download().await?.map(|file| file.revision);
+        if current != expected {
+            return Err(Error::RemoteChanged());
+        }
+
+        // write to a temporary file and rename it so other machines never see a partial file
+        let tmp_path = self.path.with_extension("tmp");
+        tokio::fs::write(&tmp_path, &data).await?;
+        tokio::fs::rename(&tmp_path, &self.path).await?;
+
+        Ok(content_revision(&data))
+    }
+}
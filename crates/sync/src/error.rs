@@ -0,0 +1,32 @@
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Io error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("UTF-8 error: {0}")]
+    Utf8(#[from] std::str::Utf8Error),
+
+    #[error("Reqwest error: {0}")]
+    Reqwest(#[from] reqwest::Error),
+
+    #[error("URL error: {0}")]
+    InvalidUrl(#[from] url::ParseError),
+
+    #[error("Bookmark error: {0}")]
+    Bookmark(#[from] parus_bookmark::Error),
+
+    #[error("Unexpected response status: {0}")]
+    UnexpectedStatus(u16),
+
+    #[error("Remote bookmarks were changed during sync")]
+    RemoteChanged(),
+
+    #[error("Sync is not configured")]
+    NotConfigured(),
+
+    #[error("Remote bookmarks are encrypted with another key")]
+    RemoteEncrypted(),
+}
@@ -0,0 +1,9 @@
+pub mod backend;
+pub mod directory;
+pub mod error;
+pub mod sync;
+pub mod webdav;
+
+pub use backend::{Backend, BackendConfig, RemoteFile, SyncBackend};
+pub use error::Error;
+pub use sync::{pending_changes, sync, ConflictStrategy, SyncConfig, SyncOutcome};
@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+
+use parus_bookmark::{
+    crypto::{self, EncryptionKey},
+    diff::{DiffOperation, NodeKey},
+    merge::MergeConflict,
+    Arena, Bookmarks,
+};
+
+use crate::{
+    backend::{BackendConfig, SyncBackend},
+    error::Error,
+};
+
+/// Number of retries when the remote file is changed by another machine during sync
+const MAX_RETRIES: usize = 3;
+
+/// Which side wins when both sides changed the same node
+#[derive(Debug, Default, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum ConflictStrategy {
+    #[default]
+    PreferLocal,
+    PreferRemote,
+}
+
+/// Serializable sync configuration
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct SyncConfig {
+    pub backend: BackendConfig,
+    #[serde(default)]
+    pub strategy: ConflictStrategy,
+}
+
+/// Result of a sync
+pub struct SyncOutcome {
+    /// Merged bookmarks, which should replace the local bookmarks and be kept as the base of the next sync
+    pub bookmarks: Bookmarks,
+    /// Conflicts resolved by the strategy
+    pub conflicts: Vec<MergeConflict>,
+    /// Whether the merged bookmarks were uploaded
+    pub uploaded: bool,
+    /// Revision of the remote file after sync
+    pub revision: String,
+}
+
+/// Local changes since the last sync. `base` is the bookmarks at the last sync, None if never synced.
+pub fn pending_changes(base: Option<&Bookmarks>, local: &Bookmarks) -> Vec<DiffOperation> {
+    let empty = Bookmarks::new(Arena::new());
+    base.unwrap_or(&empty)
+        .diff(local)
+        .into_iter()
+        .filter(|operation| match operation {
+            DiffOperation::Added(node) | DiffOperation::Removed(node) => node.key != NodeKey::Root,
+            _ => true,
+        })
+        .collect()
+}
+
+/// Sync the local bookmarks with the backend.
+/// Local and remote changes since `base` (the bookmarks at the last sync) are merged with a three-way merge,
+/// and the result is uploaded if it differs from the remote file.
+/// The remote file of an encrypted collection is encrypted with its `key` as well.
+pub async fn sync<B: SyncBackend>(
+    backend: &B,
+    local: &Bookmarks,
+    base: Option<&Bookmarks>,
+    strategy: ConflictStrategy,
+    key: Option<&EncryptionKey>,
+) -> Result<SyncOutcome, Error> {
+    let mut retries = 0;
+    loop {
+        match try_sync(backend, local, base, strategy, key).await {
+            Err(Error::RemoteChanged()) if retries < MAX_RETRIES => {
+                retries += 1;
+                log::info!("Remote bookmarks changed during sync, retry: {}", retries);
+            }
+            result => return result,
+        }
+    }
+}
+
+async fn try_sync<B: SyncBackend>(
+    backend: &B,
+    local: &Bookmarks,
+    base: Option<&Bookmarks>,
+    strategy: ConflictStrategy,
+    key: Option<&EncryptionKey>,
+) -> Result<SyncOutcome, Error> {
+    let Some(remote_file) = backend.download().await? else {
+        // first sync, upload local bookmarks as is
+        let revision = backend.upload(encode(local, key)?, None).await?;
+        return Ok(SyncOutcome {
+            bookmarks: Bookmarks::new(local.arena().clone()),
+            conflicts: Vec::new(),
+            uploaded: true,
+            revision,
+        });
+    };
+
+    let remote = decode(&remote_file.data, key)?;
+    let empty = Bookmarks::new(Arena::new());
+    let base = base.unwrap_or(&empty);
+    let result = match strategy {
+        ConflictStrategy::PreferLocal => Bookmarks::three_way_merge(base, local, &remote),
+        ConflictStrategy::PreferRemote => Bookmarks::three_way_merge(base, &remote, local),
+    };
+
+    // a plain remote file is encrypted once the collection is encrypted
+    let reencrypt = key.is_some() && !crypto::is_encrypted(&remote_file.data);
+    if result.bookmarks.is_equivalent(&remote) && !reencrypt {
+        return Ok(SyncOutcome {
+            bookmarks: result.bookmarks,
+            conflicts: result.conflicts,
+            uploaded: false,
+            revision: remote_file.revision,
+        });
+    }
+
+    let revision = backend
+        .upload(encode(&result.bookmarks, key)?, Some(remote_file.revision))
+        .await?;
+    Ok(SyncOutcome {
+        bookmarks: result.bookmarks,
+        conflicts: result.conflicts,
+        uploaded: true,
+        revision,
+    })
+}
+
+/// Content of the remote file, encrypted if the key is given
+fn encode(bookmarks: &Bookmarks, key: Option<&EncryptionKey>) -> Result<Vec<u8>, Error> {
+    let json = bookmarks.to_json()?.into_bytes();
+    match key {
+        Some(key) => Ok(key.encrypt(&json)?),
+        None => Ok(json),
+    }
+}
+
+/// Bookmarks of the remote file. An encrypted file can only be read with the key it was encrypted with.
+fn decode(data: &[u8], key: Option<&EncryptionKey>) -> Result<Bookmarks, Error> {
+    if !crypto::is_encrypted(data) {
+        return Ok(Bookmarks::from_json(std::str::from_utf8(data)?)?);
+    }
+    let json = match key.map(|key| key.decrypt(data)) {
+        Some(Ok(json)) => json,
+        None | Some(Err(parus_bookmark::Error::WrongPassphrase())) => {
+            return Err(Error::RemoteEncrypted())
+        }
+        Some(Err(e)) => return Err(e.into()),
+    };
+    Ok(Bookmarks::from_json(std::str::from_utf8(&json)?)?)
+}
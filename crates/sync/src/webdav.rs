@@ -0,0 +1,96 @@
+use reqwest::{
+    header::{ETAG, IF_MATCH, IF_NONE_MATCH},
+    Client, RequestBuilder, Response, StatusCode,
+};
+use url::Url;
+
+use crate::{
+    backend::{content_revision, is_content_revision, RemoteFile, SyncBackend},
+    error::Error,
+};
+
+/// Backend storing the bookmarks file on a WebDAV server.
+/// Concurrent updates are detected with ETag and conditional PUT requests.
+/// Servers without ETag are checked by downloading the file before uploading, which is not atomic.
+pub struct WebDavBackend {
+    client: Client,
+    /// URL of the bookmarks file, e.g. `https://dav.example.com/parus/bookmarks.json`
+    url: Url,
+    credentials: Option<(String, Option<String>)>,
+}
+
+impl WebDavBackend {
+    pub fn new(url: Url) -> Self {
+        Self {
+            client: Client::new(),
+            url,
+            credentials: None,
+        }
+    }
+
+    /// Use basic authentication
+    pub fn with_credentials(mut self, username: &str, password: Option<&str>) -> Self {
+        self.credentials = Some((username.to_string(), password.map(|p| p.to_string())));
+        self
+    }
+
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.credentials {
+            Some((username, password)) => request.basic_auth(username, password.as_ref()),
+            None => request,
+        }
+    }
+}
+
+/// ETag of the response, used as revision
+fn etag(response: &Response) -> Option<String> {
+    response
+        .headers()
+        .get(ETAG)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+}
+
+impl SyncBackend for WebDavBackend {
+    async fn download(&self) -> Result<Option<RemoteFile>, Error> {
+        let response = self
+            .authorize(self.client.get(self.url.clone()))
+            .send()
+            .await?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => {
+                let revision = etag(&response);
+                let data = response.bytes().await?.to_vec();
+                let revision = revision.unwrap_or_else(|| content_revision(&data));
+                Ok(Some(RemoteFile { data, revision }))
+            }
+            status => Err(Error::UnexpectedStatus(status.as_u16())),
+        }
+    }
+
+    async fn upload(&self, data: Vec<u8>, expected: Option<String>) -> Result<String, Error> {
+        let request = self.authorize(self.client.put(self.url.clone()));
+        let request = match &expected {
+            // the server doesn't know the revision, so it would always reject If-Match
+            Some(revision) if is_content_revision(revision) => {
+                let current = self.download().await?.map(|file| file.revision);
+                if current.as_ref() != Some(revision) {
+                    return Err(Error::RemoteChanged());
+                }
+                request
+            }
+            Some(revision) => request.header(IF_MATCH, revision),
+            None => request.header(IF_NONE_MATCH, "*"),
+        };
+        let fallback_revision = content_revision(&data);
+        let response = request.body(data).send().await?;
+
+        match response.status() {
+            StatusCode::PRECONDITION_FAILED => Err(Error::RemoteChanged()),
+            status if status.is_success() => Ok(etag(&response).unwrap_or(fallback_revision)),
+            status => Err(Error::UnexpectedStatus(status.as_u16())),
+        }
+    }
+}
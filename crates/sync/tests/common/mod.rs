@@ -0,0 +1,113 @@
+use std::sync::{Arc, Mutex};
+
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{
+        header::{AUTHORIZATION, ETAG, IF_MATCH, IF_NONE_MATCH},
+        HeaderMap, StatusCode,
+    },
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use parus_bookmark::Bookmarks;
+use url::Url;
+
+/// `user:pass` in base64
+const CREDENTIALS: &str = "Basic dXNlcjpwYXNz";
+
+/// Stored file and its version
+type StubFile = Arc<Mutex<Option<(Vec<u8>, u64)>>>;
+
+#[derive(Clone)]
+struct StubServer {
+    file: StubFile,
+    /// Whether ETag headers are sent, some servers don't
+    etags: bool,
+}
+
+fn etag(version: u64) -> String {
+    format!("\"v{version}\"")
+}
+
+fn is_authorized(headers: &HeaderMap) -> bool {
+    headers
+        .get(AUTHORIZATION)
+        .is_some_and(|value| value == CREDENTIALS)
+}
+
+async fn get_file(State(server): State<StubServer>, headers: HeaderMap) -> Response {
+    if !is_authorized(&headers) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    match server.file.lock().unwrap().as_ref() {
+        Some((data, _)) if !server.etags => data.clone().into_response(),
+        Some((data, version)) => ([(ETAG, etag(*version))], data.clone()).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+async fn put_file(State(server): State<StubServer>, headers: HeaderMap, body: Bytes) -> Response {
+    if !is_authorized(&headers) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    let mut file = server.file.lock().unwrap();
+    let current = file.as_ref().map(|(_, version)| etag(*version));
+    let if_match = headers.get(IF_MATCH).and_then(|v| v.to_str().ok());
+    let if_none_match = headers.get(IF_NONE_MATCH).and_then(|v| v.to_str().ok());
+    let satisfied = match (if_match, if_none_match) {
+        (Some(expected), _) => current.as_deref() == Some(expected),
+        (None, Some("*")) => current.is_none(),
+        _ => true,
+    };
+    if !satisfied {
+        return StatusCode::PRECONDITION_FAILED.into_response();
+    }
+
+    let version = file.as_ref().map(|(_, version)| version + 1).unwrap_or(1);
+    *file = Some((body.to_vec(), version));
+    if !server.etags {
+        return StatusCode::CREATED.into_response();
+    }
+    (StatusCode::CREATED, [(ETAG, etag(version))]).into_response()
+}
+
+/// Start a minimal WebDAV stub server supporting GET and conditional PUT of a single file,
+/// and return the URL of the file
+pub async fn start_webdav_server() -> anyhow::Result<Url> {
+    start_stub_server(true).await
+}
+
+/// Same as [`start_webdav_server`] but without ETag headers.
+/// If-Match is still checked against the hidden ETag.
+pub async fn start_webdav_server_without_etag() -> anyhow::Result<Url> {
+    start_stub_server(false).await
+}
+
+async fn start_stub_server(etags: bool) -> anyhow::Result<Url> {
+    let server = StubServer {
+        file: Arc::new(Mutex::new(None)),
+        etags,
+    };
+    let app = Router::new()
+        .route("/dav/bookmarks.json", get(get_file).put(put_file))
+        .with_state(server);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    Ok(Url::parse(&format!("http://{addr}/dav/bookmarks.json"))?)
+}
+
+/// Find the index of the first node with the title
+pub fn find_title(bookmarks: &Bookmarks, title: &str) -> Option<usize> {
+    let arena = bookmarks.arena();
+    bookmarks
+        .get_root_node_id()
+        .ok()?
+        .descendants(arena)
+        .find(|node_id| arena[*node_id].get().title == title)
+        .map(|node_id| node_id.into())
+}
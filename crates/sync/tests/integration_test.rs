@@ -0,0 +1,265 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use parus_bookmark::{crypto::EncryptionKey, Bookmarks};
+    use parus_sync::{
+        directory::DirectoryBackend, pending_changes, sync, webdav::WebDavBackend, BackendConfig,
+        ConflictStrategy, Error, SyncBackend,
+    };
+
+    use crate::common::{find_title, start_webdav_server, start_webdav_server_without_etag};
+
+    #[tokio::test]
+    async fn test_directory_backend() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let backend = DirectoryBackend::new(dir.path());
+        assert!(backend.download().await?.is_none());
+
+        let revision = backend.upload(b"first".to_vec(), None).await?;
+        let file = backend.download().await?.expect("file should exist");
+        assert_eq!(file.data, b"first");
+        assert_eq!(file.revision, revision);
+
+        // stale revision is rejected
+        assert!(matches!(
+            backend.upload(b"second".to_vec(), None).await,
+            Err(Error::RemoteChanged())
+        ));
+        backend.upload(b"second".to_vec(), Some(revision)).await?;
+        assert_eq!(backend.download().await?.unwrap().data, b"second");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_webdav_backend() -> anyhow::Result<()> {
+        let url = start_webdav_server().await?;
+        let backend = WebDavBackend::new(url.clone()).with_credentials("user", Some("pass"));
+        assert!(backend.download().await?.is_none());
+
+        let revision = backend.upload(b"first".to_vec(), None).await?;
+        let file = backend.download().await?.expect("file should exist");
+        assert_eq!(file.data, b"first");
+        assert_eq!(file.revision, revision);
+
+        assert!(matches!(
+            backend
+                .upload(b"second".to_vec(), Some("\"stale\"".to_string()))
+                .await,
+            Err(Error::RemoteChanged())
+        ));
+
+        // wrong credentials
+        let backend = WebDavBackend::new(url).with_credentials("user", Some("wrong"));
+        assert!(matches!(
+            backend.download().await,
+            Err(Error::UnexpectedStatus(401))
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_webdav_backend_without_etag() -> anyhow::Result<()> {
+        let url = start_webdav_server_without_etag().await?;
+        let backend = WebDavBackend::new(url).with_credentials("user", Some("pass"));
+
+        let revision = backend.upload(b"first".to_vec(), None).await?;
+        let file = backend.download().await?.expect("file should exist");
+        assert_eq!(file.revision, revision);
+
+        // the content revision is not sent as If-Match, which the server would reject
+        let revision = backend.upload(b"second".to_vec(), Some(revision)).await?;
+        assert_eq!(backend.download().await?.unwrap().data, b"second");
+        assert!(matches!(
+            backend.upload(b"third".to_vec(), Some(file.revision)).await,
+            Err(Error::RemoteChanged())
+        ));
+        backend.upload(b"third".to_vec(), Some(revision)).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sync_between_machines() -> anyhow::Result<()> {
+        let url = start_webdav_server().await?;
+        let backend = BackendConfig::WebDav {
+            url: url.to_string(),
+            username: Some("user".to_string()),
+            password: Some("pass".to_string()),
+        }
+        .build()?;
+
+        // machine A uploads its bookmarks
+        let mut a = Bookmarks::default();
+        a.append_bookmark_to_toolbar("Rust", "https://www.rust-lang.org/")?;
+        assert_eq!(pending_changes(None, &a).len(), 2);
+        let outcome = sync(&backend, &a, None, ConflictStrategy::PreferLocal, None).await?;
+        assert!(outcome.uploaded);
+        let mut a = outcome.bookmarks;
+        let a_base = Bookmarks::new(a.arena().clone());
+        assert!(pending_changes(Some(&a_base), &a).is_empty());
+
+        // machine B has never synced, its default toolbar is shared with A
+        let mut b = Bookmarks::default();
+        b.append_bookmark_to_toolbar("Tauri", "https://tauri.app/")?;
+        let outcome = sync(&backend, &b, None, ConflictStrategy::PreferLocal, None).await?;
+        assert!(outcome.uploaded);
+        assert!(outcome.conflicts.is_empty());
+        let b = outcome.bookmarks;
+        assert_eq!(b.count_bookmarks(), 2);
+        assert_eq!(b.get_toolbar_bookmarks().len(), 2);
+        let b_base = Bookmarks::new(b.arena().clone());
+
+        // A renames its bookmark and receives B's bookmark
+        let rust = find_title(&a, "Rust").expect("Rust should exist");
+        a.update_title(rust, "Rust Lang".to_string())?;
+        assert_eq!(pending_changes(Some(&a_base), &a).len(), 1);
+        let outcome = sync(
+            &backend,
+            &a,
+            Some(&a_base),
+            ConflictStrategy::PreferLocal,
+            None,
+        )
+        .await?;
+        assert!(outcome.uploaded);
+        let a = outcome.bookmarks;
+        assert!(find_title(&a, "Rust Lang").is_some());
+        assert!(find_title(&a, "Tauri").is_some());
+
+        // B only receives changes, nothing to upload
+        let outcome = sync(
+            &backend,
+            &b,
+            Some(&b_base),
+            ConflictStrategy::PreferLocal,
+            None,
+        )
+        .await?;
+        assert!(!outcome.uploaded);
+        assert!(find_title(&outcome.bookmarks, "Rust Lang").is_some());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sync_conflict_strategy() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let backend = DirectoryBackend::new(dir.path());
+
+        let mut local = Bookmarks::default();
+        local.append_bookmark_to_toolbar("Rust", "https://www.rust-lang.org/")?;
+        let base = sync(&backend, &local, None, ConflictStrategy::PreferLocal, None)
+            .await?
+            .bookmarks;
+
+        // another machine renames the bookmark
+        let mut remote = Bookmarks::new(base.arena().clone());
+        let index = find_title(&remote, "Rust").unwrap();
+        remote.update_title(index, "Remote".to_string())?;
+        sync(
+            &backend,
+            &remote,
+            Some(&base),
+            ConflictStrategy::PreferLocal,
+            None,
+        )
+        .await?;
+
+        let mut local = Bookmarks::new(base.arena().clone());
+        local.update_title(index, "Local".to_string())?;
+
+        let outcome = sync(
+            &backend,
+            &local,
+            Some(&base),
+            ConflictStrategy::PreferRemote,
+            None,
+        )
+        .await?;
+        assert_eq!(outcome.conflicts.len(), 1);
+        assert!(!outcome.uploaded);
+        assert!(find_title(&outcome.bookmarks, "Remote").is_some());
+
+        let outcome = sync(
+            &backend,
+            &local,
+            Some(&base),
+            ConflictStrategy::PreferLocal,
+            None,
+        )
+        .await?;
+        assert_eq!(outcome.conflicts.len(), 1);
+        assert!(outcome.uploaded);
+        assert!(find_title(&outcome.bookmarks, "Local").is_some());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sync_encrypted() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let backend = DirectoryBackend::new(dir.path());
+        let key = EncryptionKey::derive("passphrase")?;
+
+        let mut local = Bookmarks::default();
+        local.append_bookmark_to_toolbar("Rust", "https://www.rust-lang.org/")?;
+        // a plain remote file is encrypted even without changes
+        sync(&backend, &local, None, ConflictStrategy::PreferLocal, None).await?;
+        let outcome = sync(
+            &backend,
+            &local,
+            None,
+            ConflictStrategy::PreferLocal,
+            Some(&key),
+        )
+        .await?;
+        assert!(outcome.uploaded);
+
+        let data = backend.download().await?.unwrap().data;
+        assert!(!String::from_utf8_lossy(&data).contains("rust-lang.org"));
+        assert_eq!(
+            key.decrypt(&data)?,
+            outcome.bookmarks.to_json()?.into_bytes()
+        );
+
+        let outcome = sync(
+            &backend,
+            &local,
+            None,
+            ConflictStrategy::PreferLocal,
+            Some(&key),
+        )
+        .await?;
+        assert!(!outcome.uploaded);
+        assert!(find_title(&outcome.bookmarks, "Rust").is_some());
+
+        // the remote file can't be read without the same key
+        for other in [None, Some(&EncryptionKey::derive("passphrase")?)] {
+            assert!(matches!(
+                sync(&backend, &local, None, ConflictStrategy::PreferLocal, other).await,
+                Err(Error::RemoteEncrypted())
+            ));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_backend_config() -> anyhow::Result<()> {
+        let json = r#"{"type":"WebDav","url":"https://dav.example.com/bookmarks.json","username":null,"password":null}"#;
+        let config: BackendConfig = serde_json::from_str(json)?;
+        assert!(config.build().is_ok());
+
+        let config = BackendConfig::WebDav {
+            url: "not a url".to_string(),
+            username: None,
+            password: None,
+        };
+        assert!(config.build().is_err());
+
+        Ok(())
+    }
+}
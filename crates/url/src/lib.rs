@@ -0,0 +1,159 @@
+//! URL normalization shared by bookmarks and the favicon server.
+//!
+//! Hosts are stored in ASCII (punycode) as `Url::host_str` returns,
+//! shown in Unicode, and compared by keys which ignore `www.` and letter case.
+
+mod psl;
+
+use std::net::IpAddr;
+
+use url::Url;
+
+use psl::PublicSuffixList;
+
+/// Lower case ASCII (punycode) host without the trailing dot, None if the host is not valid
+pub fn ascii_host(host: &str) -> Option<String> {
+    let host = host.trim().trim_end_matches('.');
+    if host.is_empty() {
+        return None;
+    }
+    if is_ip_address(host) {
+        return Some(host.to_ascii_lowercase());
+    }
+    idna::domain_to_ascii(host)
+        .ok()
+        .filter(|host| !host.is_empty())
+}
+
+/// Host for display, IDN hosts are converted from punycode into Unicode
+pub fn display_host(host: &str) -> String {
+    if is_ip_address(host) {
+        return host.to_string();
+    }
+    match idna::domain_to_unicode(host) {
+        (unicode, Ok(())) => unicode,
+        (_, Err(_)) => host.to_string(),
+    }
+}
+
+/// Public suffix of the host (e.g. `co.uk` for `www.bbc.co.uk`), None for IP addresses
+pub fn public_suffix(host: &str) -> Option<String> {
+    let host = ascii_host(host)?;
+    if is_ip_address(&host) {
+        return None;
+    }
+    Some(PublicSuffixList::get().public_suffix(&host).to_string())
+}
+
+/// Registrable domain, the public suffix and one more label (e.g. `bbc.co.uk` for `www.bbc.co.uk`).
+/// None for IP addresses and hosts which are public suffixes themselves like `localhost` or `github.io`.
+pub fn registrable_domain(host: &str) -> Option<String> {
+    let host = ascii_host(host)?;
+    if is_ip_address(&host) {
+        return None;
+    }
+    let suffix = PublicSuffixList::get().public_suffix(&host);
+    let rest = host.strip_suffix(suffix)?.strip_suffix('.')?;
+    let label = rest.rsplit('.').next()?;
+    Some(format!("{label}.{suffix}"))
+}
+
+/// Key to compare hosts, `www.Example.com.` and `example.com` have the same key.
+/// Hosts which can't be converted into ASCII are compared in lower case.
+pub fn host_key(host: &str) -> String {
+    let Some(host) = ascii_host(host) else {
+        return host.to_lowercase();
+    };
+    match host.strip_prefix("www.") {
+        // keep `www` if the rest is a public suffix, e.g. `www.ck`
+        Some(rest) if registrable_domain(rest).is_some() => rest.to_string(),
+        _ => host,
+    }
+}
+
+/// Key to compare URLs, which ignores `http` and `https`, the default port,
+/// `www.`, the trailing slash of the path and the fragment.
+/// URLs without a host are compared as is without the fragment.
+pub fn url_key(url: &Url) -> String {
+    let Some(host) = url.host_str().filter(|host| !host.is_empty()) else {
+        let mut url = url.clone();
+        url.set_fragment(None);
+        return url.to_string();
+    };
+
+    let mut key = match url.scheme() {
+        "http" | "https" => String::new(),
+        scheme => format!("{scheme}://"),
+    };
+    key.push_str(&host_key(host));
+    if let Some(port) = url.port() {
+        key.push_str(&format!(":{port}"));
+    }
+    key.push_str(url.path().trim_end_matches('/'));
+    if let Some(query) = url.query().filter(|query| !query.is_empty()) {
+        key.push('?');
+        key.push_str(query);
+    }
+    key
+}
+
+fn is_ip_address(host: &str) -> bool {
+    host.trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+        .is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hosts() {
+        assert_eq!(
+            ascii_host("Bücher.example."),
+            Some("xn--bcher-kva.example".to_string())
+        );
+        assert_eq!(ascii_host(""), None);
+        assert_eq!(display_host("xn--bcher-kva.example"), "bücher.example");
+        assert_eq!(display_host("127.0.0.1"), "127.0.0.1");
+
+        assert_eq!(
+            registrable_domain("www.bbc.co.uk"),
+            Some("bbc.co.uk".to_string())
+        );
+        assert_eq!(registrable_domain("docs.rs"), Some("docs.rs".to_string()));
+        assert_eq!(
+            registrable_domain("user.github.io"),
+            Some("user.github.io".to_string())
+        );
+        assert_eq!(registrable_domain("github.io"), None);
+        assert_eq!(registrable_domain("localhost"), None);
+        assert_eq!(registrable_domain("192.168.0.1"), None);
+        assert_eq!(public_suffix("www.bbc.co.uk"), Some("co.uk".to_string()));
+    }
+
+    #[test]
+    fn test_keys() {
+        assert_eq!(host_key("www.GitHub.com"), "github.com");
+        assert_eq!(host_key("github.com."), "github.com");
+        assert_eq!(host_key("www.bücher.example"), "xn--bcher-kva.example");
+        assert_eq!(host_key("www.ck"), "www.ck");
+
+        let key = |url: &str| url_key(&Url::parse(url).unwrap());
+        assert_eq!(
+            key("http://www.example.com/docs/#intro"),
+            key("https://example.com:443/docs")
+        );
+        assert_ne!(
+            key("https://example.com/docs"),
+            key("https://example.com/docs?a=1")
+        );
+        assert_ne!(
+            key("https://example.com:8080/"),
+            key("https://example.com/")
+        );
+        assert_eq!(key("about:blank#top"), "about:blank");
+        assert_eq!(key("file:///usr/share/doc/"), "file:///usr/share/doc/");
+    }
+}
@@ -0,0 +1,95 @@
+use std::{collections::HashSet, sync::OnceLock};
+
+/// https://publicsuffix.org/list/public_suffix_list.dat
+/// Bundled so that domains can be computed offline, update the file to follow new suffixes.
+const PUBLIC_SUFFIX_LIST: &str = include_str!("../data/public_suffix_list.dat");
+
+static LIST: OnceLock<PublicSuffixList> = OnceLock::new();
+
+/// Rules of the public suffix list, all in ASCII (punycode)
+/// https://github.com/publicsuffix/list/wiki/Format
+pub(crate) struct PublicSuffixList {
+    /// `com`, `co.uk`
+    rules: HashSet<String>,
+    /// `*.ck` is stored as `ck`
+    wildcards: HashSet<String>,
+    /// `!www.ck` is stored as `www.ck`
+    exceptions: HashSet<String>,
+}
+
+impl PublicSuffixList {
+    pub(crate) fn get() -> &'static Self {
+        LIST.get_or_init(|| Self::parse(PUBLIC_SUFFIX_LIST))
+    }
+
+    fn parse(data: &str) -> Self {
+        let mut list = Self {
+            rules: HashSet::new(),
+            wildcards: HashSet::new(),
+            exceptions: HashSet::new(),
+        };
+        for line in data.lines() {
+            // only the first word of a line is a rule
+            let Some(rule) = line.split_whitespace().next() else {
+                continue;
+            };
+            if rule.starts_with("//") {
+                continue;
+            }
+            let (set, rule) = if let Some(rule) = rule.strip_prefix('!') {
+                (&mut list.exceptions, rule)
+            } else if let Some(rule) = rule.strip_prefix("*.") {
+                (&mut list.wildcards, rule)
+            } else {
+                (&mut list.rules, rule)
+            };
+            if let Ok(rule) = idna::domain_to_ascii(rule) {
+                set.insert(rule);
+            }
+        }
+        list
+    }
+
+    /// Public suffix of an ASCII host in lower case.
+    /// A host which matches no rule uses its last label as the suffix, as the list defines.
+    pub(crate) fn public_suffix<'a>(&self, host: &'a str) -> &'a str {
+        let mut start = 0;
+        loop {
+            let candidate = &host[start..];
+            let parent = candidate.split_once('.').map(|(_, parent)| parent);
+            if self.exceptions.contains(candidate) {
+                // the exception itself is registrable
+                return parent.unwrap_or(candidate);
+            }
+            if self.rules.contains(candidate)
+                || parent.is_some_and(|parent| self.wildcards.contains(parent))
+            {
+                return candidate;
+            }
+            match candidate.find('.') {
+                Some(dot) => start += dot + 1,
+                None => return candidate,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_public_suffix() {
+        let list = PublicSuffixList::get();
+        assert_eq!(list.public_suffix("docs.rs"), "rs");
+        assert_eq!(list.public_suffix("www.bbc.co.uk"), "co.uk");
+        assert_eq!(list.public_suffix("user.github.io"), "github.io");
+        // wildcard and exception rules
+        assert_eq!(list.public_suffix("foo.bar.ck"), "bar.ck");
+        assert_eq!(list.public_suffix("www.ck"), "ck");
+        // not in the list
+        assert_eq!(list.public_suffix("example.unknowntld"), "unknowntld");
+        // IDN rules are stored as punycode
+        assert_eq!(list.public_suffix("example.xn--55qx5d.cn"), "xn--55qx5d.cn");
+    }
+}